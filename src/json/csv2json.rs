@@ -3,30 +3,30 @@ use std::{io::Write, fs::{File, read_dir, metadata, read_to_string}, path::Path,
 #[derive(Debug)]
 struct Entry {
     date: String,
-    open: f32,
-    high: f32,
-    low: f32,
-    close: f32,
-    volume: u32,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: u64,
 }
 
 impl Entry {
     fn get_date(&self) -> String {
         return self.date.to_string();
     }
-    fn get_open(&self) -> f32 {
+    fn get_open(&self) -> f64 {
         return self.open;
     }
-    fn get_high(&self) -> f32 {
+    fn get_high(&self) -> f64 {
         return self.high;
     }
-    fn get_low(&self) -> f32 {
+    fn get_low(&self) -> f64 {
         return self.low;
     }
-    fn get_close(&self) -> f32 {
+    fn get_close(&self) -> f64 {
         return self.close;
     }
-    fn get_volume(&self) -> u32 {
+    fn get_volume(&self) -> u64 {
         return self.volume;
     }
     fn to_string(&self) -> String {
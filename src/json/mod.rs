@@ -1,3 +1,4 @@
 pub mod serialize;
 pub mod sanitize;
-pub mod pretty_print;
\ No newline at end of file
+pub mod pretty_print;
+pub mod parse;
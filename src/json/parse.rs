@@ -0,0 +1,347 @@
+use std::fmt;
+
+use crate::json::serialize::{JSON, JSONObject};
+
+/// Error returned when a JSON document is not well formed. `position` is
+/// the byte offset of the problem in the input.
+///
+/// - `UnexpectedEnd`: the input ends before the document is complete
+/// - `UnexpectedCharacter`: a character that cannot start or continue the
+///   current value
+/// - `InvalidNumber`: a number that does not follow the JSON grammar
+/// - `InvalidEscape`: an unknown escape sequence or invalid `\u` code point
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum JsonError {
+    UnexpectedEnd,
+    UnexpectedCharacter {
+        position: usize,
+        character: char,
+    },
+    InvalidNumber {
+        position: usize,
+    },
+    InvalidEscape {
+        position: usize,
+    },
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            JsonError::UnexpectedEnd => write!(f, "Unexpected end of JSON input."),
+            JsonError::UnexpectedCharacter { position, character } => {
+                write!(f, "Unexpected character '{}' at position {}.", character, position)
+            },
+            JsonError::InvalidNumber { position } => write!(f, "Invalid number at position {}.", position),
+            JsonError::InvalidEscape { position } => write!(f, "Invalid escape sequence at position {}.", position),
+        };
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+/// Parses a complete JSON document following RFC 8259. Integers that fit
+/// in an `i64` become `JSONObject::Number`, every other number becomes
+/// `JSONObject::Float`. Strings are unescaped. Object keys keep their
+/// order.
+///
+/// # Arguments
+///
+/// * `string` - The JSON document
+///
+/// ### Examples
+/// ```
+/// let value = parse::parse("{\"symbol\": \"AAPL\", \"latestPrice\": 134.32, \"volume\": [1, 2]}").unwrap();
+/// let object = value.as_object().unwrap();
+/// assert_eq!(object.get("symbol").and_then(|v| v.as_str()), Some("AAPL"));
+/// assert_eq!(object.get("latestPrice").and_then(|v| v.as_f64()), Some(134.32));
+/// ```
+///
+/// #### Resources
+/// - [RFC 8259](https://datatracker.ietf.org/doc/html/rfc8259)
+pub fn parse(string: &str) -> Result<JSONObject, JsonError> {
+    let mut parser = Parser { bytes: string.as_bytes(), source: string, position: 0 };
+    let value = parser.value()?;
+    parser.whitespace();
+    if let Some(c) = parser.peek_char() {
+        return Err(JsonError::UnexpectedCharacter { position: parser.position, character: c });
+    }
+    return Ok(value);
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    source: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        return self.bytes.get(self.position).copied();
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        return self.source[self.position..].chars().next();
+    }
+
+    fn unexpected(&self) -> JsonError {
+        return match self.peek_char() {
+            Some(c) => JsonError::UnexpectedCharacter { position: self.position, character: c },
+            None => JsonError::UnexpectedEnd,
+        };
+    }
+
+    fn whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), JsonError> {
+        if self.peek() != Some(byte) {
+            return Err(self.unexpected());
+        }
+        self.position += 1;
+        return Ok(());
+    }
+
+    fn literal(&mut self, literal: &str, value: JSONObject) -> Result<JSONObject, JsonError> {
+        for &b in literal.as_bytes() {
+            self.expect(b)?;
+        }
+        return Ok(value);
+    }
+
+    fn value(&mut self) -> Result<JSONObject, JsonError> {
+        self.whitespace();
+        return match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => Ok(JSONObject::String(self.string()?)),
+            Some(b't') => self.literal("true", JSONObject::Boolean(true)),
+            Some(b'f') => self.literal("false", JSONObject::Boolean(false)),
+            Some(b'n') => self.literal("null", JSONObject::Null()),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            _ => Err(self.unexpected()),
+        };
+    }
+
+    fn object(&mut self) -> Result<JSONObject, JsonError> {
+        self.expect(b'{')?;
+        let mut object = JSON::new();
+        self.whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(JSONObject::Object(object));
+        }
+        loop {
+            self.whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.unexpected());
+            }
+            let key = self.string()?;
+            self.whitespace();
+            self.expect(b':')?;
+            let value = self.value()?;
+            object.push(&key, value);
+            self.whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(JSONObject::Object(object));
+                },
+                _ => return Err(self.unexpected()),
+            };
+        }
+    }
+
+    fn array(&mut self) -> Result<JSONObject, JsonError> {
+        self.expect(b'[')?;
+        let mut values = Vec::new();
+        self.whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(JSONObject::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(JSONObject::Array(values));
+                },
+                _ => return Err(self.unexpected()),
+            };
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let start = self.position;
+        let digits = self.source.get(start..start + 4).ok_or(JsonError::UnexpectedEnd)?;
+        let code = u32::from_str_radix(digits, 16).map_err(|_| JsonError::InvalidEscape { position: start })?;
+        self.position += 4;
+        return Ok(code);
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect(b'"')?;
+        let mut string = "".to_owned();
+        loop {
+            let start = self.position;
+            // Copy everything up to the next quote or escape at once
+            while let Some(b) = self.peek() {
+                if b == b'"' || b == b'\\' || b < 0x20 {
+                    break;
+                }
+                self.position += 1;
+            }
+            string.push_str(&self.source[start..self.position]);
+            match self.peek() {
+                Some(b'"') => {
+                    self.position += 1;
+                    return Ok(string);
+                },
+                Some(b'\\') => {
+                    let escape = self.position;
+                    self.position += 1;
+                    let c = match self.peek() {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            self.position += 1;
+                            let mut code = self.hex4()?;
+                            // Surrogate pairs encode code points above U+FFFF
+                            if (0xD800..0xDC00).contains(&code) {
+                                if self.source.get(self.position..self.position + 2) != Some("\\u") {
+                                    return Err(JsonError::InvalidEscape { position: escape });
+                                }
+                                self.position += 2;
+                                let low = self.hex4()?;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return Err(JsonError::InvalidEscape { position: escape });
+                                }
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                            }
+                            string.push(char::from_u32(code).ok_or(JsonError::InvalidEscape { position: escape })?);
+                            continue;
+                        },
+                        Some(_) => return Err(JsonError::InvalidEscape { position: escape }),
+                        None => return Err(JsonError::UnexpectedEnd),
+                    };
+                    string.push(c);
+                    self.position += 1;
+                },
+                _ => return Err(self.unexpected()),
+            };
+        }
+    }
+
+    fn digits(&mut self) -> usize {
+        let start = self.position;
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.position += 1;
+        }
+        return self.position - start;
+    }
+
+    fn number(&mut self) -> Result<JSONObject, JsonError> {
+        let start = self.position;
+        let invalid = JsonError::InvalidNumber { position: start };
+        if self.peek() == Some(b'-') {
+            self.position += 1;
+        }
+        let integer_start = self.position;
+        let integer_digits = self.digits();
+        if integer_digits == 0 || (integer_digits > 1 && self.bytes[integer_start] == b'0') {
+            return Err(invalid);
+        }
+        let mut is_integer = true;
+        if self.peek() == Some(b'.') {
+            self.position += 1;
+            if self.digits() == 0 {
+                return Err(invalid);
+            }
+            is_integer = false;
+        }
+        if let Some(b'e' | b'E') = self.peek() {
+            self.position += 1;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.position += 1;
+            }
+            if self.digits() == 0 {
+                return Err(invalid);
+            }
+            is_integer = false;
+        }
+
+        let text = &self.source[start..self.position];
+        if is_integer {
+            if let Ok(n) = text.parse::<i64>() {
+                return Ok(JSONObject::Number(n));
+            }
+        }
+        return match text.parse::<f64>() {
+            Ok(f) => Ok(JSONObject::Float(f)),
+            Err(_) => Err(invalid),
+        };
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_scalars() {
+        assert_eq!(parse("true"), Ok(JSONObject::Boolean(true)));
+        assert_eq!(parse(" null "), Ok(JSONObject::Null()));
+        assert_eq!(parse("-12"), Ok(JSONObject::Number(-12)));
+        assert_eq!(parse("134.32"), Ok(JSONObject::Float(134.32)));
+        assert_eq!(parse("1.5e3"), Ok(JSONObject::Float(1500.0)));
+        assert_eq!(parse("2427490926592"), Ok(JSONObject::Number(2427490926592)));
+        assert_eq!(parse("99999999999999999999"), Ok(JSONObject::Float(1e20)));
+    }
+
+    #[test]
+    fn test_parse_strings() {
+        assert_eq!(parse("\"a \\\"quoted\\\" \\\\ line\\n\""), Ok(JSONObject::String("a \"quoted\" \\ line\n".to_string())));
+        assert_eq!(parse("\"caf\\u00e9 \\ud83d\\ude00 é\""), Ok(JSONObject::String("café 😀 é".to_string())));
+    }
+
+    #[test]
+    fn test_parse_nested() {
+        let value = parse("{\"symbol\": \"AAPL\", \"chart\": [{\"close\": 134.32, \"volume\": 1}, {}], \"empty\": []}").unwrap();
+        let object = value.as_object().unwrap();
+        assert_eq!(object.keys(), vec!["symbol", "chart", "empty"]);
+        let chart = object.get("chart").and_then(|v| v.as_array()).unwrap();
+        assert_eq!(chart.len(), 2);
+        assert_eq!(chart[0].as_object().unwrap().get("close").and_then(|v| v.as_f64()), Some(134.32));
+        assert_eq!(chart[0].as_object().unwrap().get("volume").and_then(|v| v.as_i64()), Some(1));
+        assert_eq!(object.get("empty").and_then(|v| v.as_array()).map(|a| a.len()), Some(0));
+        assert_eq!(value.stringify(), "{\"symbol\":\"AAPL\",\"chart\":[{\"close\":134.32,\"volume\":1},{}],\"empty\":[]}");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse(""), Err(JsonError::UnexpectedEnd));
+        assert_eq!(parse("{\"a\": 1"), Err(JsonError::UnexpectedEnd));
+        assert_eq!(parse("{\"a\": 1,}"), Err(JsonError::UnexpectedCharacter { position: 8, character: '}' }));
+        assert_eq!(parse("[1] x"), Err(JsonError::UnexpectedCharacter { position: 4, character: 'x' }));
+        assert_eq!(parse("01"), Err(JsonError::InvalidNumber { position: 0 }));
+        assert_eq!(parse("-.5"), Err(JsonError::InvalidNumber { position: 0 }));
+        assert_eq!(parse("\"\\x\""), Err(JsonError::InvalidEscape { position: 1 }));
+        assert_eq!(parse("tru").unwrap_err().to_string(), "Unexpected end of JSON input.");
+    }
+}
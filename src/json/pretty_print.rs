@@ -20,6 +20,7 @@
 /// ```
 pub fn pretty_print(string: &str) -> String {
     let mut in_str = false;
+    let mut escaped = false;
     let mut level = 0;
     let mut new_str = "".to_owned();
     let c_array: Vec<char> = string.chars().collect();
@@ -50,12 +51,20 @@ pub fn pretty_print(string: &str) -> String {
                 },
                 _ => new_str.push_str(&format!("{}", c))
             };
+        } else if escaped {
+            // The character after a backslash never ends the string
+            escaped = false;
+            new_str.push_str(&format!("{}", c));
         } else {
             match c {
                 '"' => {
                     in_str = false;
                     new_str.push_str(&format!("{}", c));
                 },
+                '\\' => {
+                    escaped = true;
+                    new_str.push_str(&format!("{}", c));
+                },
                 _ => new_str.push_str(&format!("{}", c))
             };
         }
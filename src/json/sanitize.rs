@@ -20,6 +20,7 @@ use regex::Regex;
 ///     json_str
 /// );
 /// ```
+#[allow(dead_code)]
 pub fn sanitize(string: &str) -> String {
     let re = Regex::new(r#"(?P<y>[\{\[\}\]:,]|"[^"]+"|\S+)"#).unwrap();
     let mut sanitized_string = "".to_owned();
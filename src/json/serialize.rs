@@ -1,10 +1,12 @@
 use regex::Regex;
 
 #[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
 pub enum JSONObject {
     String(String),
-    Number(i32),
-    Float(f32),
+    Number(i64),
+    Float(f64),
     Boolean(bool),
     Null(),
     Object(JSON),
@@ -12,19 +14,87 @@ pub enum JSONObject {
 }
 
 #[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
 pub struct JSON {
     __root__: Vec<(String, JSONObject)>,
 }
 
 
+#[allow(dead_code)]
+impl JSONObject {
+    pub fn as_str(&self) -> Option<&str> {
+        return match self { JSONObject::String(s) => Some(s), _ => None };
+    }
+
+    /// Returns the value of a `Number` or `Float` as an `f64`.
+    pub fn as_f64(&self) -> Option<f64> {
+        return match self {
+            JSONObject::Number(n) => Some(*n as f64),
+            JSONObject::Float(f) => Some(*f),
+            _ => None,
+        };
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        return match self { JSONObject::Number(n) => Some(*n), _ => None };
+    }
+
+    /// Returns the value of a non-negative `Number`, or of a `Float` with
+    /// no fractional part, as a `u64`.
+    pub fn as_u64(&self) -> Option<u64> {
+        return match self {
+            JSONObject::Number(n) if *n >= 0 => Some(*n as u64),
+            JSONObject::Float(f) if *f >= 0.0 && f.fract() == 0.0 && *f < u64::MAX as f64 => Some(*f as u64),
+            _ => None,
+        };
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        return match self { JSONObject::Boolean(b) => Some(*b), _ => None };
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JSONObject>> {
+        return match self { JSONObject::Array(a) => Some(a), _ => None };
+    }
+
+    pub fn as_object(&self) -> Option<&JSON> {
+        return match self { JSONObject::Object(o) => Some(o), _ => None };
+    }
+
+    pub fn is_null(&self) -> bool {
+        return matches!(self, JSONObject::Null());
+    }
+
+    /// Converts the value into a compact JSON string. Non-finite floats
+    /// are written as `null`.
+    pub fn stringify(&self) -> String {
+        return match self {
+            JSONObject::String(s) => format!("\"{}\"", escape(s)),
+            JSONObject::Number(n) => n.to_string(),
+            JSONObject::Float(f) => if f.is_finite() { f.to_string() } else { "null".to_string() },
+            JSONObject::Boolean(b) => b.to_string(),
+            JSONObject::Null() => "null".to_string(),
+            JSONObject::Object(o) => o.stringify(),
+            JSONObject::Array(a) => {
+                let values: Vec<String> = a.iter().map(|el| el.stringify()).collect();
+                format!("[{}]", values.join(","))
+            },
+        };
+    }
+}
+
+
+#[allow(dead_code)]
 impl JSON {
-    fn new() -> Self {
+    pub fn new() -> Self {
         return Self { __root__: Vec::new() };
     }
 
+    #[allow(clippy::if_same_then_else)]
     fn _convert_kv<'a>(&mut self, key: &'a str, value: &'a str) -> (&'a str, JSONObject) {
         let re_object = Regex::new(r"^\{(?P<s>.*)\}$").unwrap();
-        let re_array = Regex::new(r"^\[(?P<s>.*)\]$").unwrap();
         let re_number = Regex::new(r"^-?\d+$").unwrap();
         let re_float = Regex::new(r"^-?[\d\.]+$").unwrap();
         let re_string = Regex::new(r#"^".*"$"#).unwrap();
@@ -40,11 +110,11 @@ impl JSON {
         if re_string.is_match(value) {
             return (key, JSONObject::String(value.to_string()));
         } else if re_boolean.is_match(value) {
-            return (key, JSONObject::Boolean(match value { "true" => true, _ => false }));
+            return (key, JSONObject::Boolean(value == "true"));
         } else if re_number.is_match(value) {
-            return (key, JSONObject::Number(value.parse::<i32>().unwrap()));
+            return (key, JSONObject::Number(value.parse::<i64>().unwrap()));
         } else if re_float.is_match(value) {
-            return (key, JSONObject::Float(value.parse::<f32>().unwrap()));
+            return (key, JSONObject::Float(value.parse::<f64>().unwrap()));
         } else if re_null.is_match(value) {
             return (key, JSONObject::Null());
         } else {
@@ -56,11 +126,6 @@ impl JSON {
 
         let re_object = Regex::new(r"^\{(?P<s>.*)\}$").unwrap();
         let re_array = Regex::new(r"^\[(?P<s>.*)\]$").unwrap();
-        let re_number = Regex::new(r"^-?\d+$").unwrap();
-        let re_float = Regex::new(r"^-?[\d\.]+$").unwrap();
-        let re_string = Regex::new(r#"^".*"$"#).unwrap();
-        let re_boolean = Regex::new(r"^(true|false)$").unwrap();
-        let re_null = Regex::new(r"^(null)$").unwrap();
         let re_pv = Regex::new(r#""([^"]+)":("[^"]*"|\d+|true|false|(?:\{.*\})|(?:\[.*\])),"#).unwrap();
 
         if re_object.is_match(string) {
@@ -109,11 +174,61 @@ impl JSON {
         return Err(format!("Could not find key: `{}`", key));
     }
 
+    /// Returns the value for `key`, or `None` if the key is missing.
+    pub fn get(&self, key: &str) -> Option<&JSONObject> {
+        return self.__root__.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+    }
+
+    /// Returns the keys in insertion order.
+    pub fn keys(&self) -> Vec<&str> {
+        return self.__root__.iter().map(|(k, _)| k.as_str()).collect();
+    }
+
+    #[allow(clippy::inherent_to_string)]
     pub fn to_string(&self) -> String {
         return format!("{:?}", self.__root__);
     }
+
+    /// Converts the object into a compact JSON string. Keys are written
+    /// in insertion order.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// let mut json = JSON::new();
+    /// json.push("property1", JSONObject::String("value 1".to_string()));
+    /// json.push("property2", JSONObject::Number(2));
+    /// 
+    /// assert_eq!(json.stringify(), "{\"property1\":\"value 1\",\"property2\":2}");
+    /// ```
+    pub fn stringify(&self) -> String {
+        let values: Vec<String> = self.__root__.iter()
+            .map(|(key, value)| format!("\"{}\":{}", escape(key), value.stringify()))
+            .collect();
+        return format!("{{{}}}", values.join(","));
+    }
 }
 
+/// Escapes quotes, backslashes and control characters for use inside a
+/// JSON string. Control characters without a short escape are written as
+/// `\u00XX` (RFC 8259, section 7).
+fn escape(string: &str) -> String {
+    let mut escaped = "".to_owned();
+    for c in string.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => escaped.push(c),
+        };
+    }
+    return escaped;
+}
+
+#[allow(dead_code)]
 pub fn serialize(string: &str) -> JSON {
     let mut json = JSON::new();
     json.parse(string);
@@ -144,6 +259,7 @@ pub fn serialize(string: &str) -> JSON {
 ///     json_str
 /// );
 /// ```
+#[allow(dead_code)]
 pub fn sanitize(string: &str) -> String {
     let re = Regex::new(r#"(?P<y>[\{\[\}\]:,]|"[^"]+"|\S+)"#).unwrap();
     let mut sanitized_string = "".to_owned();
@@ -176,8 +292,10 @@ pub fn sanitize(string: &str) -> String {
 ///     json_str
 /// );
 /// ```
+#[allow(dead_code)]
 pub fn pretty_print(string: &str) -> String {
     let mut in_str = false;
+    let mut escaped = false;
     let mut level = 0;
     let mut new_str = "".to_owned();
     let c_array: Vec<char> = string.chars().collect();
@@ -208,16 +326,37 @@ pub fn pretty_print(string: &str) -> String {
                 },
                 _ => new_str.push_str(&format!("{}", c))
             };
+        } else if escaped {
+            // The character after a backslash never ends the string
+            escaped = false;
+            new_str.push_str(&format!("{}", c));
         } else {
             match c {
                 '"' => {
                     in_str = false;
                     new_str.push_str(&format!("{}", c));
                 },
+                '\\' => {
+                    escaped = true;
+                    new_str.push_str(&format!("{}", c));
+                },
                 _ => new_str.push_str(&format!("{}", c))
             };
         }
     }
 
     return new_str.to_string();
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stringify_escapes_control_characters() {
+        let mut json = JSON::new();
+        json.push("text", JSONObject::String("a\"b\\c\nd\u{0}e\u{1b}f\u{1f}".to_string()));
+        assert_eq!(json.stringify(), "{\"text\":\"a\\\"b\\\\c\\nd\\u0000e\\u001bf\\u001f\"}");
+    }
+}
@@ -1,51 +1,65 @@
-use std::{path::Path};
+// House style, used throughout the code base:
+// - functions end with an explicit `return x;`
+#![allow(clippy::needless_return)]
+// - structs are initialized with `field: field`
+#![allow(clippy::redundant_field_names)]
+// - tests assert booleans with `assert_eq!(x, true)`
+#![allow(clippy::bool_assert_comparison)]
 
 mod stock;
-// mod json;
+mod json;
 
-fn main() {
-    println!("Hello, world!");
+use std::path::Path;
 
-    stock::iex::quote("aapl");
+use stock::provider::{CsvProvider, DataProvider, DateRange, Interval, IexProvider};
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(|arg| arg.as_str()) == Some("update") {
+        return update(&args[1..]).await;
+    }
+
+    // Use the IEX API when a token is set, the bundled CSV files otherwise
+    match stock::iex::Client::from_env() {
+        Ok(client) => {
+            let last_month = DateRange::since(stock::datetime::DateTime::now().sub_days(30));
+            show("aapl", &IexProvider::new(client), &last_month).await
+        },
+        Err(_) => show("aapl", &CsvProvider::new(Path::new("./src/assets")), &DateRange::all()).await,
+    };
 
     // let mut s = stock::Stock::new("aapl");
     // match s.load_data(Path::new("./src/assets/contemporary/aapl.csv")) {Ok(b) => b, Err(error) => panic!("{}", error)};
     // match s.load_historical_data(Path::new("./src/assets/historical/aapl.csv")) {Ok(b) => b, Err(error) => panic!("{}", error)};
     // s.backtest();
 
-    // let data: Vec<&stock::historical::HistoricalData> = s.query_historical_data(
+    // let series = s.get_price_series();
+    // let data = series.slice(
     //     &stock::datetime::DateTime::new("1/1/2021 00:00:00"),
     //     &stock::datetime::DateTime::new("12/31/2021 00:00:00")
     // );
 
-    // let prices: Vec<f32> = data.iter().map(|el| el.get_close()).collect();
     // const NUM_DAYS: usize = 50;
-    // let smas = stock::ta::sma::run(prices, NUM_DAYS);
+    // let smas = stock::ta::sma::run(data.get_closes(), NUM_DAYS);
     // println!("smas {:?}\n\n", smas);
 
-    // let prices: Vec<f32> = data.iter().map(|el| el.get_close()).collect();
-    // let emas = stock::ta::ema::run(prices, NUM_DAYS);
+    // let emas = stock::ta::ema::run(data.get_closes(), NUM_DAYS);
     // println!("emas {:?}\n\n", emas);
 
-    // let prices: Vec<f32> = data.iter().map(|el| el.get_close()).collect();
-    // let macds = stock::ta::macd::run(prices);
+    // let macds = stock::ta::macd::run(data.get_closes());
     // println!("macds {:?}\n\n", macds);
 
-    // let prices: Vec<f32> = data.iter().map(|el| el.get_close()).collect();
-    // let rsis = stock::ta::rsi::run(prices);
+    // let rsis = stock::ta::rsi::run(data.get_closes());
     // println!("rsis {:?}\n\n", rsis);
 
-    // let prices: Vec<f32> = data.iter().map(|el| el.get_close()).collect();
-    // let volume: Vec<u32> = data.iter().map(|el| el.get_volume()).collect();
-    // let obvs = stock::ta::obv::run(prices, volume);
+    // let obvs = stock::ta::obv::run(data.get_closes(), data.get_volumes());
     // println!("obvs {:?}\n\n", obvs);
 
-    // let prices: Vec<(f32, f32, f32)> = data.iter().map(|el| (el.get_close(), el.get_low(), el.get_high())).collect();
-    // let stoch_osc = stock::ta::stochastic_oscillator::run(prices);
+    // let stoch_osc = stock::ta::stochastic_oscillator::run(data.get_closes(), data.get_lows(), data.get_highs());
     // println!("stochastic_oscillator {:?}\n\n", stoch_osc);
 
-    // let prices: Vec<f32> = data.iter().map(|el| el.get_close()).collect();
-    // let boll_bands = stock::ta::bollinger_band::run(prices);
+    // let boll_bands = stock::ta::bollinger_band::run(data.get_closes());
     // println!("bollinger_band {:?}\n\n", boll_bands);
 
 
@@ -83,3 +97,49 @@ fn main() {
     // };
     // println!("{:?}", tmp);
 }
+
+/// Prints the fundamentals and latest bar of `ticker` from `provider`.
+async fn show<P: DataProvider>(ticker: &str, provider: &P, range: &DateRange) {
+    let mut s = stock::Stock::new(ticker);
+    match s.load_from(provider, range, Interval::Daily).await {
+        Ok(_) => (),
+        Err(error) => return eprintln!("{}", error),
+    };
+    println!("{} ({}) market cap {}", s.get_name(), s.get_ticker(), s.get_market_cap());
+    if let Some(bar) = s.get_historical_data().last() {
+        println!("{}", bar.to_string());
+    }
+}
+
+/// `update [TICKER...]`: appends new bars to `src/assets` from the IEX API
+/// for the given tickers, or every bundled ticker.
+async fn update(tickers: &[String]) {
+    let client = match stock::iex::Client::from_env() {
+        Ok(client) => client,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        },
+    };
+    let tickers: Vec<&str> = match tickers.is_empty() {
+        true => stock::update::TICKERS.to_vec(),
+        false => tickers.iter().map(|ticker| ticker.as_str()).collect(),
+    };
+    let mut failed = false;
+    for (ticker, result) in stock::update::update_all(&IexProvider::new(client), &tickers, Path::new("./src/assets")).await {
+        match result {
+            Ok(update) => match update.get_last_date() {
+                Some(date) => println!("{}: appended {} bars, up to date through {}", ticker, update.get_appended(), date.to_string()),
+                None => println!("{}: no bars", ticker),
+            },
+            Err(error) => {
+                failed = true;
+                eprintln!("{}: {}", ticker, error);
+            },
+        };
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
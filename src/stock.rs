@@ -1,10 +1,22 @@
-use std::{path::Path, fs::{read_to_string}};
+use std::{path::Path};
+
+use crate::json::serialize::JSONObject;
 
 pub mod historical;
 pub mod backtest;
 pub mod datetime;
+pub mod exchange;
+pub mod calendar;
+pub mod csv;
+pub mod load;
+pub mod cache;
+pub mod universe;
+pub mod series;
+pub mod actions;
 pub mod ta;
 pub mod iex;
+pub mod provider;
+pub mod update;
 
 #[derive(Debug)]
 pub struct Stock {
@@ -12,11 +24,15 @@ pub struct Stock {
     security_type: String,
     name: String,
     market_cap: u64,
-    pe_ratio: f32,
-    eps: f32,
-    high52: f32,
-    low52: f32,
+    pe_ratio: f64,
+    eps: f64,
+    high52: f64,
+    low52: f64,
+    summary: String,
     historical_data: Vec<historical::HistoricalData>,
+    price_series: series::PriceSeries,
+    splits: Vec<actions::Split>,
+    dividends: Vec<actions::Dividend>,
 }
 
 #[allow(dead_code)]
@@ -31,7 +47,11 @@ impl Stock {
             eps: 0.0,
             high52: 0.0,
             low52: 0.0,
-            historical_data: Vec::new()
+            summary: "".to_string(),
+            historical_data: Vec::new(),
+            price_series: series::PriceSeries::new(),
+            splits: Vec::new(),
+            dividends: Vec::new(),
         }
     }
 
@@ -47,130 +67,196 @@ impl Stock {
     pub fn get_market_cap(&self) -> u64 {return self.market_cap;}
     pub fn set_market_cap(&mut self, market_cap: u64) {self.market_cap = market_cap;}
 
-    pub fn get_pe_ratio(&self) -> f32 {return self.pe_ratio;}
-    pub fn set_pe_ratio(&mut self, pe_ratio: f32) {self.pe_ratio = pe_ratio;}
+    pub fn get_pe_ratio(&self) -> f64 {return self.pe_ratio;}
+    pub fn set_pe_ratio(&mut self, pe_ratio: f64) {self.pe_ratio = pe_ratio;}
+
+    pub fn get_eps(&self) -> f64 {return self.eps;}
+    pub fn set_eps(&mut self, eps: f64) {self.eps = eps;}
 
-    pub fn get_eps(&self) -> f32 {return self.eps;}
-    pub fn set_eps(&mut self, eps: f32) {self.eps = eps;}
+    pub fn get_high52(&self) -> f64 {return self.high52;}
+    pub fn set_high52(&mut self, high52: f64) {self.high52 = high52;}
 
-    pub fn get_high52(&self) -> f32 {return self.high52;}
-    pub fn set_high52(&mut self, high52: f32) {self.high52 = high52;}
+    pub fn get_low52(&self) -> f64 {return self.low52;}
+    pub fn set_low52(&mut self, low52: f64) {self.low52 = low52;}
 
-    pub fn get_low52(&self) -> f32 {return self.low52;}
-    pub fn set_low52(&mut self, low52: f32) {self.low52 = low52;}
+    pub fn get_summary(&self) -> String {return self.summary.to_string();}
+    pub fn set_summary(&mut self, summary: &str) {self.summary = summary.to_string();}
 
     pub fn get_historical_data(&self) -> &Vec<historical::HistoricalData> {return &self.historical_data;}
-    pub fn load_historical_data(&mut self, location: &Path) -> Result<bool, String> {
-        if !location.exists() {
-            return Err(format!("Could not find location for historical data for ticker {}: {:?}", self.ticker, location));
-        }
+    /// Loads historical data from a CSV file using the default
+    /// `LoadOptions`: bad rows are skipped and inconsistent bars are kept.
+    pub fn load_historical_data(&mut self, location: &Path) -> Result<bool, load::LoadError> {
+        self.load_historical_data_with(location, &load::LoadOptions::default())?;
+        return Ok(true);
+    }
 
-        // Extract the content
-        let content = match read_to_string(location) {
-            Ok(contents) => contents,
-            Err(error) => return Err(error.to_string())
+    /// Loads historical data from a CSV file and returns a report of the
+    /// rows that were skipped or flagged. When the options set a cache
+    /// directory, the data is read from the binary cache if it is newer
+    /// than the CSV file.
+    ///
+    /// The loaded bars are merged into the historical data, which stays
+    /// sorted by date with one bar per timestamp. A loaded bar that restates
+    /// an existing bar is handled by the options' merge policy, and the
+    /// report's `get_merge` lists duplicates, restatements and gaps. See
+    /// `historical::merge`.
+    /// 
+    /// ### Examples
+    /// ```
+    /// let mut s = Stock::new("ko");
+    /// let report = s.load_historical_data_with(Path::new("./src/assets/historical/ko.csv"), &LoadOptions::default()).unwrap();
+    /// for issue in report.get_skipped() {
+    ///     println!("Line {}: {}", issue.get_line(), issue.get_reason());
+    /// }
+    /// ```
+    pub fn load_historical_data_with(&mut self, location: &Path, options: &load::LoadOptions) -> Result<load::LoadReport, load::LoadError> {
+        let (data, mut report) = match options.get_cache_directory() {
+            Some(directory) => cache::load_historical(location, directory, options)?,
+            None => load::parse_historical(&load::read_file(location)?, options)?,
         };
 
-        // Add HistoricalData to Stock
-        let content = content.replace("\r", "");
-        let contents: Vec<&str> = content.split('\n').collect();
-        for line in &contents[1..contents.len()] {
-            let values: Vec<&str> = line.split(',').collect();
-            self.historical_data.push(historical::HistoricalData::new(
-                values[0],
-                match values[1].to_string().parse() {
-                    Ok(open) => open,
-                    Err(_) => 0.0,
-                },
-                match values[2].to_string().parse() {
-                    Ok(high) => high,
-                    Err(_) => 0.0,
-                },
-                match values[3].to_string().parse() {
-                    Ok(low) => low,
-                    Err(_) => 0.0,
-                },
-                match values[4].to_string().parse() {
-                    Ok(close) => close,
-                    Err(_) => 0.0,
-                },
-                match values[5].to_string().parse() {
-                    Ok(volume) => volume,
-                    Err(_) => 0,
-                },
-            ));
-        }
+        report.set_merge(self.merge_historical_data(data, options.get_merge_policy())?);
+        return Ok(report);
+    }
 
-        // Sort data
-        historical::sort_by_date(&mut self.historical_data);
+    /// Loads fundamentals and the historical data within `range` from any
+    /// `DataProvider`, e.g. local CSV files, the binary cache or the IEX
+    /// API.
+    ///
+    /// ### Examples
+    /// ```
+    /// let provider = provider::CsvProvider::new(Path::new("./src/assets"));
+    /// let mut s = Stock::new("aapl");
+    /// s.load_from(&provider, &provider::DateRange::all(), provider::Interval::Daily).await?;
+    /// ```
+    pub async fn load_from<P>(&mut self, source: &P, range: &provider::DateRange, interval: provider::Interval) -> Result<bool, provider::ProviderError>
+    where P: provider::DataProvider {
+        let fundamentals = source.fetch_fundamentals(&self.ticker).await?;
+        let data = source.fetch_history(&self.ticker, range, interval).await?;
+        self.apply_fundamentals(&fundamentals);
+        self.add_historical_data(data);
+        return Ok(true);
+    }
+
+    /// Adds bars to the historical data, e.g. from `iex::Client::chart`.
+    /// The data stays sorted by date and a bar with the same timestamp as
+    /// an existing bar replaces it.
+    pub fn add_historical_data(&mut self, data: Vec<historical::HistoricalData>) {
+        // `TakeNew` never fails
+        let _ = self.merge_historical_data(data, historical::MergePolicy::TakeNew);
+    }
+
+    /// Merges bars into the historical data, deduplicating overlapping
+    /// timestamps and resolving restated bars with `policy`. On error the
+    /// historical data is left unchanged. See `historical::merge`.
+    ///
+    /// ### Examples
+    /// ```
+    /// let report = s.merge_historical_data(new_bars, MergePolicy::KeepOld).unwrap();
+    /// println!("{} new bars, {} restated, {} gaps", report.get_added(), report.get_restated().len(), report.get_gaps().len());
+    /// ```
+    pub fn merge_historical_data(&mut self, data: Vec<historical::HistoricalData>, policy: historical::MergePolicy) -> Result<historical::MergeReport, historical::MergeError> {
+        let report = historical::merge(&mut self.historical_data, data, policy)?;
+        // Merged bars can land anywhere, so rebuild the columns
+        self.price_series = series::PriceSeries::from_historical(&self.historical_data);
+        return Ok(report);
+    }
+
+    pub fn get_splits(&self) -> &Vec<actions::Split> {return &self.splits;}
+    pub fn add_split(&mut self, split: actions::Split) {
+        self.splits.push(split);
+        self.splits.sort_by(|a, b| a.get_date().cmp(b.get_date()));
+    }
+
+    pub fn get_dividends(&self) -> &Vec<actions::Dividend> {return &self.dividends;}
+    pub fn add_dividend(&mut self, dividend: actions::Dividend) {
+        self.dividends.push(dividend);
+        self.dividends.sort_by(|a, b| a.get_date().cmp(b.get_date()));
+    }
 
+    /// Loads splits and dividends from a corporate actions CSV file with
+    /// `date,action,value` columns. See `actions::parse`.
+    pub fn load_actions(&mut self, location: &Path) -> Result<bool, load::LoadError> {
+        let content = load::read_file(location)?;
+        let (splits, dividends) = actions::parse(&content)?;
+        for split in splits { self.add_split(split); }
+        for dividend in dividends { self.add_dividend(dividend); }
         return Ok(true);
     }
 
-    pub fn query_historical_data(&self, start_date: &datetime::DateTime, end_date: &datetime::DateTime) -> Vec<&historical::HistoricalData> {
-        let mut o: Vec<&historical::HistoricalData> = Vec::new();
-        for r in self.historical_data.iter() {
-            if r.get_date().is_on_or_after(start_date) && r.get_date().is_on_or_before(end_date) {
-                o.push(r);
-            }
-        }
-        return o;
+    /// Returns the historical data back-adjusted for the stock's splits and,
+    /// optionally, dividends. The raw data is left untouched.
+    pub fn get_adjusted_historical_data(&self, adjustment: actions::Adjustment) -> Vec<historical::HistoricalData> {
+        return actions::back_adjust(&self.historical_data, &self.splits, &self.dividends, adjustment);
     }
 
-    pub fn load_data(&mut self, location: &Path) -> Result<bool, String> {
-        if !location.exists() {
-            return Err(format!("Could not find location for data for ticker {}: {:?}", self.ticker, location));
-        }
+    /// Returns a total-return index aligned with the historical data, with
+    /// dividends reinvested on their ex-date. See
+    /// `actions::total_return_index`.
+    pub fn get_total_return_index(&self, base: f64) -> Vec<f64> {
+        return actions::total_return_index(&self.historical_data, &self.splits, &self.dividends, base);
+    }
 
-         // Extract the content
-         let content = match read_to_string(location) {
-            Ok(contents) => contents,
-            Err(error) => return Err(error.to_string())
-        };
+    /// Returns the historical data as a columnar `PriceSeries`, whose
+    /// columns can be passed to the `ta` functions as slices. The series is
+    /// built when historical data is loaded or merged, so this does not
+    /// copy anything.
+    ///
+    /// ### Examples
+    /// ```
+    /// let series = s.get_price_series();
+    /// let rsis = ta::rsi::run(series.get_closes());
+    /// ```
+    pub fn get_price_series(&self) -> &series::PriceSeries {
+        return &self.price_series;
+    }
 
-        // Build entries
-        let content: Vec<&str> = content.split('\n').collect();
-        let values: Vec<&str> = content[1].split(',').collect();
-
-        self.set_security_type(values[0]);
-        self.set_market_cap(match values[1].to_string().parse() {
-            Ok(market_cap) => market_cap,
-            Err(_) => 0,
-        });
-        self.set_high52(match values[2].to_string().parse() {
-            Ok(high52) => high52,
-            Err(_) => 0.0,
-        });
-        self.set_low52(match values[3].to_string().parse() {
-            Ok(low52) => low52,
-            Err(_) => 0.0,
-        });
-        self.set_pe_ratio(match values[4].to_string().parse() {
-            Ok(pe_ratio) => pe_ratio,
-            Err(_) => 0.0,
-        });
-        self.set_eps(match values[5].to_string().parse() {
-            Ok(eps) => eps,
-            Err(_) => 0.0,
-        });
-        
+    /// Returns the bars dated from `start_date` to `end_date`, both
+    /// inclusive, found by binary search.
+    pub fn query_historical_data(&self, start_date: &datetime::DateTime, end_date: &datetime::DateTime) -> &[historical::HistoricalData] {
+        return historical::query(&self.historical_data, start_date, end_date);
+    }
 
+    /// Loads fundamentals from a CSV file with a header and one data row.
+    /// Columns are matched by name, so any subset of
+    /// `name,type,marketcap,high52,low52,pe,eps,summary` in any order is
+    /// accepted. Fields without a column are left unchanged.
+    pub fn load_data(&mut self, location: &Path) -> Result<bool, load::LoadError> {
+        let content = load::read_file(location)?;
+        self.apply_fundamentals(&load::parse_fundamentals(&content)?);
         return Ok(true);
     }
 
-    pub fn backtest(&self) {
-        backtest::run(self);
+    /// Copies the fields that `fundamentals` has into the stock. Missing
+    /// fields are left unchanged.
+    pub fn apply_fundamentals(&mut self, fundamentals: &load::Fundamentals) {
+        if let Some(name) = fundamentals.get_name() { self.set_name(&name); }
+        if let Some(security_type) = fundamentals.get_security_type() { self.set_security_type(&security_type); }
+        if let Some(market_cap) = fundamentals.get_market_cap() { self.set_market_cap(market_cap); }
+        if let Some(high52) = fundamentals.get_high52() { self.set_high52(high52); }
+        if let Some(low52) = fundamentals.get_low52() { self.set_low52(low52); }
+        if let Some(pe_ratio) = fundamentals.get_pe_ratio() { self.set_pe_ratio(pe_ratio); }
+        if let Some(eps) = fundamentals.get_eps() { self.set_eps(eps); }
+        if let Some(summary) = fundamentals.get_summary() { self.set_summary(&summary); }
+    }
+
+    pub fn backtest<S>(&self, strategy: &mut S, config: &backtest::BacktestConfig) -> backtest::BacktestResult
+    where S: backtest::Strategy + ?Sized {
+        return backtest::run(self, strategy, config);
     }
 
+    #[allow(clippy::inherent_to_string)]
     pub fn to_string(&self) -> String {
         let mut output = "".to_owned();
-        output.push_str("{",);
+        output.push('{');
         output.push_str(&format!("\"ticker\": \"{}\",", self.ticker));
         output.push_str(&format!("\"security_type\": \"{}\",", self.security_type));
         output.push_str(&format!("\"name\": \"{}\",", self.name));
         output.push_str(&format!("\"market_cap\": {},", self.market_cap));
         output.push_str(&format!("\"pe_ratio\": {},", self.pe_ratio));
         output.push_str(&format!("\"eps\": {},", self.eps));
+        // The summary is free text, so it is escaped
+        output.push_str(&format!("\"summary\": {},", JSONObject::String(self.summary.to_string()).stringify()));
         output.push_str(&format!("\"high52\": {},", self.high52));
         output.push_str(&format!("\"low52\": {},", self.low52));
         output.push_str("\"historical_data\": [");
@@ -221,4 +307,148 @@ mod tests {
         assert_eq!(hd[0].get_close(), 182.92);
         assert_eq!(hd[0].get_volume(), 119636836);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_load_historical_data_with_report() {
+        let mut s = Stock::new("ko");
+        let report = match s.load_historical_data_with(Path::new("./src/assets/historical/ko.csv"), &load::LoadOptions::default()) {Ok(r) => r, Err(error) => panic!("{}", error)};
+        assert_eq!(report.get_loaded(), 15214);
+        assert_eq!(s.get_historical_data().len(), 15214);
+        assert_eq!(report.get_skipped().len(), 1);
+        assert_eq!(report.get_skipped()[0].get_line(), 9997);
+        assert_eq!(report.get_flagged().len(), 0);
+    }
+
+    #[test]
+    fn test_load_historical_data_cached() {
+        let directory = std::env::temp_dir().join(format!("stockwatcher-stock-cache-{}", std::process::id()));
+        let mut options = load::LoadOptions::default();
+        options.set_cache_directory(Some(directory.clone()));
+
+        let mut parsed = Stock::new("ko");
+        let report = parsed.load_historical_data_with(Path::new("./src/assets/historical/ko.csv"), &options).unwrap();
+        assert_eq!(cache::is_fresh(&cache::cache_path(&directory, Path::new("ko.csv")), Path::new("./src/assets/historical/ko.csv")), true);
+        let mut cached = Stock::new("ko");
+        assert_eq!(cached.load_historical_data_with(Path::new("./src/assets/historical/ko.csv"), &options).unwrap(), report);
+        assert_eq!(cached.get_historical_data().len(), 15214);
+        assert_eq!(cached.get_historical_data()[9000].to_string(), parsed.get_historical_data()[9000].to_string());
+        assert_eq!(cached.get_historical_data()[15213].to_string(), parsed.get_historical_data()[15213].to_string());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_price_series_follows_merges() {
+        let mut s = Stock::new("test");
+        assert_eq!(s.get_price_series().len(), 0);
+        s.add_historical_data(vec![
+            historical::HistoricalData::new("1/2/2020 16:00:00", 10.0, 11.0, 9.0, 10.0, 100),
+            historical::HistoricalData::new("1/6/2020 16:00:00", 15.0, 16.0, 14.0, 16.0, 100),
+        ]);
+        s.add_historical_data(vec![historical::HistoricalData::new("1/3/2020 16:00:00", 12.0, 13.0, 11.0, 12.0, 100)]);
+        assert_eq!(s.get_price_series().get_closes(), &[10.0, 12.0, 16.0]);
+
+        // A failed merge leaves the series as it was
+        let restated = vec![historical::HistoricalData::new("1/3/2020 16:00:00", 12.0, 13.0, 11.0, 13.0, 100)];
+        assert!(s.merge_historical_data(restated, historical::MergePolicy::Error).is_err());
+        assert_eq!(s.get_price_series().get_closes(), &[10.0, 12.0, 16.0]);
+    }
+
+    #[test]
+    fn test_obv_full_spy_history() {
+        let mut s = Stock::new("spy");
+        s.load_historical_data(Path::new("./src/assets/historical/spy.csv")).unwrap();
+        let data = s.get_historical_data();
+        let series = s.get_price_series();
+        let obvs = ta::obv::run(series.get_closes(), series.get_volumes());
+
+        let mut expected: i128 = 0;
+        for i in 1..data.len() {
+            if data[i].get_close() > data[i - 1].get_close() { expected += data[i].get_volume() as i128; }
+            if data[i].get_close() < data[i - 1].get_close() { expected -= data[i].get_volume() as i128; }
+        }
+        assert_eq!(*obvs.last().unwrap() as i128, expected);
+        assert!(obvs.iter().any(|&obv| obv.abs() > i32::MAX as i64));
+    }
+
+    #[test]
+    fn test_load_historical_data_twice_and_query() {
+        let mut s = Stock::new("spy");
+        s.load_historical_data(Path::new("./test/data/spy_historical.csv")).unwrap();
+        let len = s.get_historical_data().len();
+        let mut options = load::LoadOptions::default();
+        options.set_merge_policy(historical::MergePolicy::Error);
+        let report = s.load_historical_data_with(Path::new("./test/data/spy_historical.csv"), &options).unwrap();
+        assert_eq!(s.get_historical_data().len(), len);
+        assert_eq!(report.get_merge().get_added(), 0);
+        assert_eq!(report.get_merge().get_duplicates(), len);
+        assert_eq!(report.get_merge().get_restated().len(), 0);
+
+        let q = s.query_historical_data(&datetime::DateTime::new("1/3/2014"), &datetime::DateTime::new("1/7/2014 16:00:00"));
+        let dates: Vec<String> = q.iter().map(|d| d.get_date().to_string()).collect();
+        assert_eq!(dates, vec!["1/3/2014 16:00:00", "1/6/2014 16:00:00", "1/7/2014 16:00:00"]);
+    }
+
+    #[test]
+    fn test_load_historical_data_not_found() {
+        let mut s = Stock::new("spy");
+        let error = s.load_historical_data(Path::new("./test/data/missing.csv")).unwrap_err();
+        assert_eq!(error, load::LoadError::NotFound { path: "./test/data/missing.csv".to_string() });
+    }
+
+    #[test]
+    fn test_load_contemporary_data_asset_layout() {
+        let mut s = Stock::new("aapl");
+        match s.load_data(Path::new("./src/assets/contemporary/aapl.csv")) {Ok(b) => b, Err(error) => panic!("{}", error)};
+        assert_eq!(s.get_name(), "Apple Inc.");
+        assert_eq!(s.get_security_type(), "EQUITY");
+        assert_eq!(s.get_market_cap(), 2427490926592);
+        assert_eq!(s.get_high52(), 182.94);
+        assert_eq!(s.get_low52(), 125.94);
+        assert_eq!(s.get_pe_ratio(), 23.94175);
+        assert_eq!(s.get_eps(), 6.56);
+        assert!(s.get_summary().starts_with("Apple Inc. designs, manufactures, and markets smartphones, personal computers"));
+    }
+
+    #[test]
+    fn test_to_string_summary() {
+        let mut s = Stock::new("test");
+        s.set_eps(1.5);
+        s.set_summary("Makes \"widgets\",\nand gadgets.");
+        assert!(s.to_string().contains("\"eps\": 1.5,\"summary\": \"Makes \\\"widgets\\\",\\nand gadgets.\",\"high52\""));
+    }
+
+    #[test]
+    fn test_load_contemporary_data_empty_values() {
+        let mut s = Stock::new("spy");
+        match s.load_data(Path::new("./src/assets/contemporary/spy.csv")) {Ok(b) => b, Err(error) => panic!("{}", error)};
+        assert_eq!(s.get_name(), "SPDR S&P 500 ETF Trust");
+        assert_eq!(s.get_security_type(), "ETF");
+        assert_eq!(s.get_market_cap(), 0);
+        assert_eq!(s.get_high52(), 479.98);
+        assert_eq!(s.get_pe_ratio(), 0.0);
+    }
+
+    #[test]
+    fn test_load_actions() {
+        let mut s = Stock::new("spy");
+        match s.load_historical_data(Path::new("./test/data/spy_historical.csv")) {Ok(b) => b, Err(error) => panic!("{}", error)};
+        match s.load_actions(Path::new("./test/data/spy_actions.csv")) {Ok(b) => b, Err(error) => panic!("{}", error)};
+        assert_eq!(s.get_splits().len(), 1);
+        assert_eq!(s.get_splits()[0].get_ratio(), 2.0);
+        assert_eq!(s.get_dividends().len(), 1);
+        assert_eq!(s.get_dividends()[0].get_amount(), 1.8289);
+
+        let adjusted = s.get_adjusted_historical_data(actions::Adjustment::Splits);
+        assert_eq!(adjusted[0].get_close(), 91.46);
+        assert_eq!(adjusted[0].get_volume(), 239273672);
+        assert_eq!(adjusted[4].get_close(), 183.52);
+        let adjusted = s.get_adjusted_historical_data(actions::Adjustment::SplitsAndDividends);
+        assert_eq!(adjusted[0].get_close(), 90.54274186225048);
+        assert_eq!(s.get_historical_data()[0].get_close(), 182.92);
+
+        let index = s.get_total_return_index(100.0);
+        assert_eq!(index.len(), s.get_historical_data().len());
+        assert_eq!(index[0], 100.0);
+        assert_eq!(index[3], 101.30598075661491);
+    }
+}
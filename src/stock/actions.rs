@@ -0,0 +1,342 @@
+use crate::stock::csv;
+use crate::stock::datetime::DateTime;
+use crate::stock::historical::HistoricalData;
+use crate::stock::load::LoadError;
+
+/// A stock split effective on its ex-date. `ratio` is new shares per old
+/// share, so a 4-for-1 split has a ratio of `4.0` and a 1-for-10 reverse
+/// split a ratio of `0.1`.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct Split {
+    date: DateTime,
+    ratio: f64,
+}
+
+#[allow(dead_code)]
+impl Split {
+    pub fn new(date: &str, ratio: f64) -> Self {
+        return Self { date: DateTime::new(date), ratio: ratio };
+    }
+
+    pub fn get_date(&self) -> &DateTime { return &self.date; }
+    pub fn get_ratio(&self) -> f64 { return self.ratio; }
+}
+
+/// A cash dividend per share, as paid at the time, effective on its
+/// ex-date.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct Dividend {
+    date: DateTime,
+    amount: f64,
+}
+
+#[allow(dead_code)]
+impl Dividend {
+    pub fn new(date: &str, amount: f64) -> Self {
+        return Self { date: DateTime::new(date), amount: amount };
+    }
+
+    pub fn get_date(&self) -> &DateTime { return &self.date; }
+    pub fn get_amount(&self) -> f64 { return self.amount; }
+}
+
+/// Which corporate actions to back-adjust prices for.
+///
+/// - `Splits`: prices are divided, and volumes multiplied, by the ratio of
+///   every later split
+/// - `SplitsAndDividends`: additionally scales prices by `1 - dividend /
+///   close` for every later dividend, using the close before the ex-date
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum Adjustment {
+    Splits,
+    SplitsAndDividends,
+}
+
+/// Parses a split ratio written as `new:old` (ex. `4:1`) or as a number.
+fn parse_ratio(value: &str) -> Option<f64> {
+    let ratio = match value.split_once([':', '/']) {
+        Some((new, old)) => new.trim().parse::<f64>().ok()? / old.trim().parse::<f64>().ok()?,
+        None => value.parse::<f64>().ok()?,
+    };
+    if !ratio.is_finite() || ratio <= 0.0 {
+        return None;
+    }
+    return Some(ratio);
+}
+
+/// Parses a corporate actions file with `date`, `action` and `value`
+/// columns, in any order. `action` is either `split` or `dividend`. Split
+/// values are ratios such as `4:1`, dividend values are cash amounts per
+/// share. Returns the splits and dividends sorted by date.
+///
+/// Note that the bundled historical files come from a source that already
+/// adjusts for splits, so only dividends should be applied to them.
+///
+/// # Arguments
+///
+/// * `content` - CSV content, header included
+///
+/// ### Examples
+/// ```
+/// let (splits, dividends) = actions::parse("date,action,value\n2020-08-31,split,4:1\n2020-08-07,dividend,0.82\n").unwrap();
+/// assert_eq!(splits[0].get_ratio(), 4.0);
+/// assert_eq!(dividends[0].get_amount(), 0.82);
+/// ```
+pub fn parse(content: &str) -> Result<(Vec<Split>, Vec<Dividend>), LoadError> {
+    let records = csv::parse(content)?;
+    let header = match records.first() {
+        Some(header) => header,
+        None => return Err(LoadError::Empty),
+    };
+    let date = header.find("date").ok_or(LoadError::MissingColumn { column: "date" })?;
+    let action = header.find("action").or(header.find("type")).ok_or(LoadError::MissingColumn { column: "action" })?;
+    let value = header.find("value").ok_or(LoadError::MissingColumn { column: "value" })?;
+
+    let mut splits = Vec::new();
+    let mut dividends = Vec::new();
+    for record in records.iter().skip(1) {
+        let invalid = |reason: String| LoadError::InvalidRow { line: record.get_line(), reason: reason };
+        let d = match DateTime::parse(record.get(date).unwrap_or("")) {
+            Ok(d) => d,
+            Err(error) => return Err(invalid(error.to_string().trim_end_matches('.').to_string())),
+        };
+        let v = record.get(value).unwrap_or("").trim();
+        match record.get(action).unwrap_or("").trim().to_lowercase().as_str() {
+            "split" => match parse_ratio(v) {
+                Some(ratio) => splits.push(Split { date: d, ratio: ratio }),
+                None => return Err(invalid(format!("invalid split ratio \"{}\"", v))),
+            },
+            "dividend" => match v.parse::<f64>() {
+                Ok(amount) if amount.is_finite() && amount >= 0.0 => dividends.push(Dividend { date: d, amount: amount }),
+                _ => return Err(invalid(format!("invalid dividend \"{}\"", v))),
+            },
+            other => return Err(invalid(format!("unknown action \"{}\"", other))),
+        };
+    }
+    splits.sort_by(|a, b| a.date.cmp(&b.date));
+    dividends.sort_by(|a, b| a.date.cmp(&b.date));
+    return Ok((splits, dividends));
+}
+
+/// Returns a back-adjusted copy of `data`, so that prices before each
+/// corporate action are comparable with prices after it. The most recent
+/// bars keep their raw prices. An action applies to every bar dated before
+/// its ex-date. `data` must be sorted by date.
+///
+/// # Arguments
+///
+/// * `data` - Raw historical data, sorted by date
+/// * `splits` - Splits to adjust for
+/// * `dividends` - Dividends to adjust for with `Adjustment::SplitsAndDividends`
+/// * `adjustment` - Which actions to adjust for
+///
+/// ### Examples
+/// ```
+/// let adjusted = actions::back_adjust(s.get_historical_data(), s.get_splits(), s.get_dividends(), Adjustment::SplitsAndDividends);
+/// ```
+///
+/// #### Resources
+/// - [CRSP Calculations](http://www.crsp.org/products/documentation/crsp-calculations)
+pub fn back_adjust(data: &[HistoricalData], splits: &[Split], dividends: &[Dividend], adjustment: Adjustment) -> Vec<HistoricalData> {
+    let mut adjusted: Vec<HistoricalData> = data.to_vec();
+    let mut price_factor: f64 = 1.0;
+    let mut volume_factor: f64 = 1.0;
+    let mut split_index = splits.len();
+    let mut dividend_index = dividends.len();
+
+    for i in (0..adjusted.len()).rev() {
+        let day = adjusted[i].get_date().start_of_day();
+
+        // Fold in every action whose ex-date comes after this bar
+        while split_index > 0 && splits[split_index - 1].date.start_of_day() > day {
+            split_index -= 1;
+            price_factor /= splits[split_index].ratio;
+            volume_factor *= splits[split_index].ratio;
+        }
+        while dividend_index > 0 && dividends[dividend_index - 1].date.start_of_day() > day {
+            dividend_index -= 1;
+            let close = adjusted[i].get_close();
+            if adjustment == Adjustment::SplitsAndDividends && close > 0.0 {
+                price_factor *= (1.0 - dividends[dividend_index].amount / close).max(0.0);
+            }
+        }
+
+        if price_factor == 1.0 && volume_factor == 1.0 {
+            continue;
+        }
+        let bar = &mut adjusted[i];
+        bar.set_open(bar.get_open() * price_factor);
+        bar.set_high(bar.get_high() * price_factor);
+        bar.set_low(bar.get_low() * price_factor);
+        bar.set_close(bar.get_close() * price_factor);
+        bar.set_volume((bar.get_volume() as f64 * volume_factor).round().min(u64::MAX as f64) as u64);
+    }
+    return adjusted;
+}
+
+/// Returns a total-return index aligned with `data`, starting at `base`.
+/// Dividends are reinvested at the close of their ex-date, so the index
+/// moves by `(close + dividend) * split ratio / previous close` on every
+/// bar. Splits and dividends dated after the previous bar and up to the
+/// current one apply to the current bar. Dividend amounts are per share
+/// after any split on the same date. `data` must hold raw prices sorted by
+/// date.
+///
+/// # Arguments
+///
+/// * `data` - Raw historical data, sorted by date
+/// * `splits` - Splits used to keep the index continuous
+/// * `dividends` - Dividends to reinvest
+/// * `base` - Value of the index on the first bar
+///
+/// ### Examples
+/// ```
+/// let index = actions::total_return_index(s.get_historical_data(), s.get_splits(), s.get_dividends(), 100.0);
+/// ```
+pub fn total_return_index(data: &[HistoricalData], splits: &[Split], dividends: &[Dividend], base: f64) -> Vec<f64> {
+    let mut index: Vec<f64> = Vec::with_capacity(data.len());
+    if data.is_empty() {
+        return index;
+    }
+    let first = data[0].get_date().start_of_day();
+    let mut split_index = splits.iter().take_while(|s| s.date.start_of_day() <= first).count();
+    let mut dividend_index = dividends.iter().take_while(|d| d.date.start_of_day() <= first).count();
+    let mut value = base;
+    index.push(base);
+
+    for i in 1..data.len() {
+        let day = data[i].get_date().start_of_day();
+        let mut ratio: f64 = 1.0;
+        let mut dividend: f64 = 0.0;
+        while split_index < splits.len() && splits[split_index].date.start_of_day() <= day {
+            ratio *= splits[split_index].ratio;
+            split_index += 1;
+        }
+        while dividend_index < dividends.len() && dividends[dividend_index].date.start_of_day() <= day {
+            dividend += dividends[dividend_index].amount;
+            dividend_index += 1;
+        }
+        let previous = data[i - 1].get_close();
+        if previous > 0.0 {
+            value *= (data[i].get_close() + dividend) * ratio / previous;
+        }
+        index.push(value);
+    }
+    return index;
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bars() -> Vec<HistoricalData> {
+        return vec![
+            HistoricalData::new("8/26/2020 16:00:00", 500.0, 510.0, 490.0, 500.0, 100),
+            HistoricalData::new("8/27/2020 16:00:00", 400.0, 420.0, 380.0, 400.0, 200),
+            HistoricalData::new("8/28/2020 16:00:00", 480.0, 500.0, 460.0, 480.0, 300),
+            HistoricalData::new("8/31/2020 16:00:00", 120.0, 130.0, 110.0, 125.0, 1200),
+            HistoricalData::new("9/1/2020 16:00:00", 125.0, 130.0, 120.0, 130.0, 1000),
+        ];
+    }
+
+    fn closes(data: &[HistoricalData]) -> Vec<f64> {
+        return data.iter().map(|d| d.get_close()).collect();
+    }
+
+    #[test]
+    fn test_parse() {
+        let content = "Date,Action,Value\n2020-08-31,split,4:1\n2020-08-07,dividend,0.82\n2014-06-09,split,7\n2011-01-03,SPLIT,1:10\n";
+        let (splits, dividends) = parse(content).unwrap();
+        let ratios: Vec<f64> = splits.iter().map(|s| s.get_ratio()).collect();
+        assert_eq!(ratios, vec![0.1, 7.0, 4.0]);
+        assert_eq!(splits[2].get_date().to_string(), "8/31/2020 00:00:00");
+        assert_eq!(dividends, vec![Dividend::new("8/7/2020 00:00:00", 0.82)]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse("date,value\n").unwrap_err(), LoadError::MissingColumn { column: "action" });
+        assert_eq!(
+            parse("date,action,value\n2020-08-31,split,0:1\n").unwrap_err(),
+            LoadError::InvalidRow { line: 2, reason: "invalid split ratio \"0:1\"".to_string() },
+        );
+        assert_eq!(
+            parse("date,action,value\n2020-08-31,dividend,-1\n").unwrap_err(),
+            LoadError::InvalidRow { line: 2, reason: "invalid dividend \"-1\"".to_string() },
+        );
+        assert_eq!(
+            parse("date,action,value\n2020-08-31,merger,1\n").unwrap_err(),
+            LoadError::InvalidRow { line: 2, reason: "unknown action \"merger\"".to_string() },
+        );
+    }
+
+    #[test]
+    fn test_back_adjust_split() {
+        let splits = vec![Split::new("8/31/2020 00:00:00", 4.0)];
+        let adjusted = back_adjust(&bars(), &splits, &[], Adjustment::Splits);
+        assert_eq!(closes(&adjusted), vec![125.0, 100.0, 120.0, 125.0, 130.0]);
+        assert_eq!(adjusted[0].get_open(), 125.0);
+        assert_eq!(adjusted[0].get_high(), 127.5);
+        assert_eq!(adjusted[0].get_low(), 122.5);
+        let volumes: Vec<u64> = adjusted.iter().map(|d| d.get_volume()).collect();
+        assert_eq!(volumes, vec![400, 800, 1200, 1200, 1000]);
+        assert_eq!(adjusted[0].get_date().to_string(), "8/26/2020 16:00:00");
+    }
+
+    #[test]
+    fn test_back_adjust_dividends() {
+        // 5.0 on a 500.0 close is 1%, 4.0 on a 400.0 close another 1%
+        let dividends = vec![Dividend::new("8/27/2020 00:00:00", 5.0), Dividend::new("8/28/2020 00:00:00", 4.0)];
+        let adjusted = back_adjust(&bars(), &[], &dividends, Adjustment::SplitsAndDividends);
+        assert_eq!(closes(&adjusted), vec![490.05, 396.0, 480.0, 125.0, 130.0]);
+        // Dividends are ignored when only adjusting for splits
+        let adjusted = back_adjust(&bars(), &[], &dividends, Adjustment::Splits);
+        assert_eq!(closes(&adjusted), closes(&bars()));
+    }
+
+    #[test]
+    fn test_back_adjust_split_and_dividend() {
+        let splits = vec![Split::new("8/31/2020 00:00:00", 4.0)];
+        let dividends = vec![Dividend::new("8/28/2020 00:00:00", 4.0)];
+        let adjusted = back_adjust(&bars(), &splits, &dividends, Adjustment::SplitsAndDividends);
+        assert_eq!(closes(&adjusted), vec![123.75, 99.0, 120.0, 125.0, 130.0]);
+        let volumes: Vec<u64> = adjusted.iter().map(|d| d.get_volume()).collect();
+        assert_eq!(volumes, vec![400, 800, 1200, 1200, 1000]);
+    }
+
+    #[test]
+    fn test_back_adjust_actions_outside_range() {
+        let splits = vec![Split::new("1/1/2000 00:00:00", 2.0), Split::new("1/1/2030 00:00:00", 2.0)];
+        let adjusted = back_adjust(&bars(), &splits, &[], Adjustment::Splits);
+        assert_eq!(closes(&adjusted), vec![250.0, 200.0, 240.0, 62.5, 65.0]);
+        assert_eq!(back_adjust(&[], &splits, &[], Adjustment::Splits).len(), 0);
+    }
+
+    #[test]
+    fn test_total_return_index() {
+        let data = vec![
+            HistoricalData::new("1/2/2020 16:00:00", 50.0, 50.0, 50.0, 50.0, 100),
+            HistoricalData::new("1/3/2020 16:00:00", 50.0, 50.0, 50.0, 50.0, 100),
+            HistoricalData::new("1/6/2020 16:00:00", 49.0, 49.0, 49.0, 49.0, 100),
+            HistoricalData::new("1/7/2020 16:00:00", 55.0, 55.0, 55.0, 55.0, 100),
+            HistoricalData::new("1/8/2020 16:00:00", 27.5, 27.5, 27.5, 27.5, 100),
+        ];
+        // A dividend before the first bar is already in the price
+        let dividends = vec![Dividend::new("1/2/2020 00:00:00", 3.0), Dividend::new("1/4/2020 00:00:00", 1.0)];
+        let splits = vec![Split::new("1/8/2020 00:00:00", 2.0)];
+        let index = total_return_index(&data, &splits, &dividends, 100.0);
+        assert_eq!(index, vec![100.0, 100.0, 100.0, 112.24489795918366, 112.24489795918366]);
+        // Price only, for comparison
+        let index = total_return_index(&data, &splits, &[], 100.0);
+        assert_eq!(index, vec![100.0, 100.0, 98.0, 109.99999999999999, 109.99999999999999]);
+        assert_eq!(total_return_index(&[], &splits, &dividends, 100.0).len(), 0);
+    }
+}
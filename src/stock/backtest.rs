@@ -1,5 +1,642 @@
-use crate::stock::Stock;
+use crate::stock::{Stock, historical::HistoricalData, datetime::DateTime, actions::Adjustment};
+use crate::stock::provider::{DataProvider, DateRange, Interval, ProviderError};
 
-pub fn run(stock: &Stock) {
-    println!("{}", stock.get_ticker());
-}
\ No newline at end of file
+pub mod report;
+pub mod strategies;
+
+/// Determines at which price an order is filled.
+///
+/// - `NextOpen`: orders emitted on a bar are filled at the open of the
+///   following bar. Orders emitted on the final bar are never filled.
+/// - `Close`: orders emitted on a bar are filled at that bar's close.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq)]
+#[allow(dead_code)]
+pub enum Fill {
+    NextOpen,
+    Close,
+}
+
+/// Side of an executed trade.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+/// An order intent emitted by a strategy.
+///
+/// - `Buy(quantity)`: buy `quantity` shares, limited by available cash
+/// - `Sell(quantity)`: sell `quantity` shares, limited by the open position
+/// - `TargetPercent(pct)`: rebalance the position to `pct` (0.0 - 1.0) of equity
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq)]
+#[allow(dead_code)]
+pub enum Order {
+    Buy(f64),
+    Sell(f64),
+    TargetPercent(f64),
+}
+
+/// Settings used to run a backtest.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct BacktestConfig {
+    initial_cash: f64,
+    fill: Fill,
+    commission: f64,
+    adjustment: Option<Adjustment>,
+    credit_dividends: bool,
+}
+
+#[allow(dead_code)]
+impl BacktestConfig {
+    pub fn new(initial_cash: f64, fill: Fill) -> Self {
+        return Self {
+            initial_cash: initial_cash,
+            fill: fill,
+            commission: 0.0,
+            adjustment: None,
+            credit_dividends: false,
+        };
+    }
+
+    pub fn get_initial_cash(&self) -> f64 { return self.initial_cash; }
+    pub fn set_initial_cash(&mut self, initial_cash: f64) { self.initial_cash = initial_cash; }
+
+    pub fn get_fill(&self) -> Fill { return self.fill; }
+    pub fn set_fill(&mut self, fill: Fill) { self.fill = fill; }
+
+    /// Commission charged as a fraction of the notional value of each trade.
+    pub fn get_commission(&self) -> f64 { return self.commission; }
+    pub fn set_commission(&mut self, commission: f64) { self.commission = commission; }
+
+    /// Prices the backtest runs on: `None` for raw prices, or back-adjusted
+    /// for the stock's corporate actions.
+    pub fn get_adjustment(&self) -> Option<Adjustment> { return self.adjustment; }
+    pub fn set_adjustment(&mut self, adjustment: Option<Adjustment>) { self.adjustment = adjustment; }
+
+    /// Whether the stock's dividends are paid as cash on the position held
+    /// going into each ex-date. Ignored when the adjustment is
+    /// `SplitsAndDividends`, whose prices already account for them.
+    pub fn get_credit_dividends(&self) -> bool { return self.credit_dividends; }
+    pub fn set_credit_dividends(&mut self, credit_dividends: bool) { self.credit_dividends = credit_dividends; }
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        return Self::new(10000.0, Fill::NextOpen);
+    }
+}
+
+/// State of the simulated account handed to the strategy on every bar.
+#[derive(Debug)]
+pub struct Context<'a> {
+    index: usize,
+    history: &'a [HistoricalData],
+    cash: f64,
+    position: f64,
+    equity: f64,
+}
+
+#[allow(dead_code)]
+impl<'a> Context<'a> {
+    /// Index of the current bar within the historical data.
+    pub fn get_index(&self) -> usize { return self.index; }
+    /// All bars up to and including the current bar.
+    pub fn get_history(&self) -> &'a [HistoricalData] { return self.history; }
+    pub fn get_cash(&self) -> f64 { return self.cash; }
+    pub fn get_position(&self) -> f64 { return self.position; }
+    /// Cash plus the open position valued at the current close.
+    pub fn get_equity(&self) -> f64 { return self.equity; }
+}
+
+/// An executed fill.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Trade {
+    date: DateTime,
+    side: Side,
+    quantity: f64,
+    price: f64,
+    commission: f64,
+    pnl: f64,
+}
+
+#[allow(dead_code)]
+impl Trade {
+    pub fn get_date(&self) -> &DateTime { return &self.date; }
+    pub fn get_side(&self) -> Side { return self.side; }
+    pub fn get_quantity(&self) -> f64 { return self.quantity; }
+    pub fn get_price(&self) -> f64 { return self.price; }
+    pub fn get_commission(&self) -> f64 { return self.commission; }
+    /// Realized profit/loss (net of commissions) against the average cost
+    /// of the position. Always `0.0` for buys.
+    pub fn get_pnl(&self) -> f64 { return self.pnl; }
+}
+
+/// Account state recorded at the close of every bar.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct EquityPoint {
+    date: DateTime,
+    cash: f64,
+    position: f64,
+    equity: f64,
+}
+
+#[allow(dead_code)]
+impl EquityPoint {
+    pub fn get_date(&self) -> &DateTime { return &self.date; }
+    pub fn get_cash(&self) -> f64 { return self.cash; }
+    pub fn get_position(&self) -> f64 { return self.position; }
+    pub fn get_equity(&self) -> f64 { return self.equity; }
+}
+
+/// Output of a backtest: the trade log and the equity curve.
+#[derive(Debug)]
+pub struct BacktestResult {
+    ticker: String,
+    initial_cash: f64,
+    trades: Vec<Trade>,
+    equity_curve: Vec<EquityPoint>,
+    dividends: f64,
+}
+
+#[allow(dead_code)]
+impl BacktestResult {
+    pub fn get_ticker(&self) -> String { return self.ticker.to_string(); }
+    pub fn get_initial_cash(&self) -> f64 { return self.initial_cash; }
+    pub fn get_trades(&self) -> &Vec<Trade> { return &self.trades; }
+    pub fn get_equity_curve(&self) -> &Vec<EquityPoint> { return &self.equity_curve; }
+
+    /// Total dividend income credited to the account.
+    pub fn get_dividends(&self) -> f64 { return self.dividends; }
+
+    /// Equity at the close of the last bar, or the initial cash if no bars
+    /// were processed.
+    pub fn get_final_equity(&self) -> f64 {
+        return match self.equity_curve.last() {
+            Some(p) => p.get_equity(),
+            None => self.initial_cash,
+        };
+    }
+}
+
+/// A trading strategy driven by the backtesting engine.
+///
+/// The engine calls `on_start` once before the first bar, `on_bar` for every
+/// bar in chronological order and `on_finish` once with the final result.
+/// Only `on_bar` is required.
+///
+/// ### Example
+/// ```
+/// struct BuyAndHold;
+///
+/// impl Strategy for BuyAndHold {
+///     fn on_bar(&mut self, _bar: &HistoricalData, ctx: &Context) -> Vec<Order> {
+///         if ctx.get_index() == 0 { vec![Order::TargetPercent(1.0)] } else { Vec::new() }
+///     }
+/// }
+/// ```
+pub trait Strategy {
+    fn on_start(&mut self, _config: &BacktestConfig) {}
+    fn on_bar(&mut self, bar: &HistoricalData, ctx: &Context) -> Vec<Order>;
+    fn on_finish(&mut self, _result: &BacktestResult) {}
+}
+
+/// `Strategy` built from a closure. See `from_fn`.
+pub struct FnStrategy<F> {
+    f: F,
+}
+
+impl<F> Strategy for FnStrategy<F>
+where F: FnMut(&HistoricalData, &Context) -> Vec<Order> {
+    fn on_bar(&mut self, bar: &HistoricalData, ctx: &Context) -> Vec<Order> {
+        return (self.f)(bar, ctx);
+    }
+}
+
+/// Wraps a closure called on every bar into a `Strategy`.
+///
+/// ### Example
+/// ```
+/// let mut strategy = backtest::from_fn(|_bar, ctx| vec![Order::TargetPercent(1.0)]);
+/// ```
+#[allow(dead_code)]
+pub fn from_fn<F>(f: F) -> FnStrategy<F>
+where F: FnMut(&HistoricalData, &Context) -> Vec<Order> {
+    return FnStrategy { f: f };
+}
+
+/// Simulated brokerage account.
+struct Account {
+    cash: f64,
+    position: f64,
+    cost_basis: f64,
+    commission: f64,
+    trades: Vec<Trade>,
+}
+
+impl Account {
+    fn execute(&mut self, order: Order, price: f64, date: &DateTime) {
+        if price <= 0.0 { return; }
+        let quantity = match order {
+            Order::Buy(q) => q,
+            Order::Sell(q) => -q,
+            Order::TargetPercent(pct) => {
+                let equity = self.cash + self.position * price;
+                (equity * pct.clamp(0.0, 1.0) / price) - self.position
+            },
+        };
+
+        if quantity > 0.0 {
+            // Never spend more than the available cash
+            let max_quantity = self.cash / (price * (1.0 + self.commission));
+            let quantity = quantity.min(max_quantity);
+            if quantity <= 0.0 { return; }
+            let commission = quantity * price * self.commission;
+            self.cash -= quantity * price + commission;
+            self.cost_basis += quantity * price + commission;
+            self.position += quantity;
+            self.trades.push(Trade {
+                date: date.clone(),
+                side: Side::Buy,
+                quantity: quantity,
+                price: price,
+                commission: commission,
+                pnl: 0.0,
+            });
+        } else if quantity < 0.0 {
+            // Never sell more than the open position
+            let quantity = (-quantity).min(self.position);
+            if quantity <= 0.0 { return; }
+            let commission = quantity * price * self.commission;
+            let cost = self.cost_basis * (quantity / self.position);
+            self.cash += quantity * price - commission;
+            self.cost_basis -= cost;
+            self.position -= quantity;
+            self.trades.push(Trade {
+                date: date.clone(),
+                side: Side::Sell,
+                quantity: quantity,
+                price: price,
+                commission: commission,
+                pnl: quantity * price - commission - cost,
+            });
+        }
+    }
+}
+
+/// Runs an event-driven backtest over the historical data of `stock`.
+///
+/// Each bar is handed to `strategy.on_bar` along with a `Context` describing
+/// the account. The returned orders are filled according to `config`, and the
+/// account's equity is recorded at the close of every bar.
+///
+/// # Arguments
+/// * `stock` - `Stock` with loaded historical data
+/// * `strategy` - `Strategy` returning the orders to place on every bar
+/// * `config` - `BacktestConfig` with the starting cash and fill model
+///
+/// ### Example
+/// ```
+/// let mut strategy = GoldenCross::default();
+/// let result = backtest::run(&stock, &mut strategy, &BacktestConfig::default());
+/// println!("{}", result.get_final_equity());
+/// ```
+pub fn run<S>(stock: &Stock, strategy: &mut S, config: &BacktestConfig) -> BacktestResult
+where S: Strategy + ?Sized {
+    strategy.on_start(config);
+    let adjusted;
+    let bars = match config.get_adjustment() {
+        Some(adjustment) => {
+            adjusted = stock.get_adjusted_historical_data(adjustment);
+            &adjusted
+        },
+        None => stock.get_historical_data(),
+    };
+    let mut account = Account {
+        cash: config.get_initial_cash(),
+        position: 0.0,
+        cost_basis: 0.0,
+        commission: config.get_commission(),
+        trades: Vec::new(),
+    };
+    let mut pending: Vec<Order> = Vec::new();
+    let mut equity_curve: Vec<EquityPoint> = Vec::new();
+    let splits = stock.get_splits();
+    let dividends = stock.get_dividends();
+    let mut dividend_index = 0;
+    let mut dividend_income = 0.0;
+    let credit_dividends = config.get_credit_dividends() && config.get_adjustment() != Some(Adjustment::SplitsAndDividends);
+
+    for (i, bar) in bars.iter().enumerate() {
+        // Dividends going ex on this bar are paid on the shares held at the
+        // previous close, before any order fills
+        let day = bar.get_date().start_of_day();
+        while dividend_index < dividends.len() && dividends[dividend_index].get_date().start_of_day() <= day {
+            if credit_dividends && i > 0 {
+                // Adjusted positions count shares after every later split,
+                // so scale the amount paid per share down the same way
+                let dividend = &dividends[dividend_index];
+                let ratio: f64 = match config.get_adjustment() {
+                    Some(_) => splits.iter()
+                        .filter(|split| split.get_date().start_of_day() > dividend.get_date().start_of_day())
+                        .map(|split| split.get_ratio())
+                        .product(),
+                    None => 1.0,
+                };
+                let amount = account.position * dividend.get_amount() / ratio;
+                account.cash += amount;
+                dividend_income += amount;
+            }
+            dividend_index += 1;
+        }
+
+        // Orders placed on the previous bar fill at this bar's open
+        for order in pending.drain(..) {
+            account.execute(order, bar.get_open(), bar.get_date());
+        }
+
+        let ctx = Context {
+            index: i,
+            history: &bars[..i+1],
+            cash: account.cash,
+            position: account.position,
+            equity: account.cash + account.position * bar.get_close(),
+        };
+        let orders = strategy.on_bar(bar, &ctx);
+        match config.get_fill() {
+            Fill::Close => for order in orders {
+                account.execute(order, bar.get_close(), bar.get_date());
+            },
+            Fill::NextOpen => pending = orders,
+        };
+
+        equity_curve.push(EquityPoint {
+            date: bar.get_date().clone(),
+            cash: account.cash,
+            position: account.position,
+            equity: account.cash + account.position * bar.get_close(),
+        });
+    }
+
+    let result = BacktestResult {
+        ticker: stock.get_ticker(),
+        initial_cash: config.get_initial_cash(),
+        trades: account.trades,
+        equity_curve: equity_curve,
+        dividends: dividend_income,
+    };
+    strategy.on_finish(&result);
+    return result;
+}
+
+
+/// Loads `ticker` within `range` from any `DataProvider` and runs a
+/// backtest on its daily bars. See `run`.
+///
+/// ### Example
+/// ```
+/// let provider = CsvProvider::new(Path::new("./src/assets"));
+/// let range = DateRange::new(DateTime::new("1/1/2015"), DateTime::new("12/31/2021"));
+/// let result = backtest::run_from(&provider, "spy", &range, &mut GoldenCross::default(), &BacktestConfig::default()).await?;
+/// ```
+#[allow(dead_code)]
+pub async fn run_from<P, S>(provider: &P, ticker: &str, range: &DateRange, strategy: &mut S, config: &BacktestConfig) -> Result<BacktestResult, ProviderError>
+where P: DataProvider, S: Strategy + ?Sized {
+    let mut stock = Stock::new(ticker);
+    stock.load_from(provider, range, Interval::Daily).await?;
+    return Ok(run(&stock, strategy, config));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_stock() -> Stock {
+        let mut s = Stock::new("test");
+        s.add_historical_data(vec![
+            HistoricalData::new("1/2/2020 16:00:00", 10.0, 11.0, 9.0, 10.0, 100),
+            HistoricalData::new("1/3/2020 16:00:00", 12.0, 13.0, 11.0, 12.0, 100),
+            HistoricalData::new("1/6/2020 16:00:00", 15.0, 16.0, 14.0, 16.0, 100),
+            HistoricalData::new("1/7/2020 16:00:00", 20.0, 21.0, 19.0, 20.0, 100),
+        ]);
+        return s;
+    }
+
+    #[test]
+    fn test_run_no_orders() {
+        let s = build_stock();
+        let result = run(&s, &mut from_fn(|_, _| Vec::new()), &BacktestConfig::new(1000.0, Fill::Close));
+        assert_eq!(result.get_trades().len(), 0);
+        assert_eq!(result.get_equity_curve().len(), 4);
+        assert_eq!(result.get_final_equity(), 1000.0);
+    }
+
+    #[test]
+    fn test_run_fill_close() {
+        let s = build_stock();
+        let result = run(&s, &mut from_fn(|_, ctx| {
+            match ctx.get_index() {
+                0 => vec![Order::Buy(10.0)],
+                2 => vec![Order::Sell(10.0)],
+                _ => Vec::new(),
+            }
+        }), &BacktestConfig::new(1000.0, Fill::Close));
+        let trades = result.get_trades();
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].get_side(), Side::Buy);
+        assert_eq!(trades[0].get_price(), 10.0);
+        assert_eq!(trades[1].get_side(), Side::Sell);
+        assert_eq!(trades[1].get_price(), 16.0);
+        assert_eq!(trades[1].get_pnl(), 60.0);
+        let curve = result.get_equity_curve();
+        assert_eq!(curve[0].get_equity(), 1000.0);
+        assert_eq!(curve[1].get_equity(), 1020.0);
+        assert_eq!(curve[1].get_position(), 10.0);
+        assert_eq!(result.get_final_equity(), 1060.0);
+    }
+
+    #[test]
+    fn test_run_fill_next_open() {
+        let s = build_stock();
+        let result = run(&s, &mut from_fn(|_, ctx| {
+            match ctx.get_index() {
+                0 => vec![Order::Buy(10.0)],
+                2 => vec![Order::Sell(10.0)],
+                _ => Vec::new(),
+            }
+        }), &BacktestConfig::new(1000.0, Fill::NextOpen));
+        let trades = result.get_trades();
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].get_price(), 12.0);
+        assert_eq!(trades[0].get_date().to_string(), "1/3/2020 16:00:00");
+        assert_eq!(trades[1].get_price(), 20.0);
+        assert_eq!(trades[1].get_pnl(), 80.0);
+        assert_eq!(result.get_final_equity(), 1080.0);
+    }
+
+    #[test]
+    fn test_run_target_percent() {
+        let s = build_stock();
+        let result = run(&s, &mut from_fn(|_, ctx| {
+            if ctx.get_index() == 0 { vec![Order::TargetPercent(1.0)] } else { Vec::new() }
+        }), &BacktestConfig::new(1000.0, Fill::Close));
+        assert_eq!(result.get_trades()[0].get_quantity(), 100.0);
+        assert_eq!(result.get_final_equity(), 2000.0);
+    }
+
+    #[test]
+    fn test_run_limits_to_cash_and_position() {
+        let s = build_stock();
+        let result = run(&s, &mut from_fn(|_, ctx| {
+            match ctx.get_index() {
+                0 => vec![Order::Buy(1000.0)],
+                1 => vec![Order::Sell(1000.0)],
+                _ => Vec::new(),
+            }
+        }), &BacktestConfig::new(100.0, Fill::Close));
+        let trades = result.get_trades();
+        assert_eq!(trades[0].get_quantity(), 10.0);
+        assert_eq!(trades[1].get_quantity(), 10.0);
+        assert_eq!(result.get_final_equity(), 120.0);
+    }
+
+    #[test]
+    fn test_run_lifecycle_hooks() {
+        struct Recorder {
+            started: bool,
+            bars: usize,
+            final_equity: f64,
+        }
+        impl Strategy for Recorder {
+            fn on_start(&mut self, _config: &BacktestConfig) { self.started = true; }
+            fn on_bar(&mut self, _bar: &HistoricalData, _ctx: &Context) -> Vec<Order> {
+                self.bars += 1;
+                return vec![Order::TargetPercent(1.0)];
+            }
+            fn on_finish(&mut self, result: &BacktestResult) { self.final_equity = result.get_final_equity(); }
+        }
+
+        let s = build_stock();
+        let mut strategy = Recorder { started: false, bars: 0, final_equity: 0.0 };
+        run(&s, &mut strategy, &BacktestConfig::new(1000.0, Fill::NextOpen));
+        assert_eq!(strategy.started, true);
+        assert_eq!(strategy.bars, 4);
+        assert_eq!(strategy.final_equity, 1666.6666666666665);
+    }
+
+    #[test]
+    fn test_run_commission() {
+        let s = build_stock();
+        let mut config = BacktestConfig::new(1000.0, Fill::Close);
+        config.set_commission(0.25);
+        let result = run(&s, &mut from_fn(|_, ctx| {
+            match ctx.get_index() {
+                0 => vec![Order::Buy(10.0)],
+                1 => vec![Order::Sell(10.0)],
+                _ => Vec::new(),
+            }
+        }), &config);
+        let trades = result.get_trades();
+        assert_eq!(trades[0].get_commission(), 25.0);
+        assert_eq!(trades[1].get_commission(), 30.0);
+        assert_eq!(trades[1].get_pnl(), -35.0);
+        assert_eq!(result.get_final_equity(), 965.0);
+    }
+
+    #[test]
+    fn test_run_adjusted_prices() {
+        let mut s = build_stock();
+        s.add_split(crate::stock::actions::Split::new("1/6/2020 00:00:00", 2.0));
+        let mut config = BacktestConfig::new(1000.0, Fill::Close);
+        config.set_adjustment(Some(Adjustment::Splits));
+        let result = run(&s, &mut from_fn(|_, ctx| {
+            match ctx.get_index() {
+                0 => vec![Order::Buy(20.0)],
+                _ => Vec::new(),
+            }
+        }), &config);
+        let trades = result.get_trades();
+        assert_eq!(trades[0].get_price(), 5.0);
+        // Without adjustment the split would show up as a loss
+        assert_eq!(result.get_final_equity(), 1300.0);
+    }
+
+    #[test]
+    fn test_run_credit_dividends() {
+        let mut s = build_stock();
+        s.add_dividend(crate::stock::actions::Dividend::new("1/2/2020 00:00:00", 5.0));
+        s.add_dividend(crate::stock::actions::Dividend::new("1/3/2020 00:00:00", 0.5));
+        s.add_dividend(crate::stock::actions::Dividend::new("1/5/2020 00:00:00", 0.25));
+        let mut config = BacktestConfig::new(1000.0, Fill::NextOpen);
+        config.set_credit_dividends(true);
+        let mut strategy = from_fn(|_, ctx| {
+            match ctx.get_index() {
+                0 => vec![Order::Buy(10.0)],
+                _ => Vec::new(),
+            }
+        });
+        // Bought at the open of 1/3, after that day's ex-date
+        let result = run(&s, &mut strategy, &config);
+        assert_eq!(result.get_dividends(), 2.5);
+        assert_eq!(result.get_equity_curve()[1].get_cash(), 880.0);
+        assert_eq!(result.get_equity_curve()[2].get_cash(), 882.5);
+        assert_eq!(result.get_final_equity(), 1082.5);
+
+        let result = run(&s, &mut strategy, &BacktestConfig::new(1000.0, Fill::NextOpen));
+        assert_eq!(result.get_dividends(), 0.0);
+        assert_eq!(result.get_final_equity(), 1080.0);
+    }
+
+    #[test]
+    fn test_run_credit_dividends_before_split() {
+        let mut s = build_stock();
+        s.add_split(crate::stock::actions::Split::new("1/6/2020 00:00:00", 2.0));
+        s.add_dividend(crate::stock::actions::Dividend::new("1/3/2020 00:00:00", 1.0));
+        let mut config = BacktestConfig::new(1000.0, Fill::Close);
+        config.set_adjustment(Some(Adjustment::Splits));
+        config.set_credit_dividends(true);
+        let mut strategy = from_fn(|_, ctx| {
+            match ctx.get_index() {
+                0 => vec![Order::Buy(20.0)],
+                _ => Vec::new(),
+            }
+        });
+        // 20 adjusted shares are 10 shares when the dividend is paid
+        let result = run(&s, &mut strategy, &config);
+        assert_eq!(result.get_dividends(), 10.0);
+        assert_eq!(result.get_final_equity(), 1310.0);
+
+        // Prices adjusted for dividends already include them
+        config.set_adjustment(Some(Adjustment::SplitsAndDividends));
+        let result = run(&s, &mut strategy, &config);
+        assert_eq!(result.get_dividends(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_run_from_provider() {
+        let mut provider = crate::stock::provider::MemoryProvider::new();
+        provider.insert_history("test", build_stock().get_historical_data().clone());
+        provider.insert_fundamentals("test", crate::stock::load::Fundamentals::new());
+        let range = DateRange::new(DateTime::new("1/3/2020"), DateTime::new("1/31/2020"));
+        let mut strategy = from_fn(|_, ctx| {
+            match ctx.get_index() {
+                0 => vec![Order::Buy(10.0)],
+                _ => Vec::new(),
+            }
+        });
+        let result = run_from(&provider, "test", &range, &mut strategy, &BacktestConfig::new(1000.0, Fill::Close)).await.unwrap();
+        assert_eq!(result.get_equity_curve().len(), 3);
+        assert_eq!(result.get_final_equity(), 1080.0);
+
+        let error = run_from(&provider, "other", &range, &mut strategy, &BacktestConfig::default()).await.unwrap_err();
+        assert_eq!(error, ProviderError::NotFound { ticker: "other".to_string() });
+    }
+}
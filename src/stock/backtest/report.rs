@@ -0,0 +1,381 @@
+use crate::json::{serialize::{JSON, JSONObject}, pretty_print::pretty_print};
+use crate::stock::backtest::{BacktestResult, Side};
+
+/// Number of trading sessions in a typical year.
+pub const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+
+/// Performance analytics computed from a `BacktestResult`.
+///
+/// All ratios assume a risk-free rate of 0. Returns, volatility, drawdown,
+/// exposure and win rate are fractions (`0.25` = 25%).
+///
+/// - `cagr`: compound annual growth rate of equity
+/// - `volatility`: annualized standard deviation of per-bar returns
+/// - `sharpe`: annualized mean return divided by volatility
+/// - `sortino`: annualized mean return divided by downside deviation
+/// - `max_drawdown`: largest peak-to-trough decline of equity
+/// - `max_drawdown_duration`: longest number of bars spent below a prior peak
+/// - `calmar`: `cagr` divided by `max_drawdown`
+/// - `win_rate`: fraction of closing trades with a positive P/L
+/// - `profit_factor`: gross profit divided by gross loss, `None` when there
+///   are winning but no losing trades (written as `null` in JSON and `n/a`
+///   in text)
+/// - `average_win`/`average_loss`: mean P/L of winning/losing closing trades
+/// - `exposure`: fraction of bars with an open position
+/// - `turnover`: annualized traded notional divided by average equity
+///
+/// ### Example
+/// ```
+/// let report = BacktestReport::new(&result);
+/// println!("{}", report.to_text());
+/// println!("{}", report.to_json());
+/// ```
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct BacktestReport {
+    ticker: String,
+    initial_equity: f64,
+    final_equity: f64,
+    total_return: f64,
+    cagr: f64,
+    volatility: f64,
+    sharpe: f64,
+    sortino: f64,
+    max_drawdown: f64,
+    max_drawdown_duration: usize,
+    calmar: f64,
+    trades: usize,
+    win_rate: f64,
+    profit_factor: Option<f64>,
+    average_win: f64,
+    average_loss: f64,
+    exposure: f64,
+    turnover: f64,
+}
+
+#[allow(dead_code)]
+impl BacktestReport {
+    /// Builds a report assuming `TRADING_DAYS_PER_YEAR` bars per year.
+    pub fn new(result: &BacktestResult) -> Self {
+        return Self::with_periods_per_year(result, TRADING_DAYS_PER_YEAR);
+    }
+
+    /// Builds a report annualizing with `periods_per_year` bars per year.
+    pub fn with_periods_per_year(result: &BacktestResult, periods_per_year: f64) -> Self {
+        let initial_equity = result.get_initial_cash();
+        let final_equity = result.get_final_equity();
+        let curve: Vec<f64> = result.get_equity_curve().iter().map(|p| p.get_equity()).collect();
+
+        // Per-bar returns, starting from the initial equity
+        let mut returns: Vec<f64> = Vec::new();
+        let mut prev = initial_equity;
+        for &equity in curve.iter() {
+            if prev != 0.0 { returns.push(equity / prev - 1.0); }
+            prev = equity;
+        }
+
+        let years = curve.len() as f64 / periods_per_year;
+        let total_return = if initial_equity != 0.0 { final_equity / initial_equity - 1.0 } else { 0.0 };
+        let cagr = if years > 0.0 && initial_equity > 0.0 && final_equity > 0.0 {
+            (final_equity / initial_equity).powf(1.0 / years) - 1.0
+        } else { 0.0 };
+
+        let mean_return = mean(&returns);
+        let volatility = std_dev(&returns) * periods_per_year.sqrt();
+        let downside = downside_dev(&returns) * periods_per_year.sqrt();
+        let sharpe = if volatility > 0.0 { mean_return * periods_per_year / volatility } else { 0.0 };
+        let sortino = if downside > 0.0 { mean_return * periods_per_year / downside } else { 0.0 };
+
+        let (max_drawdown, max_drawdown_duration) = drawdown(initial_equity, &curve);
+        let calmar = if max_drawdown > 0.0 { cagr / max_drawdown } else { 0.0 };
+
+        // Closing trades determine the win/loss statistics
+        let pnls: Vec<f64> = result.get_trades().iter()
+            .filter(|t| t.get_side() == Side::Sell)
+            .map(|t| t.get_pnl())
+            .collect();
+        let wins: Vec<f64> = pnls.iter().filter(|&&p| p > 0.0).cloned().collect();
+        let losses: Vec<f64> = pnls.iter().filter(|&&p| p < 0.0).cloned().collect();
+        let gross_profit: f64 = wins.iter().sum();
+        let gross_loss: f64 = -losses.iter().sum::<f64>();
+        let win_rate = if !pnls.is_empty() { wins.len() as f64 / pnls.len() as f64 } else { 0.0 };
+        let profit_factor = if gross_loss > 0.0 {
+            Some(gross_profit / gross_loss)
+        } else if gross_profit > 0.0 {
+            None
+        } else { Some(0.0) };
+
+        let exposure = if !curve.is_empty() {
+            result.get_equity_curve().iter().filter(|p| p.get_position() > 0.0).count() as f64 / curve.len() as f64
+        } else { 0.0 };
+        let notional: f64 = result.get_trades().iter().map(|t| t.get_quantity() * t.get_price()).sum();
+        let average_equity = mean(&curve);
+        let turnover = if average_equity > 0.0 && years > 0.0 { notional / average_equity / years } else { 0.0 };
+
+        return Self {
+            ticker: result.get_ticker(),
+            initial_equity: initial_equity,
+            final_equity: final_equity,
+            total_return: total_return,
+            cagr: cagr,
+            volatility: volatility,
+            sharpe: sharpe,
+            sortino: sortino,
+            max_drawdown: max_drawdown,
+            max_drawdown_duration: max_drawdown_duration,
+            calmar: calmar,
+            trades: result.get_trades().len(),
+            win_rate: win_rate,
+            profit_factor: profit_factor,
+            average_win: mean(&wins),
+            average_loss: mean(&losses),
+            exposure: exposure,
+            turnover: turnover,
+        };
+    }
+
+    pub fn get_ticker(&self) -> String { return self.ticker.to_string(); }
+    pub fn get_initial_equity(&self) -> f64 { return self.initial_equity; }
+    pub fn get_final_equity(&self) -> f64 { return self.final_equity; }
+    pub fn get_total_return(&self) -> f64 { return self.total_return; }
+    pub fn get_cagr(&self) -> f64 { return self.cagr; }
+    pub fn get_volatility(&self) -> f64 { return self.volatility; }
+    pub fn get_sharpe(&self) -> f64 { return self.sharpe; }
+    pub fn get_sortino(&self) -> f64 { return self.sortino; }
+    pub fn get_max_drawdown(&self) -> f64 { return self.max_drawdown; }
+    pub fn get_max_drawdown_duration(&self) -> usize { return self.max_drawdown_duration; }
+    pub fn get_calmar(&self) -> f64 { return self.calmar; }
+    pub fn get_trades(&self) -> usize { return self.trades; }
+    pub fn get_win_rate(&self) -> f64 { return self.win_rate; }
+    pub fn get_profit_factor(&self) -> Option<f64> { return self.profit_factor; }
+    pub fn get_average_win(&self) -> f64 { return self.average_win; }
+    pub fn get_average_loss(&self) -> f64 { return self.average_loss; }
+    pub fn get_exposure(&self) -> f64 { return self.exposure; }
+    pub fn get_turnover(&self) -> f64 { return self.turnover; }
+
+    /// Converts the report into a `JSON` object.
+    pub fn to_json_object(&self) -> JSON {
+        let mut json = JSON::new();
+        json.push("ticker", JSONObject::String(self.ticker.to_string()));
+        json.push("initial_equity", JSONObject::Float(self.initial_equity));
+        json.push("final_equity", JSONObject::Float(self.final_equity));
+        json.push("total_return", JSONObject::Float(self.total_return));
+        json.push("cagr", JSONObject::Float(self.cagr));
+        json.push("volatility", JSONObject::Float(self.volatility));
+        json.push("sharpe", JSONObject::Float(self.sharpe));
+        json.push("sortino", JSONObject::Float(self.sortino));
+        json.push("max_drawdown", JSONObject::Float(self.max_drawdown));
+        json.push("max_drawdown_duration", JSONObject::Number(self.max_drawdown_duration as i64));
+        json.push("calmar", JSONObject::Float(self.calmar));
+        json.push("trades", JSONObject::Number(self.trades as i64));
+        json.push("win_rate", JSONObject::Float(self.win_rate));
+        json.push("profit_factor", match self.profit_factor {
+            Some(profit_factor) => JSONObject::Float(profit_factor),
+            None => JSONObject::Null(),
+        });
+        json.push("average_win", JSONObject::Float(self.average_win));
+        json.push("average_loss", JSONObject::Float(self.average_loss));
+        json.push("exposure", JSONObject::Float(self.exposure));
+        json.push("turnover", JSONObject::Float(self.turnover));
+        return json;
+    }
+
+    /// Renders the report as a compact JSON string.
+    pub fn to_json(&self) -> String {
+        return self.to_json_object().stringify();
+    }
+
+    /// Renders the report as an indented JSON string.
+    pub fn to_pretty_json(&self) -> String {
+        return pretty_print(&self.to_json());
+    }
+
+    /// Renders the report as a human readable table.
+    pub fn to_text(&self) -> String {
+        let mut output = "".to_owned();
+        output.push_str(&format!("Backtest report: {}\n", self.ticker));
+        output.push_str(&format!("{:<24}{:>14.2}\n", "Initial equity", self.initial_equity));
+        output.push_str(&format!("{:<24}{:>14.2}\n", "Final equity", self.final_equity));
+        output.push_str(&format!("{:<24}{:>13.2}%\n", "Total return", self.total_return * 100.0));
+        output.push_str(&format!("{:<24}{:>13.2}%\n", "CAGR", self.cagr * 100.0));
+        output.push_str(&format!("{:<24}{:>13.2}%\n", "Volatility", self.volatility * 100.0));
+        output.push_str(&format!("{:<24}{:>14.2}\n", "Sharpe", self.sharpe));
+        output.push_str(&format!("{:<24}{:>14.2}\n", "Sortino", self.sortino));
+        output.push_str(&format!("{:<24}{:>13.2}%\n", "Max drawdown", self.max_drawdown * 100.0));
+        output.push_str(&format!("{:<24}{:>9} bars\n", "Max drawdown duration", self.max_drawdown_duration));
+        output.push_str(&format!("{:<24}{:>14.2}\n", "Calmar", self.calmar));
+        output.push_str(&format!("{:<24}{:>14}\n", "Trades", self.trades));
+        output.push_str(&format!("{:<24}{:>13.2}%\n", "Win rate", self.win_rate * 100.0));
+        match self.profit_factor {
+            Some(profit_factor) => output.push_str(&format!("{:<24}{:>14.2}\n", "Profit factor", profit_factor)),
+            None => output.push_str(&format!("{:<24}{:>14}\n", "Profit factor", "n/a")),
+        };
+        output.push_str(&format!("{:<24}{:>14.2}\n", "Average win", self.average_win));
+        output.push_str(&format!("{:<24}{:>14.2}\n", "Average loss", self.average_loss));
+        output.push_str(&format!("{:<24}{:>13.2}%\n", "Exposure", self.exposure * 100.0));
+        output.push_str(&format!("{:<24}{:>14.2}\n", "Turnover", self.turnover));
+        return output;
+    }
+}
+
+
+fn mean(data: &[f64]) -> f64 {
+    if data.is_empty() { return 0.0; }
+    let sum: f64 = data.iter().sum();
+    return sum / data.len() as f64;
+}
+
+fn std_dev(data: &[f64]) -> f64 {
+    if data.len() < 2 { return 0.0; }
+    let m = mean(data);
+    let variance = data.iter().map(|d| (d - m) * (d - m)).sum::<f64>() / (data.len() - 1) as f64;
+    return variance.sqrt();
+}
+
+fn downside_dev(data: &[f64]) -> f64 {
+    if data.is_empty() { return 0.0; }
+    let variance = data.iter().map(|&d| if d < 0.0 { d * d } else { 0.0 }).sum::<f64>() / data.len() as f64;
+    return variance.sqrt();
+}
+
+/// Returns the maximum drawdown (as a fraction of the peak) and the longest
+/// number of bars spent below a prior peak.
+fn drawdown(initial_equity: f64, curve: &[f64]) -> (f64, usize) {
+    let mut peak = initial_equity;
+    let mut max_drawdown: f64 = 0.0;
+    let mut duration = 0;
+    let mut max_duration = 0;
+    for &equity in curve.iter() {
+        if equity >= peak {
+            peak = equity;
+            duration = 0;
+        } else {
+            duration += 1;
+            if peak > 0.0 { max_drawdown = max_drawdown.max((peak - equity) / peak); }
+        }
+        max_duration = max_duration.max(duration);
+    }
+    return (max_drawdown, max_duration);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stock::{Stock, historical::HistoricalData, backtest::{run, from_fn, BacktestConfig, Fill, Order}};
+
+    fn build_result(closes: &[f64], orders: Vec<(usize, Order)>) -> BacktestResult {
+        let mut s = Stock::new("test");
+        s.add_historical_data(closes.iter().enumerate().map(|(i, &c)| {
+            HistoricalData::new(&format!("1/{}/2020 16:00:00", i+1), c, c, c, c, 100)
+        }).collect());
+        return run(&s, &mut from_fn(|_, ctx| {
+            orders.iter().filter(|(i, _)| *i == ctx.get_index()).map(|(_, o)| *o).collect()
+        }), &BacktestConfig::new(1000.0, Fill::Close));
+    }
+
+    #[test]
+    fn test_report_no_trades() {
+        let result = build_result(&[10.0, 11.0, 12.0], Vec::new());
+        let report = BacktestReport::new(&result);
+        assert_eq!(report.get_total_return(), 0.0);
+        assert_eq!(report.get_cagr(), 0.0);
+        assert_eq!(report.get_volatility(), 0.0);
+        assert_eq!(report.get_sharpe(), 0.0);
+        assert_eq!(report.get_max_drawdown(), 0.0);
+        assert_eq!(report.get_trades(), 0);
+        assert_eq!(report.get_win_rate(), 0.0);
+        assert_eq!(report.get_profit_factor(), Some(0.0));
+        assert_eq!(report.get_exposure(), 0.0);
+    }
+
+    #[test]
+    fn test_report_drawdown() {
+        // 1000 -> 2000 -> 1000 -> 1500 -> 2500 while fully invested
+        let result = build_result(&[10.0, 20.0, 10.0, 15.0, 25.0], vec![(0, Order::Buy(100.0))]);
+        let report = BacktestReport::new(&result);
+        assert_eq!(report.get_total_return(), 1.5);
+        assert_eq!(report.get_max_drawdown(), 0.5);
+        assert_eq!(report.get_max_drawdown_duration(), 2);
+        assert_eq!(report.get_exposure(), 1.0);
+    }
+
+    #[test]
+    fn test_report_cagr() {
+        // Doubling over two "years" of two bars each
+        let result = build_result(&[10.0, 10.0, 10.0, 20.0], vec![(0, Order::Buy(100.0))]);
+        let report = BacktestReport::with_periods_per_year(&result, 2.0);
+        assert_eq!(report.get_cagr(), 0.41421356237309515);
+        assert_eq!(report.get_max_drawdown(), 0.0);
+        assert_eq!(report.get_calmar(), 0.0);
+    }
+
+    #[test]
+    fn test_report_trade_statistics() {
+        let result = build_result(
+            &[10.0, 12.0, 10.0, 8.0, 10.0, 15.0],
+            vec![
+                (0, Order::Buy(10.0)), (1, Order::Sell(10.0)),
+                (2, Order::Buy(10.0)), (3, Order::Sell(10.0)),
+                (4, Order::Buy(10.0)), (5, Order::Sell(10.0)),
+            ]
+        );
+        let report = BacktestReport::new(&result);
+        assert_eq!(report.get_trades(), 6);
+        assert_eq!(report.get_win_rate(), 2.0 / 3.0);
+        assert_eq!(report.get_profit_factor(), Some(3.5));
+        assert_eq!(report.get_average_win(), 35.0);
+        assert_eq!(report.get_average_loss(), -20.0);
+        assert_eq!(report.get_exposure(), 0.5);
+    }
+
+    #[test]
+    fn test_report_all_winners() {
+        let result = build_result(
+            &[10.0, 12.0, 10.0, 15.0],
+            vec![
+                (0, Order::Buy(10.0)), (1, Order::Sell(10.0)),
+                (2, Order::Buy(10.0)), (3, Order::Sell(10.0)),
+            ]
+        );
+        let report = BacktestReport::new(&result);
+        assert_eq!(report.get_win_rate(), 1.0);
+        assert_eq!(report.get_profit_factor(), None);
+        assert!(report.to_json().contains("\"profit_factor\":null,"));
+        assert!(report.to_text().contains("Profit factor                      n/a\n"));
+    }
+
+    #[test]
+    fn test_report_volatility() {
+        let result = build_result(&[10.0, 11.0, 9.9, 10.89], vec![(0, Order::Buy(100.0))]);
+        let report = BacktestReport::with_periods_per_year(&result, 1.0);
+        assert!(report.get_volatility() > 0.0);
+        assert!(report.get_sortino() > report.get_sharpe());
+    }
+
+    #[test]
+    fn test_report_to_json() {
+        let result = build_result(&[10.0, 20.0], vec![(0, Order::Buy(100.0)), (1, Order::Sell(100.0))]);
+        let json = BacktestReport::new(&result).to_json();
+        assert!(json.starts_with("{\"ticker\":\"test\",\"initial_equity\":1000,\"final_equity\":2000,\"total_return\":1,"));
+        assert!(json.contains("\"trades\":2,\"win_rate\":1,\"profit_factor\":null,"));
+        assert!(json.ends_with("}"));
+    }
+
+    #[test]
+    fn test_report_to_pretty_json_quoted_ticker() {
+        let mut s = Stock::new("say \"hi\", {ok}");
+        s.add_historical_data(vec![HistoricalData::new("1/1/2020 16:00:00", 10.0, 10.0, 10.0, 10.0, 100)]);
+        let result = run(&s, &mut from_fn(|_, _| Vec::new()), &BacktestConfig::new(1000.0, Fill::Close));
+        let json = BacktestReport::new(&result).to_pretty_json();
+        assert!(json.starts_with("{\n\t\"ticker\": \"say \\\"hi\\\", {ok}\",\n\t\"initial_equity\": 1000,\n"), "{}", json);
+    }
+
+    #[test]
+    fn test_report_to_text() {
+        let result = build_result(&[10.0, 20.0], vec![(0, Order::Buy(100.0))]);
+        let text = BacktestReport::new(&result).to_text();
+        assert!(text.starts_with("Backtest report: test\n"));
+        assert!(text.contains("Total return                   100.00%\n"));
+    }
+}
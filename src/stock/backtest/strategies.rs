@@ -0,0 +1,180 @@
+use crate::stock::{historical::HistoricalData, ta::{sma::SmaState, rsi::{RsiState, RsiParams}}};
+use crate::stock::backtest::{Strategy, BacktestConfig, Context, Order};
+
+/// Golden cross / death cross trend following strategy.
+///
+/// Goes fully long when the fast SMA crosses above the slow SMA (golden
+/// cross) and exits when the fast SMA crosses back below the slow SMA
+/// (death cross). Defaults to the 50-day and 200-day SMAs.
+#[derive(Debug)]
+pub struct GoldenCross {
+    fast: usize,
+    slow: usize,
+    fast_sma: SmaState,
+    slow_sma: SmaState,
+    fast_above: Option<bool>,
+}
+
+#[allow(dead_code)]
+impl GoldenCross {
+    pub fn new(fast: usize, slow: usize) -> Self {
+        return Self {
+            fast: fast,
+            slow: slow,
+            fast_sma: SmaState::new(fast),
+            slow_sma: SmaState::new(slow),
+            fast_above: None,
+        };
+    }
+
+    pub fn get_fast(&self) -> usize { return self.fast; }
+    pub fn get_slow(&self) -> usize { return self.slow; }
+}
+
+impl Default for GoldenCross {
+    fn default() -> Self {
+        return Self::new(50, 200);
+    }
+}
+
+impl Strategy for GoldenCross {
+    fn on_start(&mut self, _config: &BacktestConfig) {
+        self.fast_sma = SmaState::new(self.fast);
+        self.slow_sma = SmaState::new(self.slow);
+        self.fast_above = None;
+    }
+
+    fn on_bar(&mut self, bar: &HistoricalData, _ctx: &Context) -> Vec<Order> {
+        let fast_sma = self.fast_sma.update(bar.get_close());
+        let slow_sma = self.slow_sma.update(bar.get_close());
+        let fast_above = match (fast_sma, slow_sma) {
+            (Some(fast_sma), Some(slow_sma)) => fast_sma > slow_sma,
+            _ => return Vec::new(),
+        };
+
+        let mut orders: Vec<Order> = Vec::new();
+        match self.fast_above {
+            Some(false) if fast_above => orders.push(Order::TargetPercent(1.0)),
+            Some(true) if !fast_above => orders.push(Order::TargetPercent(0.0)),
+            _ => {},
+        };
+        self.fast_above = Some(fast_above);
+        return orders;
+    }
+}
+
+/// RSI mean-reversion strategy.
+///
+/// Goes fully long when the RSI drops below the oversold threshold and
+/// exits when the RSI rises above the overbought threshold. Defaults to
+/// the 30/70 thresholds.
+#[derive(Debug)]
+pub struct RsiMeanReversion {
+    oversold: f64,
+    overbought: f64,
+    rsi: RsiState,
+}
+
+#[allow(dead_code)]
+impl RsiMeanReversion {
+    pub fn new(oversold: f64, overbought: f64) -> Self {
+        return Self {
+            oversold: oversold,
+            overbought: overbought,
+            rsi: RsiState::new(&RsiParams::default()),
+        };
+    }
+
+    pub fn get_oversold(&self) -> f64 { return self.oversold; }
+    pub fn get_overbought(&self) -> f64 { return self.overbought; }
+}
+
+impl Default for RsiMeanReversion {
+    fn default() -> Self {
+        return Self::new(30.0, 70.0);
+    }
+}
+
+impl Strategy for RsiMeanReversion {
+    fn on_start(&mut self, _config: &BacktestConfig) {
+        self.rsi = RsiState::new(&RsiParams::default());
+    }
+
+    fn on_bar(&mut self, bar: &HistoricalData, ctx: &Context) -> Vec<Order> {
+        let rsi = match self.rsi.update(bar.get_close()) {
+            Some(v) => v,
+            None => return Vec::new(),
+        };
+
+        if rsi < self.oversold && ctx.get_position() == 0.0 {
+            return vec![Order::TargetPercent(1.0)];
+        }
+        if rsi > self.overbought && ctx.get_position() > 0.0 {
+            return vec![Order::TargetPercent(0.0)];
+        }
+        return Vec::new();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stock::{Stock, datetime::DateTime, backtest::{run, BacktestConfig, Fill, Side}};
+
+    fn build_stock(closes: &[f64]) -> Stock {
+        let mut s = Stock::new("test");
+        let start = DateTime::new("1/1/2020 16:00:00");
+        s.add_historical_data(closes.iter().enumerate().map(|(i, &c)| {
+            HistoricalData::new(&start.add_days(i as i64).to_string(), c, c, c, c, 100)
+        }).collect());
+        return s;
+    }
+
+    #[test]
+    fn test_golden_cross() {
+        let s = build_stock(&[10.0, 9.0, 8.0, 7.0, 6.0, 7.0, 9.0, 12.0, 15.0, 18.0, 14.0, 10.0, 6.0]);
+        let mut strategy = GoldenCross::new(2, 4);
+        let result = run(&s, &mut strategy, &BacktestConfig::new(900.0, Fill::Close));
+        let trades = result.get_trades();
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].get_side(), Side::Buy);
+        assert_eq!(trades[0].get_price(), 9.0);
+        assert_eq!(trades[1].get_side(), Side::Sell);
+        assert_eq!(trades[1].get_price(), 10.0);
+        assert_eq!(result.get_final_equity(), 1000.0);
+    }
+
+    #[test]
+    fn test_golden_cross_reused() {
+        let s = build_stock(&[10.0, 9.0, 8.0, 7.0, 6.0, 7.0, 9.0, 12.0, 15.0, 18.0, 14.0, 10.0, 6.0]);
+        let mut strategy = GoldenCross::new(2, 4);
+        let first = run(&s, &mut strategy, &BacktestConfig::new(900.0, Fill::Close));
+        let second = run(&s, &mut strategy, &BacktestConfig::new(900.0, Fill::Close));
+        assert_eq!(first.get_final_equity(), second.get_final_equity());
+        assert_eq!(first.get_trades().len(), second.get_trades().len());
+    }
+
+    #[test]
+    fn test_golden_cross_not_enough_history() {
+        let s = build_stock(&[10.0, 11.0, 12.0]);
+        let result = run(&s, &mut GoldenCross::default(), &BacktestConfig::new(1000.0, Fill::Close));
+        assert_eq!(result.get_trades().len(), 0);
+    }
+
+    #[test]
+    fn test_rsi_mean_reversion() {
+        // Steady decline to an RSI of 0, followed by a steady rally
+        let mut closes: Vec<f64> = (16..31).rev().map(|v| v as f64).collect();
+        closes.extend((17..41).map(|v| v as f64));
+        let s = build_stock(&closes);
+        let result = run(&s, &mut RsiMeanReversion::default(), &BacktestConfig::new(1600.0, Fill::Close));
+        let trades = result.get_trades();
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].get_side(), Side::Buy);
+        assert_eq!(trades[0].get_price(), 16.0);
+        assert_eq!(trades[1].get_side(), Side::Sell);
+        assert_eq!(trades[1].get_price(), 33.0);
+        assert_eq!(result.get_final_equity(), 3300.0);
+    }
+}
@@ -0,0 +1,371 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use crate::stock::datetime::DateTime;
+use crate::stock::historical::HistoricalData;
+use crate::stock::load::{self, BadRows, LoadError, LoadOptions, LoadReport, OhlcCheck, RowIssue};
+
+const MAGIC: &[u8; 4] = b"SWC\x01";
+const VERSION: u16 = 2;
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// Returns where the cache for `source` is stored in `directory`, i.e.
+/// `<directory>/<file name>.bin`.
+///
+/// ### Examples
+/// ```
+/// let path = cache::cache_path(Path::new("./cache"), Path::new("./src/assets/historical/ko.csv"));
+/// assert_eq!(path, PathBuf::from("./cache/ko.csv.bin"));
+/// ```
+pub fn cache_path(directory: &Path, source: &Path) -> PathBuf {
+    let name = source.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    return directory.join(format!("{}.bin", name));
+}
+
+/// Whether `cache` exists and was written after `source` was last
+/// modified.
+pub fn is_fresh(cache: &Path, source: &Path) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+    return match (modified(cache), modified(source)) {
+        (Some(cache), Some(source)) => cache >= source,
+        _ => false,
+    };
+}
+
+/// Writes historical data and the report it was loaded with to a binary
+/// cache file. The options are stored too, so that a cache built with
+/// different options is not reused.
+///
+/// The layout is little-endian and columnar:
+///
+/// - magic `SWC\x01`, version (u16), bad rows (u8), OHLC check (u8)
+/// - row count (u64)
+/// - epoch days (i32) and seconds of the day (u32) for every row
+/// - opens, highs, lows and closes (f64) for every row
+/// - volumes (u64) for every row
+/// - skipped then flagged rows: a count (u32), then the line (u64),
+///   reason length (u32) and UTF-8 reason of each
+///
+/// # Arguments
+///
+/// * `location` - Cache file to write, replaced if it exists
+/// * `data` - Historical data to store
+/// * `report` - Report of the load that produced `data`
+/// * `options` - Options the data was loaded with
+pub fn write(location: &Path, data: &[HistoricalData], report: &LoadReport, options: &LoadOptions) -> Result<(), LoadError> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(16 + data.len() * 48);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.push(options.get_bad_rows() as u8);
+    bytes.push(options.get_ohlc_check() as u8);
+    bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+
+    for bar in data {
+        bytes.extend_from_slice(&(bar.get_date().to_epoch_day() as i32).to_le_bytes());
+    }
+    for bar in data {
+        bytes.extend_from_slice(&bar.get_date().seconds_of_day().to_le_bytes());
+    }
+    for column in [HistoricalData::get_open, HistoricalData::get_high, HistoricalData::get_low, HistoricalData::get_close] {
+        for bar in data {
+            bytes.extend_from_slice(&column(bar).to_le_bytes());
+        }
+    }
+    for bar in data {
+        bytes.extend_from_slice(&bar.get_volume().to_le_bytes());
+    }
+    for issues in [report.get_skipped(), report.get_flagged()] {
+        bytes.extend_from_slice(&(issues.len() as u32).to_le_bytes());
+        for issue in issues {
+            let reason = issue.get_reason();
+            bytes.extend_from_slice(&(issue.get_line() as u64).to_le_bytes());
+            bytes.extend_from_slice(&(reason.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(reason.as_bytes());
+        }
+    }
+
+    return fs::write(location, bytes).map_err(|error| LoadError::Io {
+        path: location.to_string_lossy().to_string(),
+        reason: error.to_string(),
+    });
+}
+
+/// Reads a cache file written by `write`. Returns the data, the report it
+/// was loaded with and the options stored in the file.
+pub fn read(location: &Path) -> Result<(Vec<HistoricalData>, LoadReport, LoadOptions), LoadError> {
+    let path = location.to_string_lossy().to_string();
+    if !location.exists() {
+        return Err(LoadError::NotFound { path: path });
+    }
+    let bytes = match fs::read(location) {
+        Ok(bytes) => bytes,
+        Err(error) => return Err(LoadError::Io { path: path, reason: error.to_string() }),
+    };
+    return decode(&bytes).map_err(|reason| LoadError::InvalidCache { path: path, reason: reason.to_string() });
+}
+
+/// Reads fixed-size values from the front of a byte slice.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], &'static str> {
+        if self.bytes.len() < N {
+            return Err("unexpected end of file");
+        }
+        let (head, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+        return Ok(head.try_into().unwrap());
+    }
+
+    fn take_slice(&mut self, len: usize) -> Result<&'a [u8], &'static str> {
+        if self.bytes.len() < len {
+            return Err("unexpected end of file");
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        return Ok(head);
+    }
+}
+
+fn decode(bytes: &[u8]) -> Result<(Vec<HistoricalData>, LoadReport, LoadOptions), &'static str> {
+    let mut reader = Reader { bytes: bytes };
+    if &reader.take::<4>()? != MAGIC {
+        return Err("not a historical data cache");
+    }
+    if u16::from_le_bytes(reader.take()?) != VERSION {
+        return Err("unsupported version");
+    }
+    let bad_rows = match reader.take::<1>()?[0] {
+        0 => BadRows::Skip,
+        1 => BadRows::Error,
+        _ => return Err("unknown bad rows option"),
+    };
+    let ohlc_check = match reader.take::<1>()?[0] {
+        0 => OhlcCheck::Ignore,
+        1 => OhlcCheck::Flag,
+        2 => OhlcCheck::Reject,
+        _ => return Err("unknown OHLC check option"),
+    };
+    let rows = u64::from_le_bytes(reader.take()?) as usize;
+    // Every row takes 48 bytes, checked up front so a corrupt count does
+    // not allocate
+    if rows > reader.bytes.len() / 48 {
+        return Err("unexpected end of file");
+    }
+
+    let mut days = Vec::with_capacity(rows);
+    for _ in 0..rows {
+        days.push(i32::from_le_bytes(reader.take()?) as i64);
+    }
+    let mut seconds = Vec::with_capacity(rows);
+    for _ in 0..rows {
+        seconds.push(u32::from_le_bytes(reader.take()?) as i64);
+    }
+    let mut prices: Vec<Vec<f64>> = Vec::with_capacity(4);
+    for _ in 0..4 {
+        let mut column = Vec::with_capacity(rows);
+        for _ in 0..rows {
+            column.push(f64::from_le_bytes(reader.take()?));
+        }
+        prices.push(column);
+    }
+    let mut data = Vec::with_capacity(rows);
+    for i in 0..rows {
+        let volume = u64::from_le_bytes(reader.take()?);
+        if seconds[i] >= SECONDS_PER_DAY {
+            return Err("invalid time of day");
+        }
+        let date = DateTime::try_from_unix_timestamp(days[i] * SECONDS_PER_DAY + seconds[i]).map_err(|_| "invalid date")?;
+        data.push(HistoricalData::from_date(
+            date,
+            prices[0][i],
+            prices[1][i],
+            prices[2][i],
+            prices[3][i],
+            volume,
+        ));
+    }
+
+    let mut issues: Vec<Vec<RowIssue>> = Vec::with_capacity(2);
+    for _ in 0..2 {
+        let count = u32::from_le_bytes(reader.take()?) as usize;
+        let mut list = Vec::new();
+        for _ in 0..count {
+            let line = u64::from_le_bytes(reader.take()?) as usize;
+            let len = u32::from_le_bytes(reader.take()?) as usize;
+            let reason = std::str::from_utf8(reader.take_slice(len)?).map_err(|_| "invalid reason")?;
+            list.push(RowIssue::new(line, reason));
+        }
+        issues.push(list);
+    }
+    if !reader.bytes.is_empty() {
+        return Err("unexpected data at the end of the file");
+    }
+
+    let flagged = issues.pop().unwrap();
+    let skipped = issues.pop().unwrap();
+    let report = LoadReport::new(data.len(), skipped, flagged);
+    return Ok((data, report, LoadOptions::new(bad_rows, ohlc_check)));
+}
+
+/// Loads historical data from `source` through a cache in `directory`.
+///
+/// The cache is used when it is newer than `source` and was built with
+/// the same options. Otherwise `source` is parsed and the cache is
+/// rebuilt, creating `directory` if needed. A cache that cannot be read is
+/// rebuilt, and a cache that cannot be written is ignored, so the cache
+/// never makes a load fail that would succeed without it.
+///
+/// # Arguments
+///
+/// * `source` - CSV file to load
+/// * `directory` - Directory holding the cache files
+/// * `options` - How to handle bad rows and inconsistent bars
+///
+/// ### Examples
+/// ```
+/// let (data, report) = cache::load_historical(Path::new("./src/assets/historical/ko.csv"), Path::new("./cache"), &LoadOptions::default()).unwrap();
+/// ```
+pub fn load_historical(source: &Path, directory: &Path, options: &LoadOptions) -> Result<(Vec<HistoricalData>, LoadReport), LoadError> {
+    let location = cache_path(directory, source);
+    if is_fresh(&location, source) {
+        if let Ok((data, report, cached)) = read(&location) {
+            if cached.get_bad_rows() == options.get_bad_rows() && cached.get_ohlc_check() == options.get_ohlc_check() {
+                return Ok((data, report));
+            }
+        }
+    }
+
+    let content = load::read_file(source)?;
+    let (data, report) = load::parse_historical(&content, options)?;
+    if fs::create_dir_all(directory).is_ok() {
+        let _ = write(&location, &data, &report, options);
+    }
+    return Ok((data, report));
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, remove_dir_all, File};
+    use std::time::{Duration, SystemTime};
+
+    /// Creates a fresh directory in the temp directory.
+    fn fixture(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("stockwatcher-cache-{}-{}", name, std::process::id()));
+        let _ = remove_dir_all(&root);
+        create_dir_all(&root).unwrap();
+        return root;
+    }
+
+    fn strings(data: &[HistoricalData]) -> Vec<String> {
+        return data.iter().map(|bar| bar.to_string()).collect();
+    }
+
+    const CSV: &str = "Date,Open,High,Low,Close,Volume\n\
+        1/2/2014 16:00:00,183.98,184.07,182.48,182.92,119636836\n\
+        1/3/2014,,,,,\n\
+        1/6/2014 09:30:15,183.47,183.56,182.08,184.0,108028139\n";
+
+    #[test]
+    fn test_write_and_read() {
+        let root = fixture("roundtrip");
+        let (data, report) = load::parse_historical(CSV, &LoadOptions::default()).unwrap();
+        let location = root.join("spy.csv.bin");
+        write(&location, &data, &report, &LoadOptions::default()).unwrap();
+
+        let (cached, cached_report, options) = read(&location).unwrap();
+        assert_eq!(strings(&cached), strings(&data));
+        assert_eq!(cached_report, report);
+        assert_eq!(cached_report.get_skipped()[0].get_line(), 3);
+        assert_eq!(cached_report.get_flagged()[0].get_line(), 4);
+        assert_eq!(options.get_bad_rows(), BadRows::Skip);
+        assert_eq!(options.get_ohlc_check(), OhlcCheck::Flag);
+        remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        let root = fixture("invalid");
+        let path = root.join("bad.bin");
+        let path_string = path.to_string_lossy().to_string();
+        fs::write(&path, "Date,Open\n").unwrap();
+        assert_eq!(read(&path).unwrap_err(), LoadError::InvalidCache { path: path_string.clone(), reason: "not a historical data cache".to_string() });
+
+        let (data, report) = load::parse_historical(CSV, &LoadOptions::default()).unwrap();
+        write(&path, &data, &report, &LoadOptions::default()).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert_eq!(read(&path).unwrap_err(), LoadError::InvalidCache { path: path_string.clone(), reason: "unexpected end of file".to_string() });
+        assert_eq!(read(&root.join("missing.bin")).unwrap_err(), LoadError::NotFound { path: root.join("missing.bin").to_string_lossy().to_string() });
+        remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_load_historical_rebuilds_corrupt_cache() {
+        let root = fixture("corrupt");
+        let source = root.join("spy.csv");
+        let directory = root.join("cache");
+        fs::write(&source, CSV).unwrap();
+        let (data, _) = load_historical(&source, &directory, &LoadOptions::default()).unwrap();
+
+        // Damage the first day, which starts right after the 16 byte header
+        let location = cache_path(&directory, &source);
+        let mut bytes = fs::read(&location).unwrap();
+        bytes[16..20].copy_from_slice(&i32::MAX.to_le_bytes());
+        fs::write(&location, &bytes).unwrap();
+        File::options().write(true).open(&source).unwrap().set_modified(SystemTime::UNIX_EPOCH).unwrap();
+        assert_eq!(read(&location).unwrap_err(), LoadError::InvalidCache { path: location.to_string_lossy().to_string(), reason: "invalid date".to_string() });
+
+        let (loaded, _) = load_historical(&source, &directory, &LoadOptions::default()).unwrap();
+        assert_eq!(strings(&loaded), strings(&data));
+        assert_eq!(read(&location).is_ok(), true);
+
+        // So does a time of day past midnight
+        let mut bytes = fs::read(&location).unwrap();
+        bytes[24..28].copy_from_slice(&(SECONDS_PER_DAY as u32).to_le_bytes());
+        fs::write(&location, &bytes).unwrap();
+        File::options().write(true).open(&source).unwrap().set_modified(SystemTime::UNIX_EPOCH).unwrap();
+        assert_eq!(read(&location).unwrap_err(), LoadError::InvalidCache { path: location.to_string_lossy().to_string(), reason: "invalid time of day".to_string() });
+        let (loaded, _) = load_historical(&source, &directory, &LoadOptions::default()).unwrap();
+        assert_eq!(strings(&loaded), strings(&data));
+        remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_load_historical_rebuilds_stale_cache() {
+        let root = fixture("stale");
+        let source = root.join("spy.csv");
+        let directory = root.join("cache");
+        fs::write(&source, CSV).unwrap();
+
+        let (data, _) = load_historical(&source, &directory, &LoadOptions::default()).unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(is_fresh(&cache_path(&directory, &source), &source), true);
+
+        // A cache hit never reads the source
+        let stored = fs::read(&source).unwrap();
+        fs::write(&source, "not csv\"").unwrap();
+        File::options().write(true).open(&source).unwrap().set_modified(SystemTime::UNIX_EPOCH).unwrap();
+        let (cached, report) = load_historical(&source, &directory, &LoadOptions::default()).unwrap();
+        assert_eq!(strings(&cached), strings(&data));
+        assert_eq!(report.get_skipped().len(), 1);
+
+        // Editing the source invalidates the cache
+        fs::write(&source, [&stored[..], b"1/7/2014 16:00:00,183.09,183.79,182.95,183.48,86144169\n"].concat()).unwrap();
+        File::options().write(true).open(&source).unwrap().set_modified(SystemTime::now() + Duration::from_secs(60)).unwrap();
+        assert_eq!(is_fresh(&cache_path(&directory, &source), &source), false);
+        let (data, _) = load_historical(&source, &directory, &LoadOptions::default()).unwrap();
+        assert_eq!(data.len(), 3);
+
+        // So do different options
+        let options = LoadOptions::new(BadRows::Skip, OhlcCheck::Reject);
+        File::options().write(true).open(&source).unwrap().set_modified(SystemTime::UNIX_EPOCH).unwrap();
+        let (data, report) = load_historical(&source, &directory, &options).unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(report.get_skipped().len(), 2);
+        remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,436 @@
+use crate::stock::datetime::{DateTime, Weekday, nth_weekday_of_month, last_weekday_of_month};
+use crate::stock::exchange::{Exchange, Session};
+use crate::stock::historical::HistoricalData;
+
+/// Regular close on early close days, 1:00 PM New York time, in seconds
+/// since midnight.
+#[allow(dead_code)]
+pub const EARLY_CLOSE: u32 = 13 * 3600;
+
+/// Reasons the NYSE/NASDAQ can be closed on a weekday.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum Holiday {
+    NewYearsDay,
+    MartinLutherKingJrDay,
+    WashingtonsBirthday,
+    GoodFriday,
+    MemorialDay,
+    Juneteenth,
+    IndependenceDay,
+    LaborDay,
+    ElectionDay,
+    Thanksgiving,
+    Christmas,
+    /// One-off closures such as national days of mourning or weather
+    /// events.
+    Special(&'static str),
+}
+
+/// One-off market closures that do not follow any holiday rule.
+const SPECIAL_CLOSURES: [(u16, u8, u8, &str); 21] = [
+    (1963, 11, 25, "Funeral of President John F. Kennedy"),
+    (1968, 4, 9, "Day of mourning for Martin Luther King Jr."),
+    (1968, 7, 5, "Paperwork crisis"),
+    (1969, 2, 10, "Snowstorm"),
+    (1969, 3, 31, "Funeral of President Dwight D. Eisenhower"),
+    (1969, 7, 21, "Apollo 11 moon landing"),
+    (1972, 12, 28, "Funeral of President Harry S. Truman"),
+    (1973, 1, 25, "Funeral of President Lyndon B. Johnson"),
+    (1977, 7, 14, "New York City blackout"),
+    (1985, 9, 27, "Hurricane Gloria"),
+    (1994, 4, 27, "Funeral of President Richard Nixon"),
+    (2001, 9, 11, "September 11 attacks"),
+    (2001, 9, 12, "September 11 attacks"),
+    (2001, 9, 13, "September 11 attacks"),
+    (2001, 9, 14, "September 11 attacks"),
+    (2004, 6, 11, "Funeral of President Ronald Reagan"),
+    (2007, 1, 2, "Funeral of President Gerald Ford"),
+    (2012, 10, 29, "Hurricane Sandy"),
+    (2012, 10, 30, "Hurricane Sandy"),
+    (2018, 12, 5, "Funeral of President George H.W. Bush"),
+    (2025, 1, 9, "Funeral of President Jimmy Carter"),
+];
+
+/// Returns Easter Sunday for the given year.
+///
+/// #### Resources
+/// - [Anonymous Gregorian algorithm](https://en.wikipedia.org/wiki/Date_of_Easter#Anonymous_Gregorian_algorithm)
+pub fn easter_sunday(year: u16) -> DateTime {
+    let y = year as i32;
+    let a = y % 19;
+    let b = y / 100;
+    let c = y % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    return DateTime::from_ymd_hms(year, month as u8, day as u8, 0, 0, 0);
+}
+
+/// Moves a fixed-date holiday that lands on a weekend to the adjacent
+/// weekday: Saturday to Friday and Sunday to Monday.
+fn observed(year: u16, month: u8, day: u8) -> DateTime {
+    let d = DateTime::from_ymd_hms(year, month, day, 0, 0, 0);
+    return match d.weekday() {
+        Weekday::Saturday => d.sub_days(1),
+        Weekday::Sunday => d.add_days(1),
+        _ => d,
+    };
+}
+
+/// Returns every rule-based holiday observed in the given year, in no
+/// particular order.
+fn holidays_in_year(year: u16) -> Vec<(DateTime, Holiday)> {
+    let mut holidays = Vec::new();
+
+    // New Year's Day is not moved back into the previous year when it falls
+    // on a Saturday
+    let new_years = DateTime::from_ymd_hms(year, 1, 1, 0, 0, 0);
+    if new_years.weekday() != Weekday::Saturday {
+        holidays.push((observed(year, 1, 1), Holiday::NewYearsDay));
+    }
+    if year >= 1998 {
+        holidays.push((nth_weekday_of_month(year, 1, Weekday::Monday, 3), Holiday::MartinLutherKingJrDay));
+    }
+    if year >= 1971 {
+        holidays.push((nth_weekday_of_month(year, 2, Weekday::Monday, 3), Holiday::WashingtonsBirthday));
+        holidays.push((last_weekday_of_month(year, 5, Weekday::Monday), Holiday::MemorialDay));
+    } else {
+        holidays.push((observed(year, 2, 22), Holiday::WashingtonsBirthday));
+        holidays.push((observed(year, 5, 30), Holiday::MemorialDay));
+    }
+    holidays.push((easter_sunday(year).sub_days(2), Holiday::GoodFriday));
+    if year >= 2022 {
+        holidays.push((observed(year, 6, 19), Holiday::Juneteenth));
+    }
+    holidays.push((observed(year, 7, 4), Holiday::IndependenceDay));
+    holidays.push((nth_weekday_of_month(year, 9, Weekday::Monday, 1), Holiday::LaborDay));
+    // Closed every Election Day until 1968, then only for presidential
+    // elections until 1980
+    if year <= 1968 || (year <= 1980 && year.is_multiple_of(4)) {
+        holidays.push((nth_weekday_of_month(year, 11, Weekday::Monday, 1).add_days(1), Holiday::ElectionDay));
+    }
+    holidays.push((nth_weekday_of_month(year, 11, Weekday::Thursday, 4), Holiday::Thanksgiving));
+    holidays.push((observed(year, 12, 25), Holiday::Christmas));
+
+    for (y, m, d, reason) in SPECIAL_CLOSURES.iter() {
+        if *y == year {
+            holidays.push((DateTime::from_ymd_hms(*y, *m, *d, 0, 0, 0), Holiday::Special(reason)));
+        }
+    }
+    return holidays;
+}
+
+/// Returns the holiday the NYSE/NASDAQ is closed for on the given date, if
+/// any. Weekends are not holidays.
+///
+/// Holidays follow the rules in effect for each year. One-off closures
+/// before 1990 are not exhaustive, ex. the Wednesday closures of the 1968
+/// paperwork crisis are not included.
+///
+/// ### Examples
+/// ```
+/// assert_eq!(holiday(&DateTime::new("4/2/2021 16:00:00")), Some(Holiday::GoodFriday));
+/// assert_eq!(holiday(&DateTime::new("7/5/2021 16:00:00")), Some(Holiday::IndependenceDay));
+/// assert_eq!(holiday(&DateTime::new("4/23/2021 16:00:00")), None);
+/// ```
+pub fn holiday(date: &DateTime) -> Option<Holiday> {
+    let day = date.start_of_day();
+    for (d, h) in holidays_in_year(date.get_year()) {
+        if d == day { return Some(h); }
+    }
+    return None;
+}
+
+/// Returns `true` if the NYSE/NASDAQ holds a regular session on the given
+/// date.
+///
+/// ### Examples
+/// ```
+/// assert_eq!(is_trading_day(&DateTime::new("4/23/2021 16:00:00")), true);
+/// assert_eq!(is_trading_day(&DateTime::new("4/24/2021 16:00:00")), false); // Saturday
+/// assert_eq!(is_trading_day(&DateTime::new("12/24/2021 16:00:00")), false); // Christmas, observed
+/// ```
+pub fn is_trading_day(date: &DateTime) -> bool {
+    return !date.weekday().is_weekend() && holiday(date).is_none();
+}
+
+/// Returns `true` if the regular session closes early, at 1:00 PM, on the
+/// given date. Early closes are modelled from 1993 onwards: the day before
+/// Independence Day, the day after Thanksgiving and Christmas Eve.
+#[allow(dead_code)]
+pub fn is_early_close(date: &DateTime) -> bool {
+    if date.get_year() < 1993 || !is_trading_day(date) {
+        return false;
+    }
+    let day = date.start_of_day();
+    let year = date.get_year();
+    return day == DateTime::from_ymd_hms(year, 7, 3, 0, 0, 0)
+        || day == nth_weekday_of_month(year, 11, Weekday::Thursday, 4).add_days(1)
+        || day == DateTime::from_ymd_hms(year, 12, 24, 0, 0, 0);
+}
+
+/// Returns the regular close on the given date in seconds since local
+/// midnight, or `None` if the market is closed.
+#[allow(dead_code)]
+pub fn regular_close(exchange: &Exchange, date: &DateTime) -> Option<u32> {
+    if !is_trading_day(date) {
+        return None;
+    }
+    if is_early_close(date) {
+        return Some(EARLY_CLOSE);
+    }
+    return Some(exchange.get_regular_close());
+}
+
+/// Returns the session for a wall-clock time in the exchange's zone, taking
+/// holidays and early closes into account.
+///
+/// ### Examples
+/// ```
+/// let nyse = Exchange::nyse();
+/// assert_eq!(session_at_local(&nyse, &DateTime::new("11/26/2021 14:00:00")), Session::AfterHours);
+/// assert_eq!(session_at_local(&nyse, &DateTime::new("11/25/2021 14:00:00")), Session::Closed);
+/// ```
+#[allow(dead_code)]
+pub fn session_at_local(exchange: &Exchange, local: &DateTime) -> Session {
+    let close = match regular_close(exchange, local) {
+        Some(close) => close,
+        None => return Session::Closed,
+    };
+    let session = exchange.session_at_local(local);
+    if session == Session::Regular && local.seconds_of_day() >= close {
+        return Session::AfterHours;
+    }
+    return session;
+}
+
+/// Returns the first trading day strictly after the given date, keeping
+/// its time.
+///
+/// ### Examples
+/// ```
+/// let d = next_trading_day(&DateTime::new("4/1/2021 16:00:00"));
+/// assert_eq!(d.to_string(), "4/5/2021 16:00:00"); // Skips Good Friday
+/// ```
+pub fn next_trading_day(date: &DateTime) -> DateTime {
+    let mut d = date.add_days(1);
+    while !is_trading_day(&d) {
+        d = d.add_days(1);
+    }
+    return d;
+}
+
+/// Returns the last trading day strictly before the given date, keeping
+/// its time.
+#[allow(dead_code)]
+pub fn previous_trading_day(date: &DateTime) -> DateTime {
+    let mut d = date.sub_days(1);
+    while !is_trading_day(&d) {
+        d = d.sub_days(1);
+    }
+    return d;
+}
+
+/// Counts the trading days from `start` to `end`, both dates included.
+/// Returns `0` if `end` comes before `start`.
+///
+/// ### Examples
+/// ```
+/// let start = DateTime::new("3/29/2021 00:00:00");
+/// let end = DateTime::new("4/5/2021 00:00:00");
+/// assert_eq!(trading_days_between(&start, &end), 5);
+/// ```
+#[allow(dead_code)]
+pub fn trading_days_between(start: &DateTime, end: &DateTime) -> usize {
+    let mut count = 0;
+    let mut d = start.start_of_day();
+    let end = end.start_of_day();
+    while d <= end {
+        if is_trading_day(&d) { count += 1; }
+        d = d.add_days(1);
+    }
+    return count;
+}
+
+/// Counts the trading days in the given calendar year.
+#[allow(dead_code)]
+pub fn trading_days_in_year(year: u16) -> usize {
+    return trading_days_between(
+        &DateTime::from_ymd_hms(year, 1, 1, 0, 0, 0),
+        &DateTime::from_ymd_hms(year, 12, 31, 0, 0, 0),
+    );
+}
+
+/// Average number of trading days per year between `start` and `end`, for
+/// annualizing metrics by actual session counts rather than a fixed 252.
+/// Can be passed to `BacktestReport::with_periods_per_year`.
+#[allow(dead_code)]
+pub fn trading_days_per_year(start: &DateTime, end: &DateTime) -> f64 {
+    let days = end.days_since(start) + 1;
+    if days <= 0 {
+        return 0.0;
+    }
+    return trading_days_between(start, end) as f64 / (days as f64 / 365.25);
+}
+
+/// Returns the trading days between the first and last bar that have no
+/// bar, as midnight `DateTime`s. `data` must be sorted by date.
+///
+/// ### Examples
+/// ```
+/// let gaps = missing_trading_days(s.get_historical_data());
+/// ```
+pub fn missing_trading_days(data: &[HistoricalData]) -> Vec<DateTime> {
+    let mut missing = Vec::new();
+    if data.is_empty() {
+        return missing;
+    }
+    let mut expected = data[0].get_date().start_of_day();
+    if !is_trading_day(&expected) {
+        expected = next_trading_day(&expected);
+    }
+    for bar in data {
+        let day = bar.get_date().start_of_day();
+        while expected < day {
+            missing.push(expected.clone());
+            expected = next_trading_day(&expected);
+        }
+        if expected == day {
+            expected = next_trading_day(&expected);
+        }
+    }
+    return missing;
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::read_to_string;
+
+    /// Reads the bar dates of a bundled historical file.
+    fn load_dates(ticker: &str) -> Vec<DateTime> {
+        let content = read_to_string(format!("./src/assets/historical/{}.csv", ticker)).unwrap();
+        return content.lines().skip(1)
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| DateTime::new(line.split(',').next().unwrap()))
+            .collect();
+    }
+
+    #[test]
+    fn test_easter_sunday() {
+        assert_eq!(easter_sunday(2021).to_string(), "4/4/2021 00:00:00");
+        assert_eq!(easter_sunday(2019).to_string(), "4/21/2019 00:00:00");
+        assert_eq!(easter_sunday(2008).to_string(), "3/23/2008 00:00:00");
+        assert_eq!(easter_sunday(2000).to_string(), "4/23/2000 00:00:00");
+    }
+
+    #[test]
+    fn test_holidays_2021() {
+        let expected = [
+            ("1/1/2021", Holiday::NewYearsDay),
+            ("1/18/2021", Holiday::MartinLutherKingJrDay),
+            ("2/15/2021", Holiday::WashingtonsBirthday),
+            ("4/2/2021", Holiday::GoodFriday),
+            ("5/31/2021", Holiday::MemorialDay),
+            ("7/5/2021", Holiday::IndependenceDay),
+            ("9/6/2021", Holiday::LaborDay),
+            ("11/25/2021", Holiday::Thanksgiving),
+            ("12/24/2021", Holiday::Christmas),
+        ];
+        for (date, h) in expected.iter() {
+            assert_eq!(holiday(&DateTime::new(date)), Some(*h), "{}", date);
+        }
+        // Juneteenth was first observed in 2022
+        assert_eq!(holiday(&DateTime::new("6/18/2021")), None);
+        assert_eq!(trading_days_in_year(2021), 252);
+    }
+
+    #[test]
+    fn test_holidays_observed() {
+        // New Year's Day on a Saturday is not observed
+        assert_eq!(holiday(&DateTime::new("12/31/2021")), None);
+        assert_eq!(is_trading_day(&DateTime::new("12/31/2021")), true);
+        // Sunday holidays move to Monday
+        assert_eq!(holiday(&DateTime::new("1/2/2017")), Some(Holiday::NewYearsDay));
+        assert_eq!(holiday(&DateTime::new("6/20/2022")), Some(Holiday::Juneteenth));
+        // Saturday holidays move to Friday
+        assert_eq!(holiday(&DateTime::new("7/3/2020")), Some(Holiday::IndependenceDay));
+        assert_eq!(holiday(&DateTime::new("11/3/1976")), None);
+        assert_eq!(holiday(&DateTime::new("11/2/1976")), Some(Holiday::ElectionDay));
+        assert_eq!(holiday(&DateTime::new("10/29/2012")), Some(Holiday::Special("Hurricane Sandy")));
+    }
+
+    #[test]
+    fn test_early_close() {
+        assert_eq!(is_early_close(&DateTime::new("11/26/2021")), true);
+        assert_eq!(is_early_close(&DateTime::new("7/3/2019")), true);
+        assert_eq!(is_early_close(&DateTime::new("12/24/2019")), true);
+        // Christmas observed on Friday 12/24/2021
+        assert_eq!(is_early_close(&DateTime::new("12/24/2021")), false);
+        assert_eq!(is_early_close(&DateTime::new("4/23/2021")), false);
+
+        let nyse = Exchange::nyse();
+        assert_eq!(regular_close(&nyse, &DateTime::new("11/26/2021")), Some(EARLY_CLOSE));
+        assert_eq!(regular_close(&nyse, &DateTime::new("4/23/2021")), Some(16 * 3600));
+        assert_eq!(regular_close(&nyse, &DateTime::new("4/24/2021")), None);
+        assert_eq!(session_at_local(&nyse, &DateTime::new("11/26/2021 12:59:59")), Session::Regular);
+        assert_eq!(session_at_local(&nyse, &DateTime::new("11/26/2021 13:00:00")), Session::AfterHours);
+        assert_eq!(session_at_local(&nyse, &DateTime::new("11/25/2021 10:00:00")), Session::Closed);
+        assert_eq!(session_at_local(&nyse, &DateTime::new("4/23/2021 10:00:00")), Session::Regular);
+    }
+
+    #[test]
+    fn test_next_previous_trading_day() {
+        assert_eq!(next_trading_day(&DateTime::new("4/1/2021 16:00:00")).to_string(), "4/5/2021 16:00:00");
+        assert_eq!(next_trading_day(&DateTime::new("4/23/2021 16:00:00")).to_string(), "4/26/2021 16:00:00");
+        assert_eq!(next_trading_day(&DateTime::new("12/23/2021 16:00:00")).to_string(), "12/27/2021 16:00:00");
+        assert_eq!(previous_trading_day(&DateTime::new("1/3/2022 16:00:00")).to_string(), "12/31/2021 16:00:00");
+        assert_eq!(previous_trading_day(&DateTime::new("1/19/2021 16:00:00")).to_string(), "1/15/2021 16:00:00");
+    }
+
+    #[test]
+    fn test_trading_days_between() {
+        assert_eq!(trading_days_between(&DateTime::new("3/29/2021"), &DateTime::new("4/5/2021")), 5);
+        assert_eq!(trading_days_between(&DateTime::new("4/23/2021"), &DateTime::new("4/23/2021")), 1);
+        assert_eq!(trading_days_between(&DateTime::new("4/24/2021"), &DateTime::new("4/25/2021")), 0);
+        assert_eq!(trading_days_between(&DateTime::new("4/25/2021"), &DateTime::new("4/23/2021")), 0);
+        let per_year = trading_days_per_year(&DateTime::new("1/1/2021"), &DateTime::new("12/31/2021"));
+        assert!((per_year - 252.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_spy_dates() {
+        let dates = load_dates("spy");
+        for d in dates.iter() {
+            assert_eq!(is_trading_day(d), true, "{}", d.to_string());
+        }
+        let first = &dates[0];
+        let last = &dates[dates.len() - 1];
+        assert_eq!(trading_days_between(first, last), dates.len());
+        let data: Vec<HistoricalData> = dates.iter().map(|d| HistoricalData::new(&d.to_string(), 1.0, 1.0, 1.0, 1.0, 1)).collect();
+        assert_eq!(missing_trading_days(&data).len(), 0);
+    }
+
+    #[test]
+    fn test_missing_trading_days() {
+        let data = vec![
+            HistoricalData::new("3/31/2021 16:00:00", 1.0, 1.0, 1.0, 1.0, 1),
+            HistoricalData::new("4/5/2021 16:00:00", 1.0, 1.0, 1.0, 1.0, 1),
+            HistoricalData::new("4/8/2021 16:00:00", 1.0, 1.0, 1.0, 1.0, 1),
+        ];
+        let missing: Vec<String> = missing_trading_days(&data).iter().map(|d| d.to_string()).collect();
+        assert_eq!(missing, vec!["4/1/2021 00:00:00", "4/6/2021 00:00:00", "4/7/2021 00:00:00"]);
+        assert_eq!(missing_trading_days(&[]).len(), 0);
+    }
+}
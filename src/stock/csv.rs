@@ -0,0 +1,351 @@
+use std::fmt;
+
+/// Error returned when a CSV document is not well formed.
+///
+/// - `UnterminatedQuote`: a quoted field is still open at the end of the input
+/// - `UnexpectedCharacter`: a closing quote is followed by something other
+///   than a delimiter or the end of the line
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum CsvError {
+    UnterminatedQuote {
+        line: usize,
+    },
+    UnexpectedCharacter {
+        line: usize,
+        character: char,
+    },
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            CsvError::UnterminatedQuote { line } => write!(f, "Unterminated quoted field starting on line {}.", line),
+            CsvError::UnexpectedCharacter { line, character } => {
+                write!(f, "Unexpected character '{}' after a closing quote on line {}.", character, line)
+            },
+        };
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+#[allow(dead_code)]
+impl CsvError {
+    /// Line the error was found on (1-based).
+    pub fn get_line(&self) -> usize {
+        return match self {
+            CsvError::UnterminatedQuote { line } => *line,
+            CsvError::UnexpectedCharacter { line, .. } => *line,
+        };
+    }
+}
+
+/// A single CSV record and the line it starts on (1-based).
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct Record {
+    line: usize,
+    fields: Vec<String>,
+}
+
+#[allow(dead_code)]
+impl Record {
+    pub fn get_line(&self) -> usize { return self.line; }
+    pub fn get_fields(&self) -> &Vec<String> { return &self.fields; }
+
+    pub fn get(&self, index: usize) -> Option<&str> {
+        return self.fields.get(index).map(|field| field.as_str());
+    }
+
+    pub fn len(&self) -> usize {
+        return self.fields.len();
+    }
+
+    /// Returns the index of the field equal to `name`, ignoring case and
+    /// surrounding whitespace. Used to look up columns in a header record.
+    ///
+    /// ### Examples
+    /// ```
+    /// let records = csv::parse("Date,Open,Adj Close\n").unwrap();
+    /// assert_eq!(records[0].find("adj close"), Some(2));
+    /// ```
+    pub fn find(&self, name: &str) -> Option<usize> {
+        return self.fields.iter().position(|field| field.trim().eq_ignore_ascii_case(name));
+    }
+}
+
+#[derive(PartialEq)]
+enum State {
+    FieldStart,
+    Unquoted,
+    Quoted,
+    QuoteInQuoted,
+    Invalid,
+}
+
+/// Parses a CSV document following RFC 4180. Fields may be quoted with
+/// `"`, and quoted fields may contain commas, doubled quotes (`""`) and
+/// line breaks. Both `\n` and `\r\n` line endings are accepted. Blank lines
+/// are skipped.
+///
+/// # Arguments
+///
+/// * `content` - The CSV document, header included
+///
+/// ### Examples
+/// ```
+/// let records = csv::parse("name,summary\nApple Inc.,\"Designs, manufactures\"\n").unwrap();
+/// assert_eq!(records[1].get(1), Some("Designs, manufactures"));
+/// assert_eq!(records[1].get_line(), 2);
+/// ```
+///
+/// #### Resources
+/// - [RFC 4180](https://datatracker.ietf.org/doc/html/rfc4180)
+pub fn parse(content: &str) -> Result<Vec<Record>, CsvError> {
+    return parse_records(content).into_iter().collect();
+}
+
+/// Parses a CSV document like `parse`, but reports errors per record
+/// instead of failing the whole document.
+///
+/// After a closing quote followed by an unexpected character, the rest of
+/// that line is dropped and parsing resumes on the next line. A quoted
+/// field left open at the end of the input is reported on the line it
+/// starts on, and parsing resumes on the line after it.
+///
+/// ### Examples
+/// ```
+/// let records = csv::parse_records("a,b\n1,\"2\"x\n3,4\n");
+/// assert_eq!(records[1], Err(CsvError::UnexpectedCharacter { line: 2, character: 'x' }));
+/// assert_eq!(records[2].as_ref().unwrap().get(0), Some("3"));
+/// ```
+pub fn parse_records(content: &str) -> Vec<Result<Record, CsvError>> {
+    let mut records = Vec::new();
+    let mut start = 0;
+    let mut line = 1;
+    while let Some((offset, next_line)) = parse_from(&content[start..], line, &mut records) {
+        start += offset;
+        line = next_line;
+    }
+    return records;
+}
+
+/// Parses `content`, whose first line is line `first_line` of the
+/// document, into `records`. Returns the byte offset and line number to
+/// resume from when a quoted field is left open, or `None` once the whole
+/// input has been read.
+fn parse_from(content: &str, first_line: usize, records: &mut Vec<Result<Record, CsvError>>) -> Option<(usize, usize)> {
+    let mut fields: Vec<String> = Vec::new();
+    let mut field = "".to_owned();
+    let mut state = State::FieldStart;
+    let mut line = first_line;
+    let mut record_line = first_line;
+    let mut quoted_line = first_line;
+    // Start of the line after the one the open quoted field starts on
+    let mut resume: Option<usize> = None;
+    let mut chars = content.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        // Treat `\r\n` as a single line break outside of quoted fields
+        if c == '\r' && state != State::Quoted && matches!(chars.peek(), Some((_, '\n'))) {
+            continue;
+        }
+        match state {
+            State::Quoted => match c {
+                '"' => state = State::QuoteInQuoted,
+                '\n' => {
+                    if line == quoted_line {
+                        resume = Some(i + 1);
+                    }
+                    line += 1;
+                    field.push(c);
+                },
+                _ => field.push(c),
+            },
+            State::QuoteInQuoted => match c {
+                '"' => {
+                    field.push('"');
+                    state = State::Quoted;
+                },
+                ',' => {
+                    fields.push(field.clone());
+                    field.clear();
+                    state = State::FieldStart;
+                },
+                '\n' => {
+                    fields.push(field.clone());
+                    field.clear();
+                    records.push(Ok(Record { line: record_line, fields: fields.clone() }));
+                    fields.clear();
+                    line += 1;
+                    record_line = line;
+                    state = State::FieldStart;
+                },
+                _ => {
+                    records.push(Err(CsvError::UnexpectedCharacter { line: line, character: c }));
+                    state = State::Invalid;
+                },
+            },
+            // Drop the rest of a line holding an error
+            State::Invalid => if c == '\n' {
+                fields.clear();
+                field.clear();
+                line += 1;
+                record_line = line;
+                state = State::FieldStart;
+            },
+            State::FieldStart | State::Unquoted => match c {
+                '"' if state == State::FieldStart => {
+                    quoted_line = line;
+                    resume = None;
+                    state = State::Quoted;
+                },
+                ',' => {
+                    fields.push(field.clone());
+                    field.clear();
+                    state = State::FieldStart;
+                },
+                '\n' => {
+                    fields.push(field.clone());
+                    field.clear();
+                    // Skip blank lines
+                    if fields.len() > 1 || !fields[0].trim().is_empty() {
+                        records.push(Ok(Record { line: record_line, fields: fields.clone() }));
+                    }
+                    fields.clear();
+                    line += 1;
+                    record_line = line;
+                    state = State::FieldStart;
+                },
+                _ => {
+                    field.push(c);
+                    state = State::Unquoted;
+                },
+            },
+        };
+    }
+
+    if state == State::Quoted {
+        records.push(Err(CsvError::UnterminatedQuote { line: quoted_line }));
+        return resume.map(|offset| (offset, quoted_line + 1));
+    }
+    // Last record without a trailing line break
+    if state != State::Invalid && (state == State::QuoteInQuoted || !fields.is_empty() || !field.trim().is_empty()) {
+        fields.push(field);
+        records.push(Ok(Record { line: record_line, fields: fields }));
+    }
+    return None;
+}
+
+/// Formats a field for a CSV file. Fields containing commas, quotes or
+/// line breaks are quoted, with quotes doubled, so that `parse` reads them
+/// back unchanged.
+///
+/// ### Examples
+/// ```
+/// assert_eq!(csv::format_field("Apple Inc."), "Apple Inc.");
+/// assert_eq!(csv::format_field("Apple, \"Inc.\""), "\"Apple, \"\"Inc.\"\"\"");
+/// ```
+pub fn format_field(field: &str) -> String {
+    if !field.contains([',', '"', '\n', '\r']) {
+        return field.to_string();
+    }
+    return format!("\"{}\"", field.replace('"', "\"\""));
+}
+
+/// Formats fields as one CSV line, without a line break.
+pub fn format_record(fields: &[&str]) -> String {
+    return fields.iter().map(|field| format_field(field)).collect::<Vec<String>>().join(",");
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(records: &[Record]) -> Vec<Vec<String>> {
+        return records.iter().map(|r| r.get_fields().clone()).collect();
+    }
+
+    #[test]
+    fn test_parse_simple() {
+        let records = parse("Date,Open\n1/2/2014 16:00:00,183.98\n").unwrap();
+        assert_eq!(fields(&records), vec![vec!["Date", "Open"], vec!["1/2/2014 16:00:00", "183.98"]]);
+        assert_eq!(records[0].get_line(), 1);
+        assert_eq!(records[1].get_line(), 2);
+    }
+
+    #[test]
+    fn test_parse_line_endings_and_blank_lines() {
+        let records = parse("a,b\r\n\r\n1,2\r\n\n3,4").unwrap();
+        assert_eq!(fields(&records), vec![vec!["a", "b"], vec!["1", "2"], vec!["3", "4"]]);
+        assert_eq!(records[1].get_line(), 3);
+        assert_eq!(records[2].get_line(), 5);
+    }
+
+    #[test]
+    fn test_parse_empty_fields() {
+        let records = parse("09/12/2001 16:00:00,,,,,\n,\n").unwrap();
+        assert_eq!(records[0].len(), 6);
+        assert_eq!(records[0].get(1), Some(""));
+        assert_eq!(fields(&records)[1], vec!["", ""]);
+    }
+
+    #[test]
+    fn test_parse_quoted() {
+        let records = parse("name,summary\n\"Apple Inc.\",\"Designs, \"\"makes\"\"\nand sells\",x\nnext,\"\"\n").unwrap();
+        assert_eq!(records[1].get(0), Some("Apple Inc."));
+        assert_eq!(records[1].get(1), Some("Designs, \"makes\"\nand sells"));
+        assert_eq!(records[1].get(2), Some("x"));
+        assert_eq!(records[2].get_line(), 4);
+        assert_eq!(records[2].get(1), Some(""));
+    }
+
+    #[test]
+    fn test_parse_quoted_last_field_without_line_break() {
+        let records = parse("a,\"b,c\"").unwrap();
+        assert_eq!(fields(&records), vec![vec!["a", "b,c"]]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse("a,b\n1,\"2\n3,4\n"), Err(CsvError::UnterminatedQuote { line: 2 }));
+        assert_eq!(parse("a,b\n1,\"2\"x\n"), Err(CsvError::UnexpectedCharacter { line: 2, character: 'x' }));
+        assert_eq!(parse("a\n\"b\nc\"d").unwrap_err().to_string(), "Unexpected character 'd' after a closing quote on line 3.");
+    }
+
+    #[test]
+    fn test_parse_records_recovers() {
+        let records = parse_records("a,b\n1,\"2\"x,\"y\nz\"\n3,4\n5,\"6\n7,8\r\n9,10");
+        assert_eq!(records[0], Ok(Record { line: 1, fields: vec!["a".to_string(), "b".to_string()] }));
+        assert_eq!(records[1], Err(CsvError::UnexpectedCharacter { line: 2, character: 'x' }));
+        // The rest of line 2 is dropped, so `z"` is read as a record
+        assert_eq!(records[2].as_ref().unwrap().get_line(), 3);
+        assert_eq!(records[3].as_ref().unwrap().get_fields(), &vec!["3", "4"]);
+        assert_eq!(records[4], Err(CsvError::UnterminatedQuote { line: 5 }));
+        assert_eq!(records[5], Ok(Record { line: 6, fields: vec!["7".to_string(), "8".to_string()] }));
+        assert_eq!(records[6], Ok(Record { line: 7, fields: vec!["9".to_string(), "10".to_string()] }));
+        assert_eq!(records.len(), 7);
+        assert_eq!(parse_records("a,\"b"), vec![Err(CsvError::UnterminatedQuote { line: 1 })]);
+    }
+
+    #[test]
+    fn test_find() {
+        let records = parse("Date, Open ,Adj Close\n").unwrap();
+        assert_eq!(records[0].find("date"), Some(0));
+        assert_eq!(records[0].find("OPEN"), Some(1));
+        assert_eq!(records[0].find("adj close"), Some(2));
+        assert_eq!(records[0].find("volume"), None);
+    }
+
+    #[test]
+    fn test_format_record_round_trip() {
+        let values = vec!["Apple Inc.", "Apple, \"Inc.\"", "line\nbreak", ""];
+        let line = format_record(&values);
+        assert_eq!(line, "Apple Inc.,\"Apple, \"\"Inc.\"\"\",\"line\nbreak\",");
+        assert_eq!(fields(&parse(&line).unwrap()), vec![values]);
+    }
+}
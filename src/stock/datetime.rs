@@ -1,3 +1,311 @@
+use std::{fmt, str::FromStr, time::{SystemTime, UNIX_EPOCH}};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+const MONTH_ABBREVIATIONS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// Error returned by `DateTime::parse`.
+/// 
+/// - `Empty`: the input is empty or only whitespace
+/// - `InvalidFormat`: the input does not match any supported format
+/// - `OutOfRange`: a component is outside of its valid range (ex. month 13
+///   or 2/30)
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum DateTimeError {
+    Empty,
+    InvalidFormat {
+        input: String,
+    },
+    OutOfRange {
+        input: String,
+        field: &'static str,
+        value: i64,
+    },
+}
+
+impl fmt::Display for DateTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            DateTimeError::Empty => write!(f, "Cannot parse an empty datetime string."),
+            DateTimeError::InvalidFormat { input } => write!(f, "Unrecognized datetime format \"{}\".", input),
+            DateTimeError::OutOfRange { input, field, value } => {
+                write!(f, "Invalid {} {} in datetime string \"{}\".", field, value, input)
+            },
+        };
+    }
+}
+
+impl std::error::Error for DateTimeError {}
+
+/// Day of the week.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+#[allow(dead_code)]
+impl Weekday {
+    /// Returns the ISO-8601 day number, from 1 (Monday) to 7 (Sunday).
+    pub fn number_from_monday(&self) -> u8 {
+        return match self {
+            Weekday::Monday => 1,
+            Weekday::Tuesday => 2,
+            Weekday::Wednesday => 3,
+            Weekday::Thursday => 4,
+            Weekday::Friday => 5,
+            Weekday::Saturday => 6,
+            Weekday::Sunday => 7,
+        };
+    }
+
+    pub fn abbreviation(&self) -> &'static str {
+        return match self {
+            Weekday::Monday => "Mon",
+            Weekday::Tuesday => "Tue",
+            Weekday::Wednesday => "Wed",
+            Weekday::Thursday => "Thu",
+            Weekday::Friday => "Fri",
+            Weekday::Saturday => "Sat",
+            Weekday::Sunday => "Sun",
+        };
+    }
+
+    pub fn is_weekend(&self) -> bool {
+        return *self == Weekday::Saturday || *self == Weekday::Sunday;
+    }
+
+    fn from_days_since_epoch(days: i64) -> Self {
+        // 1/1/1970 was a Thursday
+        return match (days + 3).rem_euclid(7) {
+            0 => Weekday::Monday,
+            1 => Weekday::Tuesday,
+            2 => Weekday::Wednesday,
+            3 => Weekday::Thursday,
+            4 => Weekday::Friday,
+            5 => Weekday::Saturday,
+            _ => Weekday::Sunday,
+        };
+    }
+}
+
+/// Returns `true` if `year` is a leap year in the Gregorian calendar.
+/// 
+/// ### Examples
+/// ```
+/// assert_eq!(is_leap_year(2020), true);
+/// assert_eq!(is_leap_year(2021), false);
+/// assert_eq!(is_leap_year(1900), false);
+/// assert_eq!(is_leap_year(2000), true);
+/// ```
+pub fn is_leap_year(year: u16) -> bool {
+    return (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400);
+}
+
+/// Returns the number of days in the given `month` (1-12) of `year`.
+pub fn days_in_month(year: u16, month: u8) -> u8 {
+    return match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 => if is_leap_year(year) { 29 } else { 28 },
+        _ => panic!("Invalid month {}.", month),
+    };
+}
+
+/// Returns midnight on the `n`th (1-based) `weekday` of the given month,
+/// ex. the third Monday of January. Panics if the month has no such day.
+/// 
+/// ### Examples
+/// ```
+/// let d = nth_weekday_of_month(2021, 1, Weekday::Monday, 3);
+/// assert_eq!(d.to_string(), "1/18/2021 00:00:00");
+/// ```
+pub fn nth_weekday_of_month(year: u16, month: u8, weekday: Weekday, n: u8) -> DateTime {
+    let first = DateTime::from_ymd_hms(year, month, 1, 0, 0, 0);
+    let offset = (weekday.number_from_monday() as i64 - first.weekday().number_from_monday() as i64).rem_euclid(7);
+    let day = 1 + offset + (n as i64 - 1) * 7;
+    if n == 0 || day > days_in_month(year, month) as i64 {
+        panic!("There is no {:?} number {} in {}/{}.", weekday, n, month, year);
+    }
+    return DateTime::from_ymd_hms(year, month, day as u8, 0, 0, 0);
+}
+
+/// Returns midnight on the last `weekday` of the given month, ex. the last
+/// Monday of May.
+pub fn last_weekday_of_month(year: u16, month: u8, weekday: Weekday) -> DateTime {
+    let last = DateTime::from_ymd_hms(year, month, days_in_month(year, month), 0, 0, 0);
+    let offset = (last.weekday().number_from_monday() as i64 - weekday.number_from_monday() as i64).rem_euclid(7);
+    return last.sub_days(offset);
+}
+
+/// Number of days from 1/1/1970 to the given civil date.
+/// 
+/// #### Resources
+/// - [Howard Hinnant: chrono-Compatible Low-Level Date Algorithms](http://howardhinnant.github.io/date_algorithms.html#days_from_civil)
+fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    return era * 146_097 + doe - 719_468;
+}
+
+/// Civil date `(year, month, day)` for the given number of days since
+/// 1/1/1970. Inverse of `days_from_civil`.
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u8;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    return (year, month, day);
+}
+
+/// Number of ISO weeks (52 or 53) in the given ISO week-numbering year.
+fn iso_weeks_in_year(year: i64) -> u8 {
+    let p = |y: i64| (y + y.div_euclid(4) - y.div_euclid(100) + y.div_euclid(400)).rem_euclid(7);
+    if p(year) == 4 || p(year - 1) == 3 { return 53; }
+    return 52;
+}
+
+/// Parses an unsigned integer made only of ASCII digits.
+fn parse_number(part: &str, input: &str) -> Result<i64, DateTimeError> {
+    if part.is_empty() || part.len() > 9 || !part.chars().all(|c| c.is_ascii_digit()) {
+        return Err(DateTimeError::InvalidFormat { input: input.to_string() });
+    }
+    return Ok(part.parse::<i64>().unwrap());
+}
+
+/// Builds a `DateTime` after checking that every component is in range.
+fn validated(year: i64, month: i64, day: i64, hour: i64, minute: i64, second: i64, input: &str) -> Result<DateTime, DateTimeError> {
+    let out_of_range = |field: &'static str, value: i64| DateTimeError::OutOfRange { input: input.to_string(), field: field, value: value };
+    if !(1..=9999).contains(&year) { return Err(out_of_range("year", year)); }
+    if !(1..=12).contains(&month) { return Err(out_of_range("month", month)); }
+    if day < 1 || day > days_in_month(year as u16, month as u8) as i64 { return Err(out_of_range("day", day)); }
+    if hour > 23 { return Err(out_of_range("hour", hour)); }
+    if minute > 59 { return Err(out_of_range("minute", minute)); }
+    if second > 59 { return Err(out_of_range("second", second)); }
+    return Ok(DateTime::from_ymd_hms(year as u16, month as u8, day as u8, hour as u8, minute as u8, second as u8));
+}
+
+/// Parses `hh:mm` or `hh:mm:ss`, ignoring any fractional seconds.
+fn parse_time(time: &str, input: &str) -> Result<(i64, i64, i64), DateTimeError> {
+    let time = match time.split_once('.') {
+        Some((time, fraction)) => {
+            parse_number(fraction, input)?;
+            time
+        },
+        None => time,
+    };
+    let parts: Vec<&str> = time.split(':').collect();
+    return match parts.len() {
+        2 => Ok((parse_number(parts[0], input)?, parse_number(parts[1], input)?, 0)),
+        3 => Ok((parse_number(parts[0], input)?, parse_number(parts[1], input)?, parse_number(parts[2], input)?)),
+        _ => Err(DateTimeError::InvalidFormat { input: input.to_string() }),
+    };
+}
+
+/// Parses Unix epoch seconds, or milliseconds when more than 11 digits long.
+fn parse_epoch(s: &str, digits: usize, input: &str) -> Result<DateTime, DateTimeError> {
+    let value = match s.parse::<i64>() {
+        Ok(value) => value,
+        Err(_) => return Err(DateTimeError::OutOfRange { input: input.to_string(), field: "timestamp", value: 0 }),
+    };
+    let timestamp = if digits > 11 { value.div_euclid(1000) } else { value };
+    if !timestamp_in_range(timestamp) {
+        return Err(DateTimeError::OutOfRange { input: input.to_string(), field: "timestamp", value: value });
+    }
+    return Ok(DateTime::from_unix_timestamp(timestamp));
+}
+
+/// Whether `timestamp` falls within the years 1 to 9999.
+fn timestamp_in_range(timestamp: i64) -> bool {
+    let min = days_from_civil(1, 1, 1) * SECONDS_PER_DAY;
+    let max = days_from_civil(10000, 1, 1) * SECONDS_PER_DAY;
+    return timestamp >= min && timestamp < max;
+}
+
+/// Parses the ISO-8601 basic date `yyyymmdd`.
+fn parse_compact(s: &str, input: &str) -> Result<DateTime, DateTimeError> {
+    return validated(parse_number(&s[..4], input)?, parse_number(&s[4..6], input)?, parse_number(&s[6..], input)?, 0, 0, 0, input);
+}
+
+/// Parses `m/d/yyyy` with an optional ` hh:mm[:ss]` time.
+fn parse_slashed(s: &str, input: &str) -> Result<DateTime, DateTimeError> {
+    let (date, time) = match s.split_once(' ') {
+        Some((date, time)) => (date, Some(time.trim())),
+        None => (s, None),
+    };
+    let parts: Vec<&str> = date.split('/').collect();
+    if parts.len() != 3 || parts[2].len() != 4 {
+        return Err(DateTimeError::InvalidFormat { input: input.to_string() });
+    }
+    let (hour, minute, second) = match time {
+        Some(time) => parse_time(time, input)?,
+        None => (0, 0, 0),
+    };
+    return validated(parse_number(parts[2], input)?, parse_number(parts[0], input)?, parse_number(parts[1], input)?, hour, minute, second, input);
+}
+
+/// Parses ISO-8601 `yyyy-mm-dd` with an optional `Thh:mm[:ss[.fff]]` time
+/// and `Z` or `±hh[:mm]` offset. Returns the wall time as written and the
+/// offset in seconds east of UTC, if any.
+fn parse_iso(s: &str, input: &str) -> Result<(DateTime, Option<i32>), DateTimeError> {
+    let (date, time) = match s.find(['T', 't', ' ']) {
+        Some(i) => (&s[..i], Some(&s[i + 1..])),
+        None => (s, None),
+    };
+    let parts: Vec<&str> = date.split('-').collect();
+    if parts.len() != 3 || parts[0].len() != 4 {
+        return Err(DateTimeError::InvalidFormat { input: input.to_string() });
+    }
+
+    let mut offset = None;
+    let (hour, minute, second) = match time {
+        Some(time) => {
+            let time = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+                offset = Some(0);
+                time
+            } else if let Some(i) = time.find(['+', '-']) {
+                let sign = if &time[i..i + 1] == "-" { -1 } else { 1 };
+                let zone = time[i + 1..].replace(':', "");
+                if zone.len() != 2 && zone.len() != 4 {
+                    return Err(DateTimeError::InvalidFormat { input: input.to_string() });
+                }
+                let hours = parse_number(&zone[..2], input)?;
+                let minutes = if zone.len() == 4 { parse_number(&zone[2..], input)? } else { 0 };
+                if hours > 23 || minutes > 59 {
+                    return Err(DateTimeError::OutOfRange { input: input.to_string(), field: "offset", value: hours * 100 + minutes });
+                }
+                offset = Some(sign * (hours as i32 * 3600 + minutes as i32 * 60));
+                &time[..i]
+            } else {
+                time
+            };
+            parse_time(time, input)?
+        },
+        None => (0, 0, 0),
+    };
+
+    let d = validated(parse_number(parts[0], input)?, parse_number(parts[1], input)?, parse_number(parts[2], input)?, hour, minute, second, input)?;
+    return Ok((d, offset));
+}
+
 /// Requires date to be formatted as `m/d/yyyy hh:mm:ss`.
 /// 
 /// # Example
@@ -5,8 +313,12 @@
 /// let d = "1/2/2014 16:00:00";
 /// let d = "12/31/2020 05:31:22";
 /// ```
+/// 
+/// `DateTime`s are ordered chronologically. The derived comparisons rely on
+/// the fields being declared from the most to the least significant.
 #[derive(Debug)]
 #[derive(Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct DateTime {
     year: u16,
     month: u8,
@@ -18,40 +330,382 @@ pub struct DateTime {
 
 #[allow(dead_code)]
 impl DateTime {
+    /// Creates a `DateTime` from any format accepted by `DateTime::parse`.
+    /// 
+    /// Panics if the string cannot be parsed. Use `DateTime::parse` to
+    /// handle the error instead.
     pub fn new(datetime: &str) -> Self {
-        // Regex captures are incredibly slow in Rust at the moment...
-        // let re = Regex::new(r"^(\d{1,2})/(\d{1,2})/(\d{4}) (\d{1,2}):(\d{2}):(\d{2})$").unwrap();
-        let mut i = 0;
-        let mut month = "".to_owned();
-        let mut day = "".to_owned();
-        let mut year = "".to_owned();
-        let mut hour = "".to_owned();
-        let mut minute = "".to_owned();
-        let mut second = "".to_owned();
-        for c in datetime.chars() {
-            if c == '/' || c == ' ' || c == ':' {
-                i += 1;
-            } else {
-                match i {
-                    0 => month.push_str(&format!("{}", c)),
-                    1 => day.push_str(&format!("{}", c)),
-                    2 => year.push_str(&format!("{}", c)),
-                    3 => hour.push_str(&format!("{}", c)),
-                    4 => minute.push_str(&format!("{}", c)),
-                    5 => second.push_str(&format!("{}", c)),
-                    _ => panic!("Extra characters found in datetime string \"{}\".", datetime)
+        return match Self::parse(datetime) {
+            Ok(d) => d,
+            Err(error) => panic!("{}", error),
+        };
+    }
+
+    /// Parses a `DateTime`, validating every component.
+    /// 
+    /// Accepted formats:
+    /// - `m/d/yyyy hh:mm:ss`, `m/d/yyyy hh:mm` and `m/d/yyyy`
+    /// - ISO-8601 `yyyy-mm-dd`, `yyyy-mm-ddThh:mm[:ss[.fff]]` with an
+    ///   optional `Z` or `±hh:mm` offset
+    /// - ISO-8601 basic `yyyymmdd` (exactly 8 digits)
+    /// - Unix epoch seconds, or milliseconds when more than 11 digits long
+    /// 
+    /// Missing times default to `00:00:00`. Inputs with an offset, i.e.
+    /// ISO-8601 with `Z` or `±hh:mm` and epoch timestamps, are converted to
+    /// UTC, so the same instant written either way gives the same
+    /// `DateTime`. Inputs without one are kept as written. Use
+    /// `DateTime::parse_with_offset` to keep the wall time of an offset
+    /// input instead.
+    /// 
+    /// ### Examples
+    /// ```
+    /// assert_eq!(DateTime::parse("4/23/2021 16:00:00").unwrap().to_string(), "4/23/2021 16:00:00");
+    /// assert_eq!(DateTime::parse("2021-04-23T16:00:00-04:00").unwrap().to_string(), "4/23/2021 20:00:00");
+    /// assert_eq!(DateTime::parse("2021-04-23").unwrap().to_string(), "4/23/2021 00:00:00");
+    /// assert_eq!(DateTime::parse("20210423").unwrap().to_string(), "4/23/2021 00:00:00");
+    /// assert_eq!(DateTime::parse("1619193600").unwrap().to_string(), "4/23/2021 16:00:00");
+    /// assert!(DateTime::parse("2/30/2021").is_err());
+    /// ```
+    pub fn parse(datetime: &str) -> Result<Self, DateTimeError> {
+        let (d, offset) = Self::parse_with_offset(datetime)?;
+        return match offset {
+            Some(offset) if offset != 0 => {
+                let timestamp = d.to_unix_timestamp() - offset as i64;
+                match Self::try_from_unix_timestamp(timestamp) {
+                    Ok(d) => Ok(d),
+                    Err(_) => Err(DateTimeError::OutOfRange { input: datetime.to_string(), field: "timestamp", value: timestamp }),
                 }
+            },
+            _ => Ok(d),
+        };
+    }
+
+    /// Parses a `DateTime` like `DateTime::parse`, but keeps the wall time
+    /// of an ISO-8601 input as written and returns its offset in seconds
+    /// east of UTC, if any. Epoch timestamps are UTC and return an offset
+    /// of `0`.
+    /// 
+    /// ### Examples
+    /// ```
+    /// let (d, offset) = DateTime::parse_with_offset("2021-04-23T16:00:00-04:00").unwrap();
+    /// assert_eq!(d.to_string(), "4/23/2021 16:00:00");
+    /// assert_eq!(offset, Some(-14400));
+    /// assert_eq!(d.add_seconds(-offset.unwrap() as i64).to_string(), "4/23/2021 20:00:00");
+    /// ```
+    pub fn parse_with_offset(datetime: &str) -> Result<(Self, Option<i32>), DateTimeError> {
+        let s = datetime.trim();
+        if s.is_empty() {
+            return Err(DateTimeError::Empty);
+        }
+
+        let unsigned = s.strip_prefix('-').unwrap_or(s);
+        if unsigned.chars().all(|c| c.is_ascii_digit()) {
+            if unsigned.len() == 8 && unsigned.len() == s.len() {
+                return Ok((parse_compact(s, datetime)?, None));
+            }
+            return Ok((parse_epoch(s, unsigned.len(), datetime)?, Some(0)));
+        }
+        if s.contains('/') {
+            return Ok((parse_slashed(s, datetime)?, None));
+        }
+        return parse_iso(s, datetime);
+    }
+
+    /// Formats the `DateTime` using strftime-like specifiers. Any other
+    /// character is copied as is.
+    /// 
+    /// - `%Y` year, `%y` two digit year
+    /// - `%m` zero-padded month, `%-m` month, `%b` abbreviated month name
+    /// - `%d` zero-padded day, `%-d` day, `%a` abbreviated weekday name
+    /// - `%H`, `%M`, `%S` zero-padded hour, minute and second
+    /// - `%j` zero-padded day of the year
+    /// - `%s` Unix timestamp
+    /// - `%%` a literal `%`
+    /// 
+    /// ### Examples
+    /// ```
+    /// let d = DateTime::new("4/3/2021 16:00:00");
+    /// assert_eq!(d.format("%Y-%m-%d"), "2021-04-03");
+    /// assert_eq!(d.format("%-m/%-d/%Y %H:%M:%S"), d.to_string());
+    /// assert_eq!(d.format("%a %b %-d, %Y"), "Sat Apr 3, 2021");
+    /// ```
+    pub fn format(&self, pattern: &str) -> String {
+        let mut formatted = "".to_owned();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                formatted.push(c);
+                continue;
             }
+            match chars.next() {
+                Some('Y') => formatted.push_str(&format!("{:04}", self.year)),
+                Some('y') => formatted.push_str(&format!("{:02}", self.year % 100)),
+                Some('m') => formatted.push_str(&format!("{:02}", self.month)),
+                Some('b') => formatted.push_str(MONTH_ABBREVIATIONS[self.month as usize - 1]),
+                Some('d') => formatted.push_str(&format!("{:02}", self.day)),
+                Some('a') => formatted.push_str(self.weekday().abbreviation()),
+                Some('H') => formatted.push_str(&format!("{:02}", self.hour)),
+                Some('M') => formatted.push_str(&format!("{:02}", self.minute)),
+                Some('S') => formatted.push_str(&format!("{:02}", self.second)),
+                Some('j') => formatted.push_str(&format!("{:03}", self.ordinal())),
+                Some('s') => formatted.push_str(&self.to_unix_timestamp().to_string()),
+                Some('%') => formatted.push('%'),
+                Some('-') => match chars.next() {
+                    Some('m') => formatted.push_str(&self.month.to_string()),
+                    Some('d') => formatted.push_str(&self.day.to_string()),
+                    Some(other) => formatted.push_str(&format!("%-{}", other)),
+                    None => formatted.push_str("%-"),
+                },
+                Some(other) => formatted.push_str(&format!("%{}", other)),
+                None => formatted.push('%'),
+            };
+        }
+        return formatted;
+    }
+
+    /// Creates a `DateTime` from its components.
+    /// 
+    /// ### Examples
+    /// ```
+    /// let d = DateTime::from_ymd_hms(2021, 4, 23, 16, 0, 0);
+    /// assert_eq!(d.to_string(), "4/23/2021 16:00:00");
+    /// ```
+    pub fn from_ymd_hms(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Self {
+        return Self { year, month, day, hour, minute, second };
+    }
+
+    /// Creates a `DateTime` from the number of seconds since
+    /// 1/1/1970 00:00:00.
+    /// 
+    /// Panics if the timestamp is outside the years 1 to 9999. Use
+    /// `DateTime::try_from_unix_timestamp` to handle the error instead.
+    /// 
+    /// ### Examples
+    /// ```
+    /// let d = DateTime::from_unix_timestamp(1619193600);
+    /// assert_eq!(d.to_string(), "4/23/2021 16:00:00");
+    /// ```
+    pub fn from_unix_timestamp(timestamp: i64) -> Self {
+        return match Self::try_from_unix_timestamp(timestamp) {
+            Ok(d) => d,
+            Err(error) => panic!("{}", error),
+        };
+    }
+
+    /// Creates a `DateTime` from the number of seconds since
+    /// 1/1/1970 00:00:00. Returns a `DateTimeError` if the timestamp is
+    /// outside the years 1 to 9999.
+    pub fn try_from_unix_timestamp(timestamp: i64) -> Result<Self, DateTimeError> {
+        if !timestamp_in_range(timestamp) {
+            return Err(DateTimeError::OutOfRange { input: timestamp.to_string(), field: "timestamp", value: timestamp });
+        }
+        let days = timestamp.div_euclid(SECONDS_PER_DAY);
+        let secs = timestamp.rem_euclid(SECONDS_PER_DAY);
+        let (year, month, day) = civil_from_days(days);
+        return Ok(Self {
+            year: year as u16,
+            month: month,
+            day: day,
+            hour: (secs / 3600) as u8,
+            minute: (secs % 3600 / 60) as u8,
+            second: (secs % 60) as u8,
+        });
+    }
+
+    /// Returns the current UTC time from the system clock.
+    pub fn now() -> Self {
+        let timestamp = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs() as i64,
+            Err(_) => 0,
+        };
+        return Self::from_unix_timestamp(timestamp);
+    }
+
+    /// Returns the number of seconds since 1/1/1970 00:00:00.
+    /// 
+    /// ### Examples
+    /// ```
+    /// let d = DateTime::new("4/23/2021 16:00:00");
+    /// assert_eq!(d.to_unix_timestamp(), 1619193600);
+    /// ```
+    pub fn to_unix_timestamp(&self) -> i64 {
+        let days = days_from_civil(self.year as i64, self.month, self.day);
+        return days * SECONDS_PER_DAY + self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64;
+    }
+
+    /// Returns the number of whole days since 1/1/1970, ignoring the time.
+    pub fn to_epoch_day(&self) -> i64 {
+        return days_from_civil(self.year as i64, self.month, self.day);
+    }
+
+    pub fn add_seconds(&self, seconds: i64) -> Self {
+        return Self::from_unix_timestamp(self.to_unix_timestamp() + seconds);
+    }
+
+    pub fn sub_seconds(&self, seconds: i64) -> Self {
+        return self.add_seconds(-seconds);
+    }
+
+    /// Moves the date by `days` calendar days, keeping the time.
+    /// 
+    /// ### Examples
+    /// ```
+    /// let d = DateTime::new("2/28/2020 16:00:00");
+    /// assert_eq!(d.add_days(1).to_string(), "2/29/2020 16:00:00");
+    /// assert_eq!(d.add_days(2).to_string(), "3/1/2020 16:00:00");
+    /// ```
+    pub fn add_days(&self, days: i64) -> Self {
+        return self.add_seconds(days * SECONDS_PER_DAY);
+    }
+
+    pub fn sub_days(&self, days: i64) -> Self {
+        return self.add_days(-days);
+    }
+
+    pub fn add_weeks(&self, weeks: i64) -> Self {
+        return self.add_days(weeks * 7);
+    }
+
+    pub fn sub_weeks(&self, weeks: i64) -> Self {
+        return self.add_days(-weeks * 7);
+    }
+
+    /// Moves the date by `days` weekdays, skipping Saturdays and Sundays.
+    /// Exchange holidays are not taken into account. Starting from a
+    /// weekend, the first step lands on the adjacent weekday.
+    /// 
+    /// ### Examples
+    /// ```
+    /// let d = DateTime::new("4/23/2021 16:00:00"); // Friday
+    /// assert_eq!(d.add_trading_days(1).to_string(), "4/26/2021 16:00:00");
+    /// assert_eq!(d.sub_trading_days(5).to_string(), "4/16/2021 16:00:00");
+    /// ```
+    pub fn add_trading_days(&self, days: i64) -> Self {
+        let step = if days < 0 { -1 } else { 1 };
+        let mut remaining = days.abs();
+        let mut d = self.clone();
+        while remaining > 0 {
+            d = d.add_days(step);
+            if !d.weekday().is_weekend() { remaining -= 1; }
+        }
+        return d;
+    }
+
+    pub fn sub_trading_days(&self, days: i64) -> Self {
+        return self.add_trading_days(-days);
+    }
+
+    /// Moves the date by `months` calendar months, keeping the time. The
+    /// day is clamped to the length of the resulting month. Panics if the
+    /// result is outside the years 1 to 9999.
+    /// 
+    /// ### Examples
+    /// ```
+    /// let d = DateTime::new("1/31/2021 16:00:00");
+    /// assert_eq!(d.add_months(1).to_string(), "2/28/2021 16:00:00");
+    /// assert_eq!(d.sub_months(2).to_string(), "11/30/2020 16:00:00");
+    /// ```
+    pub fn add_months(&self, months: i64) -> Self {
+        let total = self.year as i64 * 12 + (self.month as i64 - 1) + months;
+        let year = total.div_euclid(12);
+        if !(1..=9999).contains(&year) {
+            panic!("{}", DateTimeError::OutOfRange { input: self.to_string(), field: "year", value: year });
         }
+        let year = year as u16;
+        let month = (total.rem_euclid(12) + 1) as u8;
+        let day = std::cmp::min(self.day, days_in_month(year, month));
+        return Self::from_ymd_hms(year, month, day, self.hour, self.minute, self.second);
+    }
+
+    pub fn sub_months(&self, months: i64) -> Self {
+        return self.add_months(-months);
+    }
+
+    /// Moves the date by `years` calendar years, keeping the time. 2/29
+    /// becomes 2/28 in non-leap years.
+    pub fn add_years(&self, years: i64) -> Self {
+        return self.add_months(years * 12);
+    }
+
+    pub fn sub_years(&self, years: i64) -> Self {
+        return self.add_months(-years * 12);
+    }
+
+    /// Returns the number of seconds from `d` to the caller. The result is
+    /// negative if the caller comes before `d`.
+    /// 
+    /// ### Examples
+    /// ```
+    /// let d1 = DateTime::new("4/23/2021 16:00:00");
+    /// let d2 = DateTime::new("4/23/2021 09:30:00");
+    /// assert_eq!(d1.seconds_since(&d2), 23400);
+    /// ```
+    pub fn seconds_since(&self, d: &DateTime) -> i64 {
+        return self.to_unix_timestamp() - d.to_unix_timestamp();
+    }
+
+    /// Returns the number of calendar days from `d` to the caller,
+    /// ignoring the time. The result is negative if the caller comes
+    /// before `d`.
+    /// 
+    /// ### Examples
+    /// ```
+    /// let d1 = DateTime::new("3/1/2020 00:00:00");
+    /// let d2 = DateTime::new("2/1/2020 23:59:59");
+    /// assert_eq!(d1.days_since(&d2), 29);
+    /// ```
+    pub fn days_since(&self, d: &DateTime) -> i64 {
+        return self.to_epoch_day() - d.to_epoch_day();
+    }
 
-        return Self {
-            month: month.parse::<u8>().unwrap(),
-            day: day.parse::<u8>().unwrap(),
-            year: year.parse::<u16>().unwrap(),
-            hour: hour.parse::<u8>().unwrap(),
-            minute: minute.parse::<u8>().unwrap(),
-            second: second.parse::<u8>().unwrap(),
+    pub fn weekday(&self) -> Weekday {
+        return Weekday::from_days_since_epoch(self.to_epoch_day());
+    }
+
+    /// Returns the day of the year, from 1 to 366.
+    pub fn ordinal(&self) -> u16 {
+        return (self.to_epoch_day() - days_from_civil(self.year as i64, 1, 1) + 1) as u16;
+    }
+
+    /// Returns the ISO-8601 `(year, week)`. Weeks start on Monday and week 1
+    /// contains the year's first Thursday, so the ISO year can differ from
+    /// the calendar year around New Year.
+    /// 
+    /// ### Examples
+    /// ```
+    /// assert_eq!(DateTime::new("1/1/2021 00:00:00").iso_week(), (2020, 53));
+    /// assert_eq!(DateTime::new("4/23/2021 00:00:00").iso_week(), (2021, 16));
+    /// ```
+    pub fn iso_week(&self) -> (u16, u8) {
+        let year = self.year as i64;
+        let week = (self.ordinal() as i64 - self.weekday().number_from_monday() as i64 + 10) / 7;
+        if week < 1 {
+            return ((year - 1) as u16, iso_weeks_in_year(year - 1));
+        }
+        if week > iso_weeks_in_year(year) as i64 {
+            return ((year + 1) as u16, 1);
         }
+        return (self.year, week as u8);
+    }
+
+    pub fn is_leap_year(&self) -> bool {
+        return is_leap_year(self.year);
+    }
+
+    /// Returns the number of seconds elapsed since midnight.
+    pub fn seconds_of_day(&self) -> u32 {
+        return self.hour as u32 * 3600 + self.minute as u32 * 60 + self.second as u32;
+    }
+
+    /// Returns midnight at the start of the caller's day.
+    pub fn start_of_day(&self) -> Self {
+        return Self::from_ymd_hms(self.year, self.month, self.day, 0, 0, 0);
+    }
+
+    /// Returns midnight on 1/1 of the caller's year, e.g. the start of a
+    /// year-to-date window.
+    pub fn start_of_year(&self) -> Self {
+        return Self::from_ymd_hms(self.year, 1, 1, 0, 0, 0);
     }
 
     pub fn get_year(&self) -> u16 { return self.year; }
@@ -72,8 +726,7 @@ impl DateTime {
     /// assert_eq!(d2.is_on(&d1), true);
     /// ```
     pub fn is_on(&self, d: &DateTime) -> bool {
-        if self.year == d.get_year() && self.month == d.get_month() && self.day == d.get_day() && self.hour == d.get_hour() && self.minute == d.get_minute() && self.second == d.get_second() { return true; }
-        return false;
+        return self == d;
     }
 
     /// Compares two `DateTime`s and returns `true` if the caller comes
@@ -86,13 +739,7 @@ impl DateTime {
     /// assert_eq!(d2.is_after(&d1), false);
     /// ```
     pub fn is_after(&self, d: &DateTime) -> bool {
-        if self.second > d.get_second() { return true; }
-        if self.minute > d.get_minute() { return true; }
-        if self.hour > d.get_hour() { return true; }
-        if self.day > d.get_day() { return true; }
-        if self.month > d.get_month() { return true; }
-        if self.year > d.get_year() { return true; }
-        return false;
+        return self > d;
     }
 
     /// Compares two `DateTime`s and returns `true` if the caller is on
@@ -105,10 +752,7 @@ impl DateTime {
     /// assert_eq!(d2.is_on_or_after(&d1), false);
     /// ```
     pub fn is_on_or_after(&self, d: &DateTime) -> bool {
-        let is_on = self.is_on(d);
-        let is_after = self.is_after(d);
-        if is_on || is_after { return true; }
-        return false;
+        return self >= d;
     }
 
     /// Compares two `DateTime`s and returns `true` if the caller comes
@@ -121,13 +765,7 @@ impl DateTime {
     /// assert_eq!(d2.is_before(&d1), false);
     /// ```
     pub fn is_before(&self, d: &DateTime) -> bool {
-        if self.second < d.get_second() { return true; }
-        if self.minute < d.get_minute() { return true; }
-        if self.hour < d.get_hour() { return true; }
-        if self.day < d.get_day() { return true; }
-        if self.month < d.get_month() { return true; }
-        if self.year < d.get_year() { return true; }
-        return false;
+        return self < d;
     }
 
     /// Compares two `DateTime`s and returns `true` if the caller is on
@@ -140,12 +778,10 @@ impl DateTime {
     /// assert_eq!(d2.is_on_or_before(&d1), false);
     /// ```
     pub fn is_on_or_before(&self, d: &DateTime) -> bool {
-        let is_on = self.is_on(d);
-        let is_before = self.is_before(d);
-        if is_on || is_before { return true; }
-        return false;
+        return self <= d;
     }
 
+    #[allow(clippy::inherent_to_string)]
     pub fn to_string(&self) -> String {
         let hr = if self.hour < 10 { format!("0{}", self.hour) } else { format!("{}", self.hour) };
         let min = if self.minute < 10 { format!("0{}", self.minute) } else { format!("{}", self.minute) };
@@ -154,6 +790,14 @@ impl DateTime {
     }
 }
 
+impl FromStr for DateTime {
+    type Err = DateTimeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return DateTime::parse(s);
+    }
+}
+
 /// Compares two `DateTime`s and returns `true` if the first `DateTime` is
 /// before the second. Returns `false` if `DateTime`s are exquivalent.
 /// 
@@ -245,4 +889,309 @@ mod tests {
         assert_eq!(d1.is_before(&d2), false);
         assert_eq!(d1.is_on_or_before(&d2), false);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_comparisons_cross_field() {
+        // A later second must not outweigh an earlier year
+        let d1 = DateTime::new("1/1/2021 00:00:01");
+        let d2 = DateTime::new("12/31/2021 00:00:00");
+        assert_eq!(d1.is_after(&d2), false);
+        assert_eq!(d1.is_before(&d2), true);
+        assert_eq!(d2.is_after(&d1), true);
+        assert_eq!(d2.is_before(&d1), false);
+
+        // Later month, earlier year
+        let d1 = DateTime::new("12/1/2020 16:00:00");
+        let d2 = DateTime::new("1/1/2021 16:00:00");
+        assert_eq!(d1.is_after(&d2), false);
+        assert_eq!(d1.is_on_or_after(&d2), false);
+        assert_eq!(d1.is_before(&d2), true);
+
+        // Later day, earlier month
+        let d1 = DateTime::new("3/31/2021 16:00:00");
+        let d2 = DateTime::new("4/1/2021 09:30:00");
+        assert_eq!(d1.is_after(&d2), false);
+        assert_eq!(d1.is_before(&d2), true);
+        assert_eq!(d2.is_on_or_before(&d1), false);
+
+        // Later minute, earlier hour
+        let d1 = DateTime::new("4/23/2021 15:59:59");
+        let d2 = DateTime::new("4/23/2021 16:00:00");
+        assert_eq!(d1.is_after(&d2), false);
+        assert_eq!(d1.is_before(&d2), true);
+    }
+
+    #[test]
+    fn test_ord() {
+        let mut dates = [
+            DateTime::new("12/31/2021 00:00:00"),
+            DateTime::new("1/1/2021 00:00:01"),
+            DateTime::new("4/23/2020 16:00:00"),
+            DateTime::new("1/1/2021 00:00:00"),
+        ];
+        dates.sort();
+        let dates: Vec<String> = dates.iter().map(|d| d.to_string()).collect();
+        assert_eq!(dates, vec!["4/23/2020 16:00:00", "1/1/2021 00:00:00", "1/1/2021 00:00:01", "12/31/2021 00:00:00"]);
+        assert_eq!(DateTime::new("4/23/2021 16:00:00"), DateTime::new("04/23/2021 16:00:00"));
+        assert!(DateTime::new("1/1/2021 00:00:01") < DateTime::new("12/31/2021 00:00:00"));
+    }
+
+    #[test]
+    fn test_btreemap_and_hash() {
+        use std::collections::{BTreeMap, HashSet};
+        let mut map: BTreeMap<DateTime, f64> = BTreeMap::new();
+        map.insert(DateTime::new("12/31/2021 00:00:00"), 3.0);
+        map.insert(DateTime::new("1/1/2021 00:00:01"), 2.0);
+        map.insert(DateTime::new("1/1/2021 00:00:00"), 1.0);
+        let values: Vec<f64> = map.values().cloned().collect();
+        assert_eq!(values, vec![1.0, 2.0, 3.0]);
+
+        let mut set: HashSet<DateTime> = HashSet::new();
+        set.insert(DateTime::new("1/1/2021 00:00:00"));
+        set.insert(DateTime::new("01/01/2021 00:00:00"));
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn test_leap_years() {
+        assert_eq!(is_leap_year(2020), true);
+        assert_eq!(is_leap_year(2021), false);
+        assert_eq!(is_leap_year(1900), false);
+        assert_eq!(is_leap_year(2000), true);
+        assert_eq!(days_in_month(2020, 2), 29);
+        assert_eq!(days_in_month(2021, 2), 28);
+        assert_eq!(days_in_month(2021, 4), 30);
+        assert_eq!(days_in_month(2021, 12), 31);
+    }
+
+    #[test]
+    fn test_unix_timestamp() {
+        assert_eq!(DateTime::new("1/1/1970 00:00:00").to_unix_timestamp(), 0);
+        assert_eq!(DateTime::new("4/23/2021 16:00:00").to_unix_timestamp(), 1619193600);
+        assert_eq!(DateTime::new("2/29/2020 12:30:45").to_unix_timestamp(), 1582979445);
+        assert_eq!(DateTime::new("1/2/1962 16:00:00").to_unix_timestamp(), -252316800);
+        assert_eq!(DateTime::from_unix_timestamp(0).to_string(), "1/1/1970 00:00:00");
+        assert_eq!(DateTime::from_unix_timestamp(1619193600).to_string(), "4/23/2021 16:00:00");
+        assert_eq!(DateTime::from_unix_timestamp(1582979445).to_string(), "2/29/2020 12:30:45");
+        assert_eq!(DateTime::from_unix_timestamp(-252316800).to_string(), "1/2/1962 16:00:00");
+        assert_eq!(DateTime::from_unix_timestamp(-1).to_string(), "12/31/1969 23:59:59");
+    }
+
+    #[test]
+    fn test_add_sub_days() {
+        let d = DateTime::new("12/31/2020 16:00:00");
+        assert_eq!(d.add_days(1).to_string(), "1/1/2021 16:00:00");
+        assert_eq!(d.add_days(60).to_string(), "3/1/2021 16:00:00");
+        assert_eq!(d.sub_days(366).to_string(), "12/31/2019 16:00:00");
+        assert_eq!(d.add_weeks(1).to_string(), "1/7/2021 16:00:00");
+        assert_eq!(d.sub_weeks(52).to_string(), "1/2/2020 16:00:00");
+        assert_eq!(d.add_seconds(8 * 3600).to_string(), "1/1/2021 00:00:00");
+        assert_eq!(d.sub_seconds(1).to_string(), "12/31/2020 15:59:59");
+    }
+
+    #[test]
+    fn test_add_sub_trading_days() {
+        let friday = DateTime::new("4/23/2021 16:00:00");
+        assert_eq!(friday.add_trading_days(0).to_string(), "4/23/2021 16:00:00");
+        assert_eq!(friday.add_trading_days(1).to_string(), "4/26/2021 16:00:00");
+        assert_eq!(friday.add_trading_days(6).to_string(), "5/3/2021 16:00:00");
+        assert_eq!(friday.sub_trading_days(5).to_string(), "4/16/2021 16:00:00");
+        let saturday = DateTime::new("4/24/2021 16:00:00");
+        assert_eq!(saturday.add_trading_days(1).to_string(), "4/26/2021 16:00:00");
+        assert_eq!(saturday.sub_trading_days(1).to_string(), "4/23/2021 16:00:00");
+    }
+
+    #[test]
+    fn test_add_sub_months_years() {
+        let d = DateTime::new("1/31/2020 16:00:00");
+        assert_eq!(d.add_months(1).to_string(), "2/29/2020 16:00:00");
+        assert_eq!(d.add_months(13).to_string(), "2/28/2021 16:00:00");
+        assert_eq!(d.sub_months(1).to_string(), "12/31/2019 16:00:00");
+        assert_eq!(d.sub_months(14).to_string(), "11/30/2018 16:00:00");
+        let d = DateTime::new("2/29/2020 16:00:00");
+        assert_eq!(d.add_years(1).to_string(), "2/28/2021 16:00:00");
+        assert_eq!(d.add_years(4).to_string(), "2/29/2024 16:00:00");
+        assert_eq!(d.sub_years(1).to_string(), "2/28/2019 16:00:00");
+    }
+
+    #[test]
+    fn test_differences() {
+        let d1 = DateTime::new("1/1/2021 00:00:00");
+        let d2 = DateTime::new("12/31/2020 23:59:59");
+        assert_eq!(d1.seconds_since(&d2), 1);
+        assert_eq!(d2.seconds_since(&d1), -1);
+        assert_eq!(d1.days_since(&d2), 1);
+        let d1 = DateTime::new("1/1/2021 16:00:00");
+        let d2 = DateTime::new("1/1/2020 16:00:00");
+        assert_eq!(d1.days_since(&d2), 366);
+        assert_eq!(d1.seconds_since(&d2), 366 * 86_400);
+    }
+
+    #[test]
+    fn test_weekday() {
+        assert_eq!(DateTime::new("1/1/1970 00:00:00").weekday(), Weekday::Thursday);
+        assert_eq!(DateTime::new("4/23/2021 16:00:00").weekday(), Weekday::Friday);
+        assert_eq!(DateTime::new("4/24/2021 16:00:00").weekday(), Weekday::Saturday);
+        assert_eq!(DateTime::new("4/25/2021 16:00:00").weekday(), Weekday::Sunday);
+        assert_eq!(DateTime::new("1/2/1962 16:00:00").weekday(), Weekday::Tuesday);
+        assert_eq!(DateTime::new("2/29/2000 00:00:00").weekday(), Weekday::Tuesday);
+    }
+
+    #[test]
+    fn test_ordinal_and_iso_week() {
+        assert_eq!(DateTime::new("1/1/2021 00:00:00").ordinal(), 1);
+        assert_eq!(DateTime::new("12/31/2020 00:00:00").ordinal(), 366);
+        assert_eq!(DateTime::new("12/31/2021 00:00:00").ordinal(), 365);
+        assert_eq!(DateTime::new("1/1/2021 00:00:00").iso_week(), (2020, 53));
+        assert_eq!(DateTime::new("1/4/2021 00:00:00").iso_week(), (2021, 1));
+        assert_eq!(DateTime::new("4/23/2021 00:00:00").iso_week(), (2021, 16));
+        assert_eq!(DateTime::new("12/29/2014 00:00:00").iso_week(), (2015, 1));
+        assert_eq!(DateTime::new("1/1/2018 00:00:00").iso_week(), (2018, 1));
+        assert_eq!(DateTime::new("12/31/2018 00:00:00").iso_week(), (2019, 1));
+        assert_eq!(DateTime::new("1/1/2017 00:00:00").iso_week(), (2016, 52));
+    }
+
+    #[test]
+    fn test_windows() {
+        let d = DateTime::new("4/23/2021 16:00:00");
+        assert_eq!(d.start_of_year().to_string(), "1/1/2021 00:00:00");
+        assert_eq!(d.start_of_day().to_string(), "4/23/2021 00:00:00");
+        assert_eq!(d.sub_weeks(52).to_string(), "4/24/2020 16:00:00");
+    }
+
+    #[test]
+    fn test_parse_legacy() {
+        assert_eq!(DateTime::parse("4/23/2021 16:00:00").unwrap().to_string(), "4/23/2021 16:00:00");
+        assert_eq!(DateTime::parse("04/03/2021 09:30:05").unwrap().to_string(), "4/3/2021 09:30:05");
+        assert_eq!(DateTime::parse("4/23/2021 16:00").unwrap().to_string(), "4/23/2021 16:00:00");
+        assert_eq!(DateTime::parse("4/23/2021").unwrap().to_string(), "4/23/2021 00:00:00");
+        assert_eq!(DateTime::parse("  4/23/2021 16:00:00 ").unwrap().to_string(), "4/23/2021 16:00:00");
+        assert_eq!("2/29/2020 16:00:00".parse::<DateTime>().unwrap().to_string(), "2/29/2020 16:00:00");
+    }
+
+    #[test]
+    fn test_parse_iso() {
+        assert_eq!(DateTime::parse("2021-04-23").unwrap().to_string(), "4/23/2021 00:00:00");
+        assert_eq!(DateTime::parse("2021-04-23T16:00:00").unwrap().to_string(), "4/23/2021 16:00:00");
+        assert_eq!(DateTime::parse("2021-04-23T16:00:00Z").unwrap().to_string(), "4/23/2021 16:00:00");
+        assert_eq!(DateTime::parse("2021-04-23 16:00").unwrap().to_string(), "4/23/2021 16:00:00");
+        assert_eq!(DateTime::parse("2021-04-23T16:00:00.123Z").unwrap().to_string(), "4/23/2021 16:00:00");
+        assert_eq!(DateTime::parse("2021-04-23T16:00:00-04:00").unwrap().to_string(), "4/23/2021 20:00:00");
+        assert_eq!(DateTime::parse("2021-04-23T01:30:00+0200").unwrap().to_string(), "4/22/2021 23:30:00");
+        assert_eq!(DateTime::parse("2021-04-23T16:00:00+05").unwrap().to_string(), "4/23/2021 11:00:00");
+        assert_eq!(
+            DateTime::parse("9999-12-31T23:00:00-05:00"),
+            Err(DateTimeError::OutOfRange { input: "9999-12-31T23:00:00-05:00".to_string(), field: "timestamp", value: 253402315200 }),
+        );
+    }
+
+    #[test]
+    fn test_parse_offset_matches_epoch() {
+        let iso = DateTime::parse("2021-03-01T09:30:00-05:00").unwrap();
+        assert_eq!(iso, DateTime::parse("1614609000").unwrap());
+        assert_eq!(iso, DateTime::parse("2021-03-01T14:30:00Z").unwrap());
+        assert_eq!(iso.to_string(), "3/1/2021 14:30:00");
+        assert_eq!(DateTime::parse_with_offset("2021-03-01T09:30:00-05:00").unwrap().0.to_string(), "3/1/2021 09:30:00");
+    }
+
+    #[test]
+    fn test_parse_with_offset() {
+        let offset = |input: &str| DateTime::parse_with_offset(input).unwrap().1;
+        assert_eq!(offset("2021-04-23T16:00:00-04:00"), Some(-14400));
+        assert_eq!(offset("2021-04-23T01:30:00+0200"), Some(7200));
+        assert_eq!(offset("2021-04-23T16:00:00Z"), Some(0));
+        assert_eq!(offset("2021-04-23T16:00:00"), None);
+        assert_eq!(offset("4/23/2021 16:00:00"), None);
+        assert_eq!(offset("1619193600"), Some(0));
+    }
+
+    #[test]
+    fn test_parse_compact() {
+        assert_eq!(DateTime::parse("20220608").unwrap().to_string(), "6/8/2022 00:00:00");
+        assert_eq!(DateTime::parse("20221332"), Err(DateTimeError::OutOfRange { input: "20221332".to_string(), field: "month", value: 13 }));
+        assert_eq!(DateTime::parse("00000101"), Err(DateTimeError::OutOfRange { input: "00000101".to_string(), field: "year", value: 0 }));
+    }
+
+    #[test]
+    fn test_parse_epoch() {
+        assert_eq!(DateTime::parse("0").unwrap().to_string(), "1/1/1970 00:00:00");
+        assert_eq!(DateTime::parse("1619193600").unwrap().to_string(), "4/23/2021 16:00:00");
+        assert_eq!(DateTime::parse("1619193600000").unwrap().to_string(), "4/23/2021 16:00:00");
+        assert_eq!(DateTime::parse("1619193600999").unwrap().to_string(), "4/23/2021 16:00:00");
+        assert_eq!(DateTime::parse("-252316800").unwrap().to_string(), "1/2/1962 16:00:00");
+        assert_eq!(DateTime::parse("-1000000").unwrap().to_string(), "12/20/1969 10:13:20");
+        assert_eq!(DateTime::parse("99999999999999999999"), Err(DateTimeError::OutOfRange { input: "99999999999999999999".to_string(), field: "timestamp", value: 0 }));
+        assert_eq!(DateTime::try_from_unix_timestamp(i64::MAX), Err(DateTimeError::OutOfRange { input: i64::MAX.to_string(), field: "timestamp", value: i64::MAX }));
+        assert_eq!(DateTime::try_from_unix_timestamp(1619193600).unwrap().to_string(), "4/23/2021 16:00:00");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid year 10000 in datetime string \"12/31/9999 16:00:00\".")]
+    fn test_add_months_out_of_range() {
+        DateTime::new("12/31/9999 16:00:00").add_months(1);
+    }
+
+    #[test]
+    fn test_parse_out_of_range() {
+        assert_eq!(DateTime::parse("13/1/2021"), Err(DateTimeError::OutOfRange { input: "13/1/2021".to_string(), field: "month", value: 13 }));
+        assert_eq!(DateTime::parse("2021-02-30"), Err(DateTimeError::OutOfRange { input: "2021-02-30".to_string(), field: "day", value: 30 }));
+        assert_eq!(DateTime::parse("2/29/2021"), Err(DateTimeError::OutOfRange { input: "2/29/2021".to_string(), field: "day", value: 29 }));
+        assert_eq!(DateTime::parse("4/0/2021"), Err(DateTimeError::OutOfRange { input: "4/0/2021".to_string(), field: "day", value: 0 }));
+        assert_eq!(DateTime::parse("4/23/2021 24:00:00"), Err(DateTimeError::OutOfRange { input: "4/23/2021 24:00:00".to_string(), field: "hour", value: 24 }));
+        assert_eq!(DateTime::parse("2021-04-23T16:60"), Err(DateTimeError::OutOfRange { input: "2021-04-23T16:60".to_string(), field: "minute", value: 60 }));
+        assert_eq!(DateTime::parse("4/23/2021 16:00:61"), Err(DateTimeError::OutOfRange { input: "4/23/2021 16:00:61".to_string(), field: "second", value: 61 }));
+        assert_eq!(DateTime::parse("0000-01-01"), Err(DateTimeError::OutOfRange { input: "0000-01-01".to_string(), field: "year", value: 0 }));
+    }
+
+    #[test]
+    fn test_parse_invalid_format() {
+        assert_eq!(DateTime::parse(""), Err(DateTimeError::Empty));
+        assert_eq!(DateTime::parse("   "), Err(DateTimeError::Empty));
+        for input in ["4/23/21", "4/23", "2021/04", "April 23, 2021", "2021-04", "21-04-23", "2021-04-23T16", "4/23/2021 16:00:00:00", "2021-04-23T16:00:00+5", "4/23/2021 4pm"] {
+            assert_eq!(DateTime::parse(input), Err(DateTimeError::InvalidFormat { input: input.to_string() }), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_error_display() {
+        assert_eq!(DateTime::parse("").unwrap_err().to_string(), "Cannot parse an empty datetime string.");
+        assert_eq!(DateTime::parse("4/23").unwrap_err().to_string(), "Unrecognized datetime format \"4/23\".");
+        assert_eq!(DateTime::parse("2021-13-01").unwrap_err().to_string(), "Invalid month 13 in datetime string \"2021-13-01\".");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid month 13 in datetime string \"13/1/2021 00:00:00\".")]
+    fn test_new_panics() {
+        DateTime::new("13/1/2021 00:00:00");
+    }
+
+    #[test]
+    fn test_format() {
+        let d = DateTime::new("4/3/2021 16:05:09");
+        assert_eq!(d.format("%Y-%m-%d"), "2021-04-03");
+        assert_eq!(d.format("%Y-%m-%dT%H:%M:%SZ"), "2021-04-03T16:05:09Z");
+        assert_eq!(d.format("%-m/%-d/%Y %H:%M:%S"), d.to_string());
+        assert_eq!(d.format("%a %b %-d, %Y"), "Sat Apr 3, 2021");
+        assert_eq!(d.format("%m/%d/%y"), "04/03/21");
+        assert_eq!(d.format("%j"), "093");
+        assert_eq!(d.format("%s"), "1617465909");
+        assert_eq!(d.format("100%% %q %"), "100% %q %");
+    }
+
+    #[test]
+    fn test_nth_and_last_weekday_of_month() {
+        assert_eq!(nth_weekday_of_month(2021, 1, Weekday::Monday, 3).to_string(), "1/18/2021 00:00:00");
+        assert_eq!(nth_weekday_of_month(2021, 3, Weekday::Sunday, 2).to_string(), "3/14/2021 00:00:00");
+        assert_eq!(nth_weekday_of_month(2021, 11, Weekday::Sunday, 1).to_string(), "11/7/2021 00:00:00");
+        assert_eq!(nth_weekday_of_month(2021, 11, Weekday::Thursday, 4).to_string(), "11/25/2021 00:00:00");
+        assert_eq!(last_weekday_of_month(2021, 5, Weekday::Monday).to_string(), "5/31/2021 00:00:00");
+        assert_eq!(last_weekday_of_month(2021, 10, Weekday::Sunday).to_string(), "10/31/2021 00:00:00");
+        assert_eq!(last_weekday_of_month(2020, 2, Weekday::Saturday).to_string(), "2/29/2020 00:00:00");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_nth_weekday_of_month_missing() {
+        nth_weekday_of_month(2021, 2, Weekday::Monday, 5);
+    }
+}
@@ -0,0 +1,434 @@
+use std::cmp::Ordering;
+
+use crate::stock::datetime::{DateTime, Weekday, nth_weekday_of_month, last_weekday_of_month};
+
+/// Time zones that exchange timestamps can be expressed in.
+///
+/// - `Utc`: Coordinated Universal Time
+/// - `Fixed`: a constant offset from UTC in seconds (ex. `-14400` for UTC-4)
+/// - `UsEastern`: New York time, observing US daylight saving time
+///
+/// `UsEastern` follows the US daylight saving rules in effect since 1967.
+/// Earlier timestamps, and the 1974-1975 emergency schedule, are treated as
+/// standard time.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum TimeZone {
+    Utc,
+    Fixed(i32),
+    UsEastern,
+}
+
+const EASTERN_STANDARD_OFFSET: i32 = -5 * 3600;
+const EASTERN_DAYLIGHT_OFFSET: i32 = -4 * 3600;
+
+#[allow(dead_code)]
+impl TimeZone {
+    /// Returns the offset from UTC in seconds at the given UTC instant.
+    ///
+    /// ### Examples
+    /// ```
+    /// let zone = TimeZone::UsEastern;
+    /// assert_eq!(zone.offset_at(&DateTime::new("1/4/2021 14:30:00")), -18000);
+    /// assert_eq!(zone.offset_at(&DateTime::new("4/23/2021 13:30:00")), -14400);
+    /// ```
+    pub fn offset_at(&self, utc: &DateTime) -> i32 {
+        return match self {
+            TimeZone::Utc => 0,
+            TimeZone::Fixed(offset) => *offset,
+            TimeZone::UsEastern => if is_us_eastern_dst(utc) { EASTERN_DAYLIGHT_OFFSET } else { EASTERN_STANDARD_OFFSET },
+        };
+    }
+
+    /// Returns `true` if daylight saving time is in effect at the given UTC
+    /// instant.
+    pub fn is_dst_at(&self, utc: &DateTime) -> bool {
+        return match self {
+            TimeZone::UsEastern => is_us_eastern_dst(utc),
+            _ => false,
+        };
+    }
+
+    /// Returns the abbreviation in effect at the given UTC instant, ex. `EDT`.
+    pub fn abbreviation_at(&self, utc: &DateTime) -> String {
+        return match self {
+            TimeZone::Utc => "UTC".to_string(),
+            TimeZone::Fixed(offset) => format!("UTC{}", format_offset(*offset)),
+            TimeZone::UsEastern => if is_us_eastern_dst(utc) { "EDT".to_string() } else { "EST".to_string() },
+        };
+    }
+}
+
+/// Daylight saving start and end, as UTC instants, for the given year. Both
+/// transitions happen at 2:00 local time.
+///
+/// #### Resources
+/// - [Daylight saving time in the United States](https://en.wikipedia.org/wiki/Daylight_saving_time_in_the_United_States)
+fn us_eastern_dst_range(year: u16) -> Option<(DateTime, DateTime)> {
+    let (start, end) = if year >= 2007 {
+        (nth_weekday_of_month(year, 3, Weekday::Sunday, 2), nth_weekday_of_month(year, 11, Weekday::Sunday, 1))
+    } else if year >= 1987 {
+        (nth_weekday_of_month(year, 4, Weekday::Sunday, 1), last_weekday_of_month(year, 10, Weekday::Sunday))
+    } else if year >= 1967 {
+        (last_weekday_of_month(year, 4, Weekday::Sunday), last_weekday_of_month(year, 10, Weekday::Sunday))
+    } else {
+        return None;
+    };
+    // 2:00 EST is 7:00 UTC and 2:00 EDT is 6:00 UTC
+    return Some((start.add_seconds(7 * 3600), end.add_seconds(6 * 3600)));
+}
+
+fn is_us_eastern_dst(utc: &DateTime) -> bool {
+    return match us_eastern_dst_range(utc.get_year()) {
+        Some((start, end)) => utc >= &start && utc < &end,
+        None => false,
+    };
+}
+
+/// Formats an offset in seconds as `±hh:mm`.
+fn format_offset(offset: i32) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.abs();
+    return format!("{}{:02}:{:02}", sign, offset / 3600, offset % 3600 / 60);
+}
+
+
+/// An instant in time together with the time zone it should be displayed
+/// in. The instant is stored in UTC, so comparisons between timestamps in
+/// different zones are chronological.
+///
+/// ### Example
+/// ```
+/// let close = ZonedDateTime::from_local(&DateTime::new("4/23/2021 16:00:00"), TimeZone::UsEastern);
+/// assert_eq!(close.get_utc().to_string(), "4/23/2021 20:00:00");
+/// assert_eq!(close.to_rfc3339(), "2021-04-23T16:00:00-04:00");
+/// ```
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct ZonedDateTime {
+    utc: DateTime,
+    zone: TimeZone,
+}
+
+#[allow(dead_code)]
+impl ZonedDateTime {
+    pub fn from_utc(utc: &DateTime, zone: TimeZone) -> Self {
+        return Self { utc: utc.clone(), zone: zone };
+    }
+
+    /// Interprets `local` as a wall-clock time in `zone`.
+    ///
+    /// When clocks fall back, a repeated local time resolves to the first
+    /// (daylight saving) occurrence. When clocks spring forward, a skipped
+    /// local time is read with the standard offset, so 2:30 becomes 3:30
+    /// daylight time.
+    pub fn from_local(local: &DateTime, zone: TimeZone) -> Self {
+        let (standard, daylight) = match zone {
+            TimeZone::Utc => (0, 0),
+            TimeZone::Fixed(offset) => (offset, offset),
+            TimeZone::UsEastern => (EASTERN_STANDARD_OFFSET, EASTERN_DAYLIGHT_OFFSET),
+        };
+        let as_daylight = local.sub_seconds(daylight as i64);
+        if zone.offset_at(&as_daylight) == daylight {
+            return Self { utc: as_daylight, zone: zone };
+        }
+        return Self { utc: local.sub_seconds(standard as i64), zone: zone };
+    }
+
+    pub fn get_utc(&self) -> &DateTime { return &self.utc; }
+    pub fn get_zone(&self) -> TimeZone { return self.zone; }
+
+    /// Returns the offset from UTC in seconds at this instant.
+    pub fn get_offset(&self) -> i32 {
+        return self.zone.offset_at(&self.utc);
+    }
+
+    pub fn is_dst(&self) -> bool {
+        return self.zone.is_dst_at(&self.utc);
+    }
+
+    /// Returns the wall-clock time in this timestamp's zone.
+    pub fn to_local(&self) -> DateTime {
+        return self.utc.add_seconds(self.get_offset() as i64);
+    }
+
+    /// Returns the same instant displayed in another zone.
+    pub fn with_zone(&self, zone: TimeZone) -> Self {
+        return Self { utc: self.utc.clone(), zone: zone };
+    }
+
+    pub fn to_unix_timestamp(&self) -> i64 {
+        return self.utc.to_unix_timestamp();
+    }
+
+    /// Formats the local time with its offset, ex. `2021-04-23T16:00:00-04:00`.
+    /// UTC is written with a `Z` suffix.
+    pub fn to_rfc3339(&self) -> String {
+        let local = self.to_local().format("%Y-%m-%dT%H:%M:%S");
+        if self.zone == TimeZone::Utc {
+            return format!("{}Z", local);
+        }
+        return format!("{}{}", local, format_offset(self.get_offset()));
+    }
+
+    #[allow(clippy::inherent_to_string)]
+    pub fn to_string(&self) -> String {
+        return format!("{} {}", self.to_local().to_string(), self.zone.abbreviation_at(&self.utc));
+    }
+}
+
+impl PartialEq for ZonedDateTime {
+    fn eq(&self, other: &Self) -> bool {
+        return self.utc == other.utc;
+    }
+}
+
+impl Eq for ZonedDateTime {}
+
+impl PartialOrd for ZonedDateTime {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+impl Ord for ZonedDateTime {
+    fn cmp(&self, other: &Self) -> Ordering {
+        return self.utc.cmp(&other.utc);
+    }
+}
+
+
+/// Trading session a timestamp falls in.
+///
+/// - `PreMarket`: extended hours before the regular open
+/// - `Regular`: the regular trading session
+/// - `AfterHours`: extended hours after the regular close
+/// - `Closed`: outside of any session, including weekends
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum Session {
+    PreMarket,
+    Regular,
+    AfterHours,
+    Closed,
+}
+
+#[allow(dead_code)]
+impl Session {
+    pub fn is_extended(&self) -> bool {
+        return *self == Session::PreMarket || *self == Session::AfterHours;
+    }
+
+    pub fn is_open(&self) -> bool {
+        return *self != Session::Closed;
+    }
+}
+
+
+/// Trading hours of an exchange, in seconds since local midnight. Sessions
+/// are half-open, so a timestamp exactly at the regular close belongs to
+/// the after-hours session. Bars are expected to be stamped with their
+/// start time.
+///
+/// ### Example
+/// ```
+/// let nyse = Exchange::nyse();
+/// assert_eq!(nyse.session_at_local(&DateTime::new("4/23/2021 09:30:00")), Session::Regular);
+/// assert_eq!(nyse.session_at_local(&DateTime::new("4/23/2021 08:00:00")), Session::PreMarket);
+/// ```
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Exchange {
+    name: String,
+    zone: TimeZone,
+    pre_market_open: u32,
+    regular_open: u32,
+    regular_close: u32,
+    after_hours_close: u32,
+}
+
+#[allow(dead_code)]
+impl Exchange {
+    pub fn new(name: &str, zone: TimeZone, pre_market_open: u32, regular_open: u32, regular_close: u32, after_hours_close: u32) -> Self {
+        return Self {
+            name: name.to_string(),
+            zone: zone,
+            pre_market_open: pre_market_open,
+            regular_open: regular_open,
+            regular_close: regular_close,
+            after_hours_close: after_hours_close,
+        };
+    }
+
+    /// New York Stock Exchange: pre-market 4:00-9:30, regular 9:30-16:00
+    /// and after-hours 16:00-20:00 New York time.
+    pub fn nyse() -> Self {
+        return Self::new("NYSE", TimeZone::UsEastern, 4 * 3600, 9 * 3600 + 1800, 16 * 3600, 20 * 3600);
+    }
+
+    /// NASDAQ shares the NYSE trading hours.
+    pub fn nasdaq() -> Self {
+        let mut exchange = Self::nyse();
+        exchange.name = "NASDAQ".to_string();
+        return exchange;
+    }
+
+    pub fn get_name(&self) -> String { return self.name.to_string(); }
+    pub fn get_zone(&self) -> TimeZone { return self.zone; }
+    pub fn get_pre_market_open(&self) -> u32 { return self.pre_market_open; }
+    pub fn get_regular_open(&self) -> u32 { return self.regular_open; }
+    pub fn get_regular_close(&self) -> u32 { return self.regular_close; }
+    pub fn get_after_hours_close(&self) -> u32 { return self.after_hours_close; }
+
+    /// Returns the session at the given instant.
+    pub fn session_at(&self, timestamp: &ZonedDateTime) -> Session {
+        return self.session_at_local(&timestamp.with_zone(self.zone).to_local());
+    }
+
+    /// Returns the session for a wall-clock time in the exchange's zone,
+    /// such as the timestamps in the historical CSVs.
+    pub fn session_at_local(&self, local: &DateTime) -> Session {
+        if local.weekday().is_weekend() {
+            return Session::Closed;
+        }
+        let seconds = local.seconds_of_day();
+        if seconds < self.pre_market_open || seconds >= self.after_hours_close {
+            return Session::Closed;
+        }
+        if seconds < self.regular_open {
+            return Session::PreMarket;
+        }
+        if seconds < self.regular_close {
+            return Session::Regular;
+        }
+        return Session::AfterHours;
+    }
+
+    /// Returns the regular open on the given local date.
+    pub fn regular_open_on(&self, date: &DateTime) -> ZonedDateTime {
+        return ZonedDateTime::from_local(&date.start_of_day().add_seconds(self.regular_open as i64), self.zone);
+    }
+
+    /// Returns the regular close on the given local date.
+    pub fn regular_close_on(&self, date: &DateTime) -> ZonedDateTime {
+        return ZonedDateTime::from_local(&date.start_of_day().add_seconds(self.regular_close as i64), self.zone);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_us_eastern_dst_transitions() {
+        let zone = TimeZone::UsEastern;
+        // 2007 and later: second Sunday of March to first Sunday of November
+        assert_eq!(zone.is_dst_at(&DateTime::new("3/14/2021 06:59:59")), false);
+        assert_eq!(zone.is_dst_at(&DateTime::new("3/14/2021 07:00:00")), true);
+        assert_eq!(zone.is_dst_at(&DateTime::new("11/7/2021 05:59:59")), true);
+        assert_eq!(zone.is_dst_at(&DateTime::new("11/7/2021 06:00:00")), false);
+        // 1987-2006: first Sunday of April to last Sunday of October
+        assert_eq!(zone.is_dst_at(&DateTime::new("4/2/2006 06:59:59")), false);
+        assert_eq!(zone.is_dst_at(&DateTime::new("4/2/2006 07:00:00")), true);
+        assert_eq!(zone.is_dst_at(&DateTime::new("10/29/2006 06:00:00")), false);
+        // 1967-1986: last Sunday of April to last Sunday of October
+        assert_eq!(zone.is_dst_at(&DateTime::new("4/27/1980 07:00:00")), true);
+        assert_eq!(zone.is_dst_at(&DateTime::new("4/20/1980 12:00:00")), false);
+        assert_eq!(zone.is_dst_at(&DateTime::new("10/26/1980 06:00:00")), false);
+        // No daylight saving modelled before 1967
+        assert_eq!(zone.is_dst_at(&DateTime::new("7/1/1962 12:00:00")), false);
+        assert_eq!(TimeZone::Utc.is_dst_at(&DateTime::new("7/1/2021 12:00:00")), false);
+    }
+
+    #[test]
+    fn test_offsets_and_abbreviations() {
+        let summer = DateTime::new("7/1/2021 12:00:00");
+        let winter = DateTime::new("1/4/2021 12:00:00");
+        assert_eq!(TimeZone::UsEastern.offset_at(&summer), -14400);
+        assert_eq!(TimeZone::UsEastern.offset_at(&winter), -18000);
+        assert_eq!(TimeZone::Fixed(3600).offset_at(&summer), 3600);
+        assert_eq!(TimeZone::Utc.offset_at(&summer), 0);
+        assert_eq!(TimeZone::UsEastern.abbreviation_at(&summer), "EDT");
+        assert_eq!(TimeZone::UsEastern.abbreviation_at(&winter), "EST");
+        assert_eq!(TimeZone::Fixed(-16200).abbreviation_at(&winter), "UTC-04:30");
+    }
+
+    #[test]
+    fn test_local_utc_conversion() {
+        let d = ZonedDateTime::from_local(&DateTime::new("1/4/2021 09:30:00"), TimeZone::UsEastern);
+        assert_eq!(d.get_utc().to_string(), "1/4/2021 14:30:00");
+        assert_eq!(d.to_local().to_string(), "1/4/2021 09:30:00");
+        assert_eq!(d.is_dst(), false);
+        assert_eq!(d.to_string(), "1/4/2021 09:30:00 EST");
+
+        let d = ZonedDateTime::from_utc(&DateTime::new("4/23/2021 20:00:00"), TimeZone::UsEastern);
+        assert_eq!(d.to_local().to_string(), "4/23/2021 16:00:00");
+        assert_eq!(d.to_rfc3339(), "2021-04-23T16:00:00-04:00");
+        assert_eq!(d.with_zone(TimeZone::Utc).to_rfc3339(), "2021-04-23T20:00:00Z");
+        assert_eq!(d.with_zone(TimeZone::Fixed(3600)).to_rfc3339(), "2021-04-23T21:00:00+01:00");
+        assert_eq!(d.to_unix_timestamp(), 1619208000);
+    }
+
+    #[test]
+    fn test_local_ambiguous_and_skipped() {
+        // 1:30 happens twice on 11/7/2021, the first time in EDT
+        let d = ZonedDateTime::from_local(&DateTime::new("11/7/2021 01:30:00"), TimeZone::UsEastern);
+        assert_eq!(d.get_utc().to_string(), "11/7/2021 05:30:00");
+        assert_eq!(d.is_dst(), true);
+        // 2:30 does not exist on 3/14/2021
+        let d = ZonedDateTime::from_local(&DateTime::new("3/14/2021 02:30:00"), TimeZone::UsEastern);
+        assert_eq!(d.get_utc().to_string(), "3/14/2021 07:30:00");
+        assert_eq!(d.to_local().to_string(), "3/14/2021 03:30:00");
+    }
+
+    #[test]
+    fn test_ordering_across_zones() {
+        let utc = ZonedDateTime::from_utc(&DateTime::new("4/23/2021 20:00:00"), TimeZone::Utc);
+        let eastern = ZonedDateTime::from_local(&DateTime::new("4/23/2021 16:00:00"), TimeZone::UsEastern);
+        let later = ZonedDateTime::from_local(&DateTime::new("4/23/2021 16:30:00"), TimeZone::UsEastern);
+        assert_eq!(utc, eastern);
+        assert!(later > utc);
+    }
+
+    #[test]
+    fn test_sessions() {
+        let nyse = Exchange::nyse();
+        assert_eq!(nyse.session_at_local(&DateTime::new("4/23/2021 03:59:59")), Session::Closed);
+        assert_eq!(nyse.session_at_local(&DateTime::new("4/23/2021 04:00:00")), Session::PreMarket);
+        assert_eq!(nyse.session_at_local(&DateTime::new("4/23/2021 09:29:59")), Session::PreMarket);
+        assert_eq!(nyse.session_at_local(&DateTime::new("4/23/2021 09:30:00")), Session::Regular);
+        assert_eq!(nyse.session_at_local(&DateTime::new("4/23/2021 15:59:59")), Session::Regular);
+        assert_eq!(nyse.session_at_local(&DateTime::new("4/23/2021 16:00:00")), Session::AfterHours);
+        assert_eq!(nyse.session_at_local(&DateTime::new("4/23/2021 19:59:59")), Session::AfterHours);
+        assert_eq!(nyse.session_at_local(&DateTime::new("4/23/2021 20:00:00")), Session::Closed);
+        assert_eq!(nyse.session_at_local(&DateTime::new("4/24/2021 12:00:00")), Session::Closed);
+        assert_eq!(Session::PreMarket.is_extended(), true);
+        assert_eq!(Session::Regular.is_extended(), false);
+        assert_eq!(Session::Closed.is_open(), false);
+    }
+
+    #[test]
+    fn test_sessions_from_utc() {
+        let nasdaq = Exchange::nasdaq();
+        assert_eq!(nasdaq.get_name(), "NASDAQ");
+        // 13:45 UTC is 9:45 EDT in April but 8:45 EST in January
+        let april = ZonedDateTime::from_utc(&DateTime::new("4/23/2021 13:45:00"), TimeZone::Utc);
+        let january = ZonedDateTime::from_utc(&DateTime::new("1/4/2021 13:45:00"), TimeZone::Utc);
+        assert_eq!(nasdaq.session_at(&april), Session::Regular);
+        assert_eq!(nasdaq.session_at(&january), Session::PreMarket);
+    }
+
+    #[test]
+    fn test_regular_open_and_close() {
+        let nyse = Exchange::nyse();
+        let date = DateTime::new("4/23/2021 16:00:00");
+        assert_eq!(nyse.regular_open_on(&date).get_utc().to_string(), "4/23/2021 13:30:00");
+        assert_eq!(nyse.regular_close_on(&date).get_utc().to_string(), "4/23/2021 20:00:00");
+        let date = DateTime::new("12/1/2020 16:00:00");
+        assert_eq!(nyse.regular_close_on(&date).get_utc().to_string(), "12/1/2020 21:00:00");
+    }
+}
@@ -1,19 +1,24 @@
+use std::{fmt, ops::Range};
+
+use crate::stock::calendar;
 use crate::stock::datetime::{DateTime};
+use crate::stock::exchange::{Exchange, Session};
 
 #[derive(Debug)]
 #[derive(Clone)]
+#[derive(PartialEq)]
 pub struct HistoricalData {
     date: DateTime,
-    open: f32,
-    high: f32,
-    low: f32,
-    close: f32,
-    volume: u32,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: u64,
 }
 
 #[allow(dead_code)]
 impl HistoricalData {
-    pub fn new(date: &str, open: f32, high: f32, low: f32, close: f32, volume: u32) -> Self {
+    pub fn new(date: &str, open: f64, high: f64, low: f64, close: f64, volume: u64) -> Self {
         return Self {
             date: DateTime::new(date),
             open: open,
@@ -24,70 +29,302 @@ impl HistoricalData {
         };
     }
 
+    /// Creates a bar from an already parsed `DateTime`.
+    pub fn from_date(date: DateTime, open: f64, high: f64, low: f64, close: f64, volume: u64) -> Self {
+        return Self {
+            date: date,
+            open: open,
+            high: high,
+            low: low,
+            close: close,
+            volume: volume,
+        };
+    }
+
     pub fn get_date(&self) -> &DateTime { return &self.date; }
     pub fn set_date(&mut self, date: &str) { self.date = DateTime::new(date); }
 
-    pub fn get_open(&self) -> f32 { return self.open; }
-    pub fn set_open(&mut self, open: f32) { self.open = open; }
+    pub fn get_open(&self) -> f64 { return self.open; }
+    pub fn set_open(&mut self, open: f64) { self.open = open; }
 
-    pub fn get_high(&self) -> f32 { return self.high; }
-    pub fn set_high(&mut self, high: f32) { self.high = high; }
+    pub fn get_high(&self) -> f64 { return self.high; }
+    pub fn set_high(&mut self, high: f64) { self.high = high; }
 
-    pub fn get_low(&self) -> f32 { return self.low; }
-    pub fn set_low(&mut self, low: f32) { self.low = low; }
+    pub fn get_low(&self) -> f64 { return self.low; }
+    pub fn set_low(&mut self, low: f64) { self.low = low; }
 
-    pub fn get_close(&self) -> f32 { return self.close; }
-    pub fn set_close(&mut self, close: f32) { self.close = close; }
+    pub fn get_close(&self) -> f64 { return self.close; }
+    pub fn set_close(&mut self, close: f64) { self.close = close; }
 
-    pub fn get_volume(&self) -> u32 { return self.volume; }
-    pub fn set_volume(&mut self, volume: u32) { self.volume = volume; }
+    pub fn get_volume(&self) -> u64 { return self.volume; }
+    pub fn set_volume(&mut self, volume: u64) { self.volume = volume; }
 
+    /// Returns the trading session the bar belongs to, reading its date as
+    /// local time on `exchange`.
+    /// 
+    /// Daily bars (the bundled CSVs and the IEX charts) are stamped with the
+    /// regular close, e.g. `16:00:00`, so a bar stamped exactly at the close
+    /// ends the regular session and is `Session::Regular`. Other bars are
+    /// classified by their start time like `Exchange::session_at_local`.
+    pub fn get_session(&self, exchange: &Exchange) -> Session {
+        if !self.date.weekday().is_weekend() && self.date.seconds_of_day() == exchange.get_regular_close() {
+            return Session::Regular;
+        }
+        return exchange.session_at_local(&self.date);
+    }
+
+    #[allow(clippy::inherent_to_string)]
     pub fn to_string(&self) -> String {
         let mut output = "".to_owned();
-        output.push_str("{",);
+        output.push('{');
         output.push_str(&format!("\"date\": \"{}\",", self.get_date().to_string()));
         output.push_str(&format!("\"close\": {},", self.get_close()));
         output.push_str(&format!("\"high\": {},", self.get_high()));
         output.push_str(&format!("\"low\": {},", self.get_low()));
         output.push_str(&format!("\"open\": {},", self.get_open()));
         output.push_str(&format!("\"volume\": {}", self.get_volume()));
-        output.push_str("}");
+        output.push('}');
         return output;
     }
 }
 
-pub fn sort_by_date(d: &mut Vec<HistoricalData>) {
-    quicksort(d);
+/// Sorts historical data by date. The sort is stable, so bars with the same
+/// timestamp keep their relative order, and runs in O(n log n), or O(n) when
+/// the data is already sorted.
+pub fn sort_by_date(d: &mut [HistoricalData]) {
+    d.sort_by(|a, b| a.get_date().cmp(b.get_date()));
 }
 
-fn partition(a: &mut Vec<HistoricalData>, low: i64, high: i64) -> i64 {
-    let pivot = match a.get(high as usize) {
-        Some(el) => el.clone(),
-        _ => panic!("Array index {} out of bounds.", high-1)
-    };
-    let mut i = low - 1;
-    for j in low..high {
-        if a[j as usize].get_date().is_before(&pivot.get_date()) {
-            i += 1;
-            a.swap(i as usize, j as usize);
+/// Removes bars that share a timestamp with the bar after them, so that the
+/// last bar of each timestamp is kept. After a stable sort this is the bar
+/// added most recently. `d` must be sorted by date.
+///
+/// Returns the number of bars removed.
+pub fn dedup_by_date(d: &mut Vec<HistoricalData>) -> usize {
+    let mut write = 0;
+    for read in 0..d.len() {
+        if read + 1 < d.len() && d[read + 1].get_date() == d[read].get_date() {
+            continue;
+        }
+        d.swap(write, read);
+        write += 1;
+    }
+    let removed = d.len() - write;
+    d.truncate(write);
+    return removed;
+}
+
+/// Returns the indices of the bars dated from `start_date` to `end_date`,
+/// both inclusive, found by binary search. `d` must be sorted by date.
+pub fn range(d: &[HistoricalData], start_date: &DateTime, end_date: &DateTime) -> Range<usize> {
+    let from = d.partition_point(|bar| bar.get_date() < start_date);
+    let to = d.partition_point(|bar| bar.get_date() <= end_date);
+    return from..to.max(from);
+}
+
+/// Returns the bars dated from `start_date` to `end_date`, both inclusive,
+/// as a slice of `d`. `d` must be sorted by date.
+///
+/// ### Examples
+/// ```
+/// let year = historical::query(s.get_historical_data(), &DateTime::new("1/1/2021"), &DateTime::new("12/31/2021 23:59:59"));
+/// ```
+pub fn query<'a>(d: &'a [HistoricalData], start_date: &DateTime, end_date: &DateTime) -> &'a [HistoricalData] {
+    return &d[range(d, start_date, end_date)];
+}
+
+
+/// Relative difference below which two prices are considered equal when
+/// merging, so that a price stored as `f32` by one source (ex.
+/// `147.8300018310547`) matches the same price from another (`147.83`).
+pub const PRICE_TOLERANCE: f64 = 1e-6;
+
+/// What `merge` does with a restated bar, i.e. an incoming bar with the
+/// same timestamp as an existing bar but different prices.
+///
+/// - `KeepOld`: keep the existing bar
+/// - `TakeNew`: replace the existing bar with the incoming bar
+/// - `Error`: fail the merge and leave the data unchanged
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum MergePolicy {
+    KeepOld,
+    TakeNew,
+    Error,
+}
+
+/// An incoming bar whose prices differ from the existing bar with the same
+/// timestamp.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct Restatement {
+    old: HistoricalData,
+    new: HistoricalData,
+}
+
+#[allow(dead_code)]
+impl Restatement {
+    pub fn get_date(&self) -> &DateTime { return self.old.get_date(); }
+    pub fn get_old(&self) -> &HistoricalData { return &self.old; }
+    pub fn get_new(&self) -> &HistoricalData { return &self.new; }
+
+    /// Describes the first price that differs, e.g.
+    /// `close 147.96 restated as 148.1`.
+    pub fn describe(&self) -> String {
+        let (old, new) = (&self.old, &self.new);
+        let prices = [
+            ("open", old.get_open(), new.get_open()),
+            ("high", old.get_high(), new.get_high()),
+            ("low", old.get_low(), new.get_low()),
+            ("close", old.get_close(), new.get_close()),
+        ];
+        for (name, old_price, new_price) in prices {
+            if !same_price(old_price, new_price) {
+                return format!("{} {} restated as {}", name, old_price, new_price);
+            }
         }
+        return "prices restated".to_string();
     }
-    a.swap((i+1) as usize, high as usize);
-    return i+1;
 }
 
-fn _quicksort(a: &mut Vec<HistoricalData>, low: i64, high: i64) {
-    if low < high {
-        let pi = partition(a, low, high);
-        _quicksort(a, low, pi-1);
-        _quicksort(a, pi+1, high);
+/// Summary of a `merge`.
+///
+/// - `added`: incoming bars with a new timestamp
+/// - `duplicates`: incoming bars matching an existing bar's prices
+/// - `restated`: incoming bars whose prices differ from an existing bar's
+/// - `gaps`: trading days without a bar, as midnight `DateTime`s, from the
+///   bar before the first incoming bar through the last incoming bar
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Default)]
+pub struct MergeReport {
+    added: usize,
+    duplicates: usize,
+    restated: Vec<Restatement>,
+    gaps: Vec<DateTime>,
+}
+
+#[allow(dead_code)]
+impl MergeReport {
+    pub fn new() -> Self {
+        return Self::default();
     }
+
+    pub fn get_added(&self) -> usize { return self.added; }
+    pub fn get_duplicates(&self) -> usize { return self.duplicates; }
+    pub fn get_restated(&self) -> &Vec<Restatement> { return &self.restated; }
+    pub fn get_gaps(&self) -> &Vec<DateTime> { return &self.gaps; }
 }
 
-fn quicksort(a: &mut Vec<HistoricalData>) {
-    _quicksort(a, 0, (a.len() as i64)-1);
+/// Error returned by `merge` with `MergePolicy::Error`, for the first
+/// restated bar.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum MergeError {
+    Restated {
+        date: DateTime,
+        reason: String,
+    },
 }
 
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            MergeError::Restated { date, reason } => write!(f, "Bar on {} was restated: {}.", date.to_string(), reason),
+        };
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+fn same_price(a: f64, b: f64) -> bool {
+    return (a - b).abs() <= PRICE_TOLERANCE * a.abs().max(b.abs());
+}
+
+/// Whether two bars have the same open, high, low and close, within
+/// `PRICE_TOLERANCE`. Volumes are not compared, since sources often revise
+/// them slightly.
+pub fn same_prices(a: &HistoricalData, b: &HistoricalData) -> bool {
+    return same_price(a.get_open(), b.get_open())
+        && same_price(a.get_high(), b.get_high())
+        && same_price(a.get_low(), b.get_low())
+        && same_price(a.get_close(), b.get_close());
+}
+
+/// Merges `incoming` bars into `existing` in O(n + m log m). Incoming bars
+/// with a new timestamp are added. An incoming bar with the timestamp of an
+/// existing bar is a duplicate if its prices match, which keeps the
+/// existing bar under `KeepOld` and the incoming bar otherwise, and a
+/// restatement if they do not, which is resolved by `policy`. Among
+/// incoming bars sharing a timestamp, the last one is used.
+///
+/// The report also lists the trading days missing around the incoming bars.
+/// These are only meaningful for daily bars.
+///
+/// # Arguments
+///
+/// * `existing` - Bars sorted by date with one bar per timestamp, updated
+///   in place unless an error is returned
+/// * `incoming` - Bars in any order
+/// * `policy` - What to do with restated bars
+///
+/// ### Examples
+/// ```
+/// let report = historical::merge(&mut data, new_bars, MergePolicy::KeepOld).unwrap();
+/// for restatement in report.get_restated() {
+///     println!("{}: {}", restatement.get_date().to_string(), restatement.describe());
+/// }
+/// ```
+pub fn merge(existing: &mut Vec<HistoricalData>, mut incoming: Vec<HistoricalData>, policy: MergePolicy) -> Result<MergeReport, MergeError> {
+    sort_by_date(&mut incoming);
+    dedup_by_date(&mut incoming);
+    let mut report = MergeReport::new();
+    let (first, last) = match (incoming.first(), incoming.last()) {
+        (Some(first), Some(last)) => (first.get_date().clone(), last.get_date().clone()),
+        _ => return Ok(report),
+    };
+
+    let mut merged: Vec<HistoricalData> = Vec::with_capacity(existing.len() + incoming.len());
+    let mut old = existing.iter().peekable();
+    for new in incoming {
+        while let Some(bar) = old.next_if(|bar| bar.get_date() < new.get_date()) {
+            merged.push(bar.clone());
+        }
+        let bar = match old.next_if(|bar| bar.get_date() == new.get_date()) {
+            Some(bar) => bar,
+            None => {
+                report.added += 1;
+                merged.push(new);
+                continue;
+            },
+        };
+        if same_prices(bar, &new) {
+            report.duplicates += 1;
+            merged.push(if policy == MergePolicy::KeepOld { bar.clone() } else { new });
+            continue;
+        }
+        let restatement = Restatement { old: bar.clone(), new: new };
+        match policy {
+            MergePolicy::Error => return Err(MergeError::Restated { date: restatement.get_date().clone(), reason: restatement.describe() }),
+            MergePolicy::KeepOld => merged.push(restatement.old.clone()),
+            MergePolicy::TakeNew => merged.push(restatement.new.clone()),
+        };
+        report.restated.push(restatement);
+    }
+    merged.extend(old.cloned());
+
+    let from = merged.partition_point(|bar| bar.get_date() < &first).saturating_sub(1);
+    let to = merged.partition_point(|bar| bar.get_date() <= &last);
+    report.gaps = calendar::missing_trading_days(&merged[from..to]);
+
+    *existing = merged;
+    return Ok(report);
+}
 
 
 #[cfg(test)]
@@ -119,10 +356,10 @@ mod tests {
         let d5 = HistoricalData::new("4/23/2017 16:00:00", 10.0, 17.0, 8.0, 11.0, 10050);
         let mut dates = vec![d1,d2,d3,d4,d5];
         sort_by_date(&mut dates);
-        assert_eq!(dates[0].get_date().is_before(&dates[1].get_date()), true);
-        assert_eq!(dates[1].get_date().is_before(&dates[2].get_date()), true);
-        assert_eq!(dates[2].get_date().is_before(&dates[3].get_date()), true);
-        assert_eq!(dates[3].get_date().is_before(&dates[4].get_date()), true);
+        assert_eq!(dates[0].get_date().is_before(dates[1].get_date()), true);
+        assert_eq!(dates[1].get_date().is_before(dates[2].get_date()), true);
+        assert_eq!(dates[2].get_date().is_before(dates[3].get_date()), true);
+        assert_eq!(dates[3].get_date().is_before(dates[4].get_date()), true);
     }
 
     #[test]
@@ -134,10 +371,10 @@ mod tests {
         let d5 = HistoricalData::new("4/23/2020 16:00:00", 10.0, 17.0, 8.0, 11.0, 10050);
         let mut dates = vec![d1,d2,d3,d4,d5];
         sort_by_date(&mut dates);
-        assert_eq!(dates[0].get_date().is_before(&dates[1].get_date()), true);
-        assert_eq!(dates[1].get_date().is_before(&dates[2].get_date()), true);
-        assert_eq!(dates[2].get_date().is_before(&dates[3].get_date()), true);
-        assert_eq!(dates[3].get_date().is_before(&dates[4].get_date()), true);
+        assert_eq!(dates[0].get_date().is_before(dates[1].get_date()), true);
+        assert_eq!(dates[1].get_date().is_before(dates[2].get_date()), true);
+        assert_eq!(dates[2].get_date().is_before(dates[3].get_date()), true);
+        assert_eq!(dates[3].get_date().is_before(dates[4].get_date()), true);
     }
 
     #[test]
@@ -149,10 +386,10 @@ mod tests {
         let d5 = HistoricalData::new("4/23/2020 16:30:10", 10.0, 17.0, 8.0, 11.0, 10050);
         let mut dates = vec![d1,d2,d3,d4,d5];
         sort_by_date(&mut dates);
-        assert_eq!(dates[0].get_date().is_before(&dates[1].get_date()), true);
-        assert_eq!(dates[1].get_date().is_before(&dates[2].get_date()), true);
-        assert_eq!(dates[2].get_date().is_before(&dates[3].get_date()), true);
-        assert_eq!(dates[3].get_date().is_before(&dates[4].get_date()), true);
+        assert_eq!(dates[0].get_date().is_before(dates[1].get_date()), true);
+        assert_eq!(dates[1].get_date().is_before(dates[2].get_date()), true);
+        assert_eq!(dates[2].get_date().is_before(dates[3].get_date()), true);
+        assert_eq!(dates[3].get_date().is_before(dates[4].get_date()), true);
     }
 
     #[test]
@@ -164,9 +401,221 @@ mod tests {
         let d5 = HistoricalData::new("4/23/2020 16:30:20", 10.0, 17.0, 8.0, 11.0, 10050);
         let mut dates = vec![d1,d2,d3,d4,d5];
         sort_by_date(&mut dates);
-        assert_eq!(dates[0].get_date().is_before(&dates[1].get_date()), true);
-        assert_eq!(dates[1].get_date().is_before(&dates[2].get_date()), true);
-        assert_eq!(dates[2].get_date().is_before(&dates[3].get_date()), true);
-        assert_eq!(dates[3].get_date().is_before(&dates[4].get_date()), true);
+        assert_eq!(dates[0].get_date().is_before(dates[1].get_date()), true);
+        assert_eq!(dates[1].get_date().is_before(dates[2].get_date()), true);
+        assert_eq!(dates[2].get_date().is_before(dates[3].get_date()), true);
+        assert_eq!(dates[3].get_date().is_before(dates[4].get_date()), true);
+    }
+
+    #[test]
+    fn test_sort_by_date_cross_field() {
+        let d1 = HistoricalData::new("12/31/2021 00:00:00", 10.0, 17.0, 8.0, 11.0, 10050);
+        let d2 = HistoricalData::new("1/1/2021 00:00:01", 10.0, 17.0, 8.0, 11.0, 10050);
+        let d3 = HistoricalData::new("6/15/2020 23:59:59", 10.0, 17.0, 8.0, 11.0, 10050);
+        let d4 = HistoricalData::new("1/1/2021 00:00:00", 10.0, 17.0, 8.0, 11.0, 10050);
+        let mut dates = vec![d1,d2,d3,d4];
+        sort_by_date(&mut dates);
+        assert_eq!(dates[0].get_date().to_string(), "6/15/2020 23:59:59");
+        assert_eq!(dates[1].get_date().to_string(), "1/1/2021 00:00:00");
+        assert_eq!(dates[2].get_date().to_string(), "1/1/2021 00:00:01");
+        assert_eq!(dates[3].get_date().to_string(), "12/31/2021 00:00:00");
+    }
+
+    #[test]
+    fn test_get_session() {
+        let nyse = Exchange::nyse();
+        let d = HistoricalData::new("4/23/2021 08:00:00", 10.0, 17.0, 8.0, 11.0, 10050);
+        assert_eq!(d.get_session(&nyse), Session::PreMarket);
+        let d = HistoricalData::new("4/23/2021 10:15:00", 10.0, 17.0, 8.0, 11.0, 10050);
+        assert_eq!(d.get_session(&nyse), Session::Regular);
+        let d = HistoricalData::new("4/23/2021 17:00:00", 10.0, 17.0, 8.0, 11.0, 10050);
+        assert_eq!(d.get_session(&nyse), Session::AfterHours);
+        let d = HistoricalData::new("4/23/2021 16:00:01", 10.0, 17.0, 8.0, 11.0, 10050);
+        assert_eq!(d.get_session(&nyse), Session::AfterHours);
+    }
+
+    #[test]
+    fn test_get_session_daily_bars() {
+        use std::path::Path;
+        use crate::stock::load::{read_file, parse_historical, LoadOptions};
+
+        let nyse = Exchange::nyse();
+        let content = read_file(Path::new("./src/assets/historical/spy.csv")).unwrap();
+        let (data, _) = parse_historical(&content, &LoadOptions::default()).unwrap();
+        assert!(!data.is_empty());
+        for d in data.iter() {
+            assert_eq!(d.get_session(&nyse), Session::Regular, "{}", d.get_date().to_string());
+        }
     }
-}
\ No newline at end of file
+
+    fn bar(date: &str, close: f64) -> HistoricalData {
+        return HistoricalData::new(date, close, close, close, close, 100);
+    }
+
+    fn closes(d: &[HistoricalData]) -> Vec<f64> {
+        return d.iter().map(|bar| bar.get_close()).collect();
+    }
+
+    #[test]
+    fn test_sort_by_date_stable() {
+        let mut d = vec![
+            bar("1/3/2020 16:00:00", 1.0),
+            bar("1/2/2020 16:00:00", 2.0),
+            bar("1/3/2020 16:00:00", 3.0),
+            bar("1/2/2020 16:00:00", 4.0),
+        ];
+        sort_by_date(&mut d);
+        assert_eq!(closes(&d), vec![2.0, 4.0, 1.0, 3.0]);
+    }
+
+    #[test]
+    fn test_dedup_by_date() {
+        let mut d = vec![
+            bar("1/2/2020 16:00:00", 1.0),
+            bar("1/2/2020 16:00:00", 2.0),
+            bar("1/3/2020 16:00:00", 3.0),
+            bar("1/6/2020 16:00:00", 4.0),
+            bar("1/6/2020 16:00:00", 5.0),
+            bar("1/6/2020 16:00:00", 6.0),
+        ];
+        assert_eq!(dedup_by_date(&mut d), 3);
+        assert_eq!(closes(&d), vec![2.0, 3.0, 6.0]);
+        assert_eq!(dedup_by_date(&mut d), 0);
+        assert_eq!(dedup_by_date(&mut Vec::new()), 0);
+    }
+
+    fn existing_bars() -> Vec<HistoricalData> {
+        return vec![
+            bar("1/2/2020 16:00:00", 1.0),
+            bar("1/3/2020 16:00:00", 2.0),
+            bar("1/6/2020 16:00:00", 3.0),
+        ];
+    }
+
+    #[test]
+    fn test_merge_policies() {
+        // 1/6 is a duplicate up to f32 rounding, 1/3 is restated, 1/8 is new
+        // and leaves 1/7 missing
+        let incoming = || vec![
+            bar("1/8/2020 16:00:00", 5.0),
+            HistoricalData::new("1/6/2020 16:00:00", 3.0000001, 3.0, 3.0, 3.0, 250),
+            bar("1/3/2020 16:00:00", 2.5),
+        ];
+
+        let mut d = existing_bars();
+        let report = merge(&mut d, incoming(), MergePolicy::KeepOld).unwrap();
+        assert_eq!(closes(&d), vec![1.0, 2.0, 3.0, 5.0]);
+        assert_eq!(d[2].get_volume(), 100);
+        assert_eq!(report.get_added(), 1);
+        assert_eq!(report.get_duplicates(), 1);
+        assert_eq!(report.get_restated().len(), 1);
+        assert_eq!(report.get_restated()[0].get_date().to_string(), "1/3/2020 16:00:00");
+        assert_eq!(report.get_restated()[0].describe(), "open 2 restated as 2.5");
+        assert_eq!(report.get_gaps().iter().map(|d| d.to_string()).collect::<Vec<String>>(), vec!["1/7/2020 00:00:00"]);
+
+        let mut d = existing_bars();
+        let report = merge(&mut d, incoming(), MergePolicy::TakeNew).unwrap();
+        assert_eq!(closes(&d), vec![1.0, 2.5, 3.0, 5.0]);
+        assert_eq!(d[2].get_volume(), 250);
+        assert_eq!(report.get_restated()[0].get_new().get_close(), 2.5);
+
+        let mut d = existing_bars();
+        let error = merge(&mut d, incoming(), MergePolicy::Error).unwrap_err();
+        assert_eq!(error, MergeError::Restated { date: DateTime::new("1/3/2020 16:00:00"), reason: "open 2 restated as 2.5".to_string() });
+        assert_eq!(error.to_string(), "Bar on 1/3/2020 16:00:00 was restated: open 2 restated as 2.5.");
+        assert_eq!(d, existing_bars());
+    }
+
+    #[test]
+    fn test_merge_twice() {
+        let mut d = Vec::new();
+        let report = merge(&mut d, existing_bars(), MergePolicy::Error).unwrap();
+        assert_eq!((report.get_added(), report.get_duplicates()), (3, 0));
+        let report = merge(&mut d, existing_bars(), MergePolicy::Error).unwrap();
+        assert_eq!((report.get_added(), report.get_duplicates()), (0, 3));
+        assert_eq!(report.get_gaps().len(), 0);
+        assert_eq!(d, existing_bars());
+        assert_eq!(merge(&mut d, Vec::new(), MergePolicy::Error), Ok(MergeReport::new()));
+    }
+
+    #[test]
+    fn test_query() {
+        let d = vec![
+            bar("1/2/2020 16:00:00", 1.0),
+            bar("1/3/2020 16:00:00", 2.0),
+            bar("1/6/2020 16:00:00", 3.0),
+            bar("1/7/2020 16:00:00", 4.0),
+        ];
+        let q = query(&d, &DateTime::new("1/3/2020 16:00:00"), &DateTime::new("1/6/2020 16:00:00"));
+        assert_eq!(closes(q), vec![2.0, 3.0]);
+        assert_eq!(q.as_ptr(), d[1..].as_ptr());
+        assert_eq!(range(&d, &DateTime::new("1/1/2020"), &DateTime::new("1/7/2020")), 0..3);
+        assert_eq!(range(&d, &DateTime::new("1/4/2020"), &DateTime::new("1/5/2020")), 2..2);
+        assert_eq!(query(&d, &DateTime::new("1/7/2020"), &DateTime::new("1/2/2020")).len(), 0);
+        assert_eq!(query(&[], &DateTime::new("1/1/2020"), &DateTime::new("1/7/2020")).len(), 0);
+    }
+
+    /// Times sorting, deduplicating and querying a bundled file. Run with
+    /// `cargo test --release -- --ignored --nocapture bench_`.
+    fn bench_file(path: &str) {
+        use crate::stock::load::{self, LoadOptions};
+        use std::time::Instant;
+
+        let content = load::read_file(std::path::Path::new(path)).unwrap();
+        let (data, _) = load::parse_historical(&content, &LoadOptions::default()).unwrap();
+        let start = Instant::now();
+        let mut sorted = data.clone();
+        sort_by_date(&mut sorted);
+        println!("{}: sort {} sorted bars in {:?}", path, sorted.len(), start.elapsed());
+
+        let mut reversed = data.clone();
+        reversed.reverse();
+        let start = Instant::now();
+        sort_by_date(&mut reversed);
+        println!("{}: sort {} reversed bars in {:?}", path, reversed.len(), start.elapsed());
+
+        // Deterministic shuffle
+        let mut shuffled = data.clone();
+        let mut seed: u64 = 42;
+        for i in (1..shuffled.len()).rev() {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            shuffled.swap(i, (seed >> 33) as usize % (i + 1));
+        }
+        let start = Instant::now();
+        sort_by_date(&mut shuffled);
+        println!("{}: sort {} shuffled bars in {:?}", path, shuffled.len(), start.elapsed());
+
+        let mut doubled = [&data[..], &data[..]].concat();
+        let start = Instant::now();
+        sort_by_date(&mut doubled);
+        let removed = dedup_by_date(&mut doubled);
+        println!("{}: sort and dedup {} bars ({} removed) in {:?}", path, doubled.len() + removed, removed, start.elapsed());
+
+        let queries = 10000;
+        let first = data[0].get_date().clone();
+        let start = Instant::now();
+        let mut total = 0;
+        for i in 0..queries {
+            let from = first.add_days(i as i64);
+            total += query(&sorted, &from, &from.add_years(1)).len();
+        }
+        println!("{}: {} one-year queries ({} bars) in {:?}", path, queries, total, start.elapsed());
+
+        for d in [&reversed, &shuffled, &doubled] {
+            assert_eq!(d.len(), sorted.len());
+            assert!(d.iter().zip(sorted.iter()).all(|(a, b)| a.get_date() == b.get_date()));
+        }
+    }
+
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_sort_and_query_ko() {
+        bench_file("./src/assets/historical/ko.csv");
+    }
+
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_sort_and_query_wmt() {
+        bench_file("./src/assets/historical/wmt.csv");
+    }
+}
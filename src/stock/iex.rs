@@ -0,0 +1,645 @@
+use std::{env, fmt};
+
+use crate::json::parse::{self, JsonError};
+use crate::json::serialize::{JSON, JSONObject};
+use crate::stock::Stock;
+use crate::stock::datetime::DateTime;
+use crate::stock::historical::HistoricalData;
+use crate::stock::load::Fundamentals;
+
+#[cfg(test)]
+pub mod mock;
+
+/// Base URL of the IEX Cloud production API.
+pub const DEFAULT_BASE_URL: &str = "https://cloud.iexapis.com/stable";
+
+/// Error returned by the IEX client.
+///
+/// - `MissingToken`: no API token was given and `IEX_TOKEN` is not set
+/// - `Http`: the request could not be sent or the response not read
+/// - `Status`: the API answered with a non-success status
+/// - `Json`: the response body is not valid JSON
+/// - `MissingField`: a required field is missing or `null`
+/// - `InvalidField`: a field has an unexpected type or format
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum IexError {
+    MissingToken,
+    Http {
+        reason: String,
+    },
+    Status {
+        status: u16,
+        body: String,
+    },
+    Json(JsonError),
+    MissingField {
+        field: &'static str,
+    },
+    InvalidField {
+        field: &'static str,
+        reason: String,
+    },
+}
+
+impl fmt::Display for IexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            IexError::MissingToken => write!(f, "No IEX Cloud token. Set the IEX_TOKEN environment variable."),
+            IexError::Http { reason } => write!(f, "IEX request failed: {}", reason),
+            IexError::Status { status, body } => write!(f, "IEX returned status {}: {}", status, body),
+            IexError::Json(error) => write!(f, "Invalid IEX response: {}", error),
+            IexError::MissingField { field } => write!(f, "IEX response is missing field `{}`.", field),
+            IexError::InvalidField { field, reason } => write!(f, "Invalid field `{}` in IEX response: {}.", field, reason),
+        };
+    }
+}
+
+impl std::error::Error for IexError {}
+
+impl From<JsonError> for IexError {
+    fn from(error: JsonError) -> Self {
+        return IexError::Json(error);
+    }
+}
+
+impl From<reqwest::Error> for IexError {
+    fn from(error: reqwest::Error) -> Self {
+        return IexError::Http { reason: error.to_string() };
+    }
+}
+
+/// Range of daily bars returned by the chart endpoint. `Max` reaches back
+/// at most `MAX_HISTORY_YEARS`.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum ChartRange {
+    FiveDays,
+    OneMonth,
+    ThreeMonths,
+    SixMonths,
+    YearToDate,
+    OneYear,
+    TwoYears,
+    FiveYears,
+    Max,
+}
+
+/// How many years of bars `ChartRange::Max` returns at most.
+pub const MAX_HISTORY_YEARS: i64 = 15;
+
+impl ChartRange {
+    #[allow(dead_code)]
+    pub const ALL: [ChartRange; 9] = [
+        ChartRange::FiveDays,
+        ChartRange::OneMonth,
+        ChartRange::ThreeMonths,
+        ChartRange::SixMonths,
+        ChartRange::YearToDate,
+        ChartRange::OneYear,
+        ChartRange::TwoYears,
+        ChartRange::FiveYears,
+        ChartRange::Max,
+    ];
+
+    /// Returns the range as used in the chart endpoint's path.
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            ChartRange::FiveDays => "5d",
+            ChartRange::OneMonth => "1m",
+            ChartRange::ThreeMonths => "3m",
+            ChartRange::SixMonths => "6m",
+            ChartRange::YearToDate => "ytd",
+            ChartRange::OneYear => "1y",
+            ChartRange::TwoYears => "2y",
+            ChartRange::FiveYears => "5y",
+            ChartRange::Max => "max",
+        };
+    }
+
+    /// Returns the shortest range whose bars reach back to `start` when
+    /// requested on `today`. Ranges are measured in calendar days with some
+    /// slack for weekends and holidays.
+    pub fn covering(start: &DateTime, today: &DateTime) -> Self {
+        return match today.days_since(start) {
+            i64::MIN..=4 => ChartRange::FiveDays,
+            5..=27 => ChartRange::OneMonth,
+            28..=88 => ChartRange::ThreeMonths,
+            89..=180 => ChartRange::SixMonths,
+            181..=364 => ChartRange::OneYear,
+            365..=729 => ChartRange::TwoYears,
+            730..=1824 => ChartRange::FiveYears,
+            _ => ChartRange::Max,
+        };
+    }
+}
+
+/// Latest quote from the `/stock/{symbol}/quote` endpoint. Price fields
+/// other than `latestPrice` are `null` outside of some sessions, so they
+/// are optional.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct Quote {
+    symbol: String,
+    company_name: String,
+    latest_price: f64,
+    latest_update: DateTime,
+    open: Option<f64>,
+    high: Option<f64>,
+    low: Option<f64>,
+    close: Option<f64>,
+    previous_close: Option<f64>,
+    volume: Option<u64>,
+    market_cap: Option<u64>,
+    pe_ratio: Option<f64>,
+    week52_high: Option<f64>,
+    week52_low: Option<f64>,
+}
+
+#[allow(dead_code)]
+impl Quote {
+    pub fn get_symbol(&self) -> String { return self.symbol.to_string(); }
+    pub fn get_company_name(&self) -> String { return self.company_name.to_string(); }
+    pub fn get_latest_price(&self) -> f64 { return self.latest_price; }
+    pub fn get_latest_update(&self) -> &DateTime { return &self.latest_update; }
+    pub fn get_open(&self) -> Option<f64> { return self.open; }
+    pub fn get_high(&self) -> Option<f64> { return self.high; }
+    pub fn get_low(&self) -> Option<f64> { return self.low; }
+    pub fn get_close(&self) -> Option<f64> { return self.close; }
+    pub fn get_previous_close(&self) -> Option<f64> { return self.previous_close; }
+    pub fn get_volume(&self) -> Option<u64> { return self.volume; }
+    pub fn get_market_cap(&self) -> Option<u64> { return self.market_cap; }
+    pub fn get_pe_ratio(&self) -> Option<f64> { return self.pe_ratio; }
+    pub fn get_week52_high(&self) -> Option<f64> { return self.week52_high; }
+    pub fn get_week52_low(&self) -> Option<f64> { return self.week52_low; }
+
+    fn from_json(json: &JSONObject) -> Result<Self, IexError> {
+        let o = as_object(json)?;
+        return Ok(Self {
+            symbol: required_str(o, "symbol")?,
+            company_name: optional_str(o, "companyName").unwrap_or_default(),
+            latest_price: required_f64(o, "latestPrice")?,
+            latest_update: DateTime::from_unix_timestamp(required_i64(o, "latestUpdate")? / 1000),
+            open: optional_f64(o, "open"),
+            high: optional_f64(o, "high"),
+            low: optional_f64(o, "low"),
+            close: optional_f64(o, "close"),
+            previous_close: optional_f64(o, "previousClose"),
+            volume: optional_u64(o, "volume"),
+            market_cap: optional_u64(o, "marketCap"),
+            pe_ratio: optional_f64(o, "peRatio"),
+            week52_high: optional_f64(o, "week52High"),
+            week52_low: optional_f64(o, "week52Low"),
+        });
+    }
+
+    /// Copies the name, market cap, P/E ratio and 52 week range into
+    /// `stock`. Fields the quote does not have are left unchanged.
+    pub fn apply_to(&self, stock: &mut Stock) {
+        if !self.company_name.is_empty() { stock.set_name(&self.company_name); }
+        if let Some(market_cap) = self.market_cap { stock.set_market_cap(market_cap); }
+        if let Some(pe_ratio) = self.pe_ratio { stock.set_pe_ratio(pe_ratio); }
+        if let Some(high52) = self.week52_high { stock.set_high52(high52); }
+        if let Some(low52) = self.week52_low { stock.set_low52(low52); }
+    }
+}
+
+/// Company profile from the `/stock/{symbol}/company` endpoint.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct Company {
+    symbol: String,
+    company_name: String,
+    exchange: String,
+    industry: String,
+    sector: String,
+    issue_type: String,
+    description: String,
+}
+
+#[allow(dead_code)]
+impl Company {
+    pub fn get_symbol(&self) -> String { return self.symbol.to_string(); }
+    pub fn get_company_name(&self) -> String { return self.company_name.to_string(); }
+    pub fn get_exchange(&self) -> String { return self.exchange.to_string(); }
+    pub fn get_industry(&self) -> String { return self.industry.to_string(); }
+    pub fn get_sector(&self) -> String { return self.sector.to_string(); }
+    pub fn get_issue_type(&self) -> String { return self.issue_type.to_string(); }
+    pub fn get_description(&self) -> String { return self.description.to_string(); }
+
+    /// Returns the security type in the form used by the contemporary CSV
+    /// files, e.g. `EQUITY` for common stock (`cs`) and `ETF` for `et`.
+    pub fn get_security_type(&self) -> String {
+        return match self.issue_type.as_str() {
+            "cs" | "ps" => "EQUITY".to_string(),
+            "et" => "ETF".to_string(),
+            "ad" => "ADR".to_string(),
+            other => other.to_uppercase(),
+        };
+    }
+
+    fn from_json(json: &JSONObject) -> Result<Self, IexError> {
+        let o = as_object(json)?;
+        return Ok(Self {
+            symbol: required_str(o, "symbol")?,
+            company_name: optional_str(o, "companyName").unwrap_or_default(),
+            exchange: optional_str(o, "exchange").unwrap_or_default(),
+            industry: optional_str(o, "industry").unwrap_or_default().trim().to_string(),
+            sector: optional_str(o, "sector").unwrap_or_default().trim().to_string(),
+            issue_type: optional_str(o, "issueType").unwrap_or_default(),
+            description: optional_str(o, "description").unwrap_or_default(),
+        });
+    }
+
+    /// Copies the name, security type and description into `stock`.
+    pub fn apply_to(&self, stock: &mut Stock) {
+        if !self.company_name.is_empty() { stock.set_name(&self.company_name); }
+        if !self.issue_type.is_empty() { stock.set_security_type(&self.get_security_type()); }
+        if !self.description.is_empty() { stock.set_summary(&self.description); }
+    }
+}
+
+/// Key statistics from the `/stock/{symbol}/stats` endpoint.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct Stats {
+    company_name: String,
+    market_cap: Option<u64>,
+    week52_high: Option<f64>,
+    week52_low: Option<f64>,
+    ttm_eps: Option<f64>,
+    pe_ratio: Option<f64>,
+}
+
+#[allow(dead_code)]
+impl Stats {
+    pub fn get_company_name(&self) -> String { return self.company_name.to_string(); }
+    pub fn get_market_cap(&self) -> Option<u64> { return self.market_cap; }
+    pub fn get_week52_high(&self) -> Option<f64> { return self.week52_high; }
+    pub fn get_week52_low(&self) -> Option<f64> { return self.week52_low; }
+    pub fn get_ttm_eps(&self) -> Option<f64> { return self.ttm_eps; }
+    pub fn get_pe_ratio(&self) -> Option<f64> { return self.pe_ratio; }
+
+    fn from_json(json: &JSONObject) -> Result<Self, IexError> {
+        let o = as_object(json)?;
+        return Ok(Self {
+            company_name: optional_str(o, "companyName").unwrap_or_default(),
+            market_cap: optional_u64(o, "marketcap"),
+            week52_high: optional_f64(o, "week52high"),
+            week52_low: optional_f64(o, "week52low"),
+            ttm_eps: optional_f64(o, "ttmEPS"),
+            pe_ratio: optional_f64(o, "peRatio"),
+        });
+    }
+
+    /// Copies the name, market cap, EPS, P/E ratio and 52 week range into
+    /// `stock`. Fields the statistics do not have are left unchanged.
+    pub fn apply_to(&self, stock: &mut Stock) {
+        if !self.company_name.is_empty() { stock.set_name(&self.company_name); }
+        if let Some(market_cap) = self.market_cap { stock.set_market_cap(market_cap); }
+        if let Some(eps) = self.ttm_eps { stock.set_eps(eps); }
+        if let Some(pe_ratio) = self.pe_ratio { stock.set_pe_ratio(pe_ratio); }
+        if let Some(high52) = self.week52_high { stock.set_high52(high52); }
+        if let Some(low52) = self.week52_low { stock.set_low52(low52); }
+    }
+}
+
+/// Async client for the IEX Cloud REST API. The token is sent as the
+/// `token` query parameter on every request, and symbols are sent in
+/// lowercase.
+///
+/// ### Example
+/// ```
+/// let client = iex::Client::new("pk_...");
+/// let quote = client.quote("aapl").await?;
+/// let bars = client.chart("aapl", iex::ChartRange::OneYear).await?;
+/// let stock = client.load_stock("aapl", iex::ChartRange::FiveYears).await?;
+/// ```
+///
+/// #### Resources
+/// - https://iexcloud.io/docs/api/
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Client {
+    base_url: String,
+    token: String,
+    http: reqwest::Client,
+}
+
+#[allow(dead_code)]
+impl Client {
+    /// Creates a client for the production API.
+    pub fn new(token: &str) -> Self {
+        return Self::with_base_url(DEFAULT_BASE_URL, token);
+    }
+
+    /// Creates a client for another base URL, e.g. the IEX Cloud sandbox
+    /// (`https://sandbox.iexapis.com/stable`) or a local mock server.
+    pub fn with_base_url(base_url: &str, token: &str) -> Self {
+        return Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            http: reqwest::Client::new(),
+        };
+    }
+
+    /// Creates a client from the `IEX_TOKEN` environment variable and, if
+    /// set, `IEX_BASE_URL`.
+    pub fn from_env() -> Result<Self, IexError> {
+        let token = match env::var("IEX_TOKEN") {
+            Ok(token) if !token.is_empty() => token,
+            _ => return Err(IexError::MissingToken),
+        };
+        let base_url = env::var("IEX_BASE_URL").unwrap_or_else(|_| DEFAULT_BASE_URL.to_string());
+        return Ok(Self::with_base_url(&base_url, &token));
+    }
+
+    pub fn get_base_url(&self) -> String { return self.base_url.to_string(); }
+    pub fn set_base_url(&mut self, base_url: &str) { self.base_url = base_url.trim_end_matches('/').to_string(); }
+
+    pub fn get_token(&self) -> String { return self.token.to_string(); }
+    pub fn set_token(&mut self, token: &str) { self.token = token.to_string(); }
+
+    /// Sends a `GET` request to `path` and parses the JSON response.
+    async fn get(&self, path: &str) -> Result<JSONObject, IexError> {
+        let response = self.http
+            .get(format!("{}{}", self.base_url, path))
+            .query(&[("token", self.token.as_str())])
+            .send()
+            .await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(IexError::Status { status: status.as_u16(), body: body });
+        }
+        return Ok(parse::parse(&body)?);
+    }
+
+    /// Fetches the latest quote for `symbol`.
+    pub async fn quote(&self, symbol: &str) -> Result<Quote, IexError> {
+        let json = self.get(&format!("/stock/{}/quote", symbol.to_lowercase())).await?;
+        return Quote::from_json(&json);
+    }
+
+    /// Fetches daily bars for `symbol` over `range`, oldest first. IEX
+    /// dates the bars without a time, so they are stamped at the 16:00:00
+    /// close like the bundled historical data.
+    pub async fn chart(&self, symbol: &str, range: ChartRange) -> Result<Vec<HistoricalData>, IexError> {
+        let json = self.get(&format!("/stock/{}/chart/{}", symbol.to_lowercase(), range.as_str())).await?;
+        let bars = match json.as_array() {
+            Some(bars) => bars,
+            None => return Err(IexError::InvalidField { field: "chart", reason: "expected an array".to_string() }),
+        };
+        let mut data: Vec<HistoricalData> = Vec::with_capacity(bars.len());
+        for bar in bars {
+            let o = as_object(bar)?;
+            data.push(HistoricalData::from_date(
+                parse_date(&required_str(o, "date")?)?,
+                required_f64(o, "open")?,
+                required_f64(o, "high")?,
+                required_f64(o, "low")?,
+                required_f64(o, "close")?,
+                required_u64(o, "volume")?,
+            ));
+        }
+        return Ok(data);
+    }
+
+    /// Fetches the company profile for `symbol`.
+    pub async fn company(&self, symbol: &str) -> Result<Company, IexError> {
+        let json = self.get(&format!("/stock/{}/company", symbol.to_lowercase())).await?;
+        return Company::from_json(&json);
+    }
+
+    /// Fetches the key statistics for `symbol`.
+    pub async fn stats(&self, symbol: &str) -> Result<Stats, IexError> {
+        let json = self.get(&format!("/stock/{}/stats", symbol.to_lowercase())).await?;
+        return Stats::from_json(&json);
+    }
+
+    /// Combines the company, stats and quote endpoints, which are requested
+    /// concurrently, into the fields of the contemporary CSV files. The
+    /// quote's market cap, P/E ratio and 52 week range take precedence over
+    /// the statistics.
+    pub async fn fundamentals(&self, symbol: &str) -> Result<Fundamentals, IexError> {
+        let (company, stats, quote) = tokio::try_join!(
+            self.company(symbol),
+            self.stats(symbol),
+            self.quote(symbol),
+        )?;
+        let name = [company.get_company_name(), quote.get_company_name(), stats.get_company_name()]
+            .into_iter()
+            .find(|name| !name.is_empty());
+        let mut fundamentals = Fundamentals::new();
+        fundamentals.set_name(name);
+        if !company.get_issue_type().is_empty() { fundamentals.set_security_type(Some(company.get_security_type())); }
+        fundamentals.set_market_cap(quote.get_market_cap().or(stats.get_market_cap()));
+        fundamentals.set_high52(quote.get_week52_high().or(stats.get_week52_high()));
+        fundamentals.set_low52(quote.get_week52_low().or(stats.get_week52_low()));
+        fundamentals.set_pe_ratio(quote.get_pe_ratio().or(stats.get_pe_ratio()));
+        fundamentals.set_eps(stats.get_ttm_eps());
+        if !company.get_description().is_empty() { fundamentals.set_summary(Some(company.get_description())); }
+        return Ok(fundamentals);
+    }
+
+    /// Builds a `Stock` from the company, stats, quote and chart endpoints,
+    /// which are requested concurrently. The quote is applied last, so its
+    /// market cap and P/E ratio take precedence over the statistics.
+    pub async fn load_stock(&self, symbol: &str, range: ChartRange) -> Result<Stock, IexError> {
+        let (company, stats, quote, chart) = tokio::try_join!(
+            self.company(symbol),
+            self.stats(symbol),
+            self.quote(symbol),
+            self.chart(symbol, range),
+        )?;
+        let mut stock = Stock::new(symbol);
+        company.apply_to(&mut stock);
+        stats.apply_to(&mut stock);
+        quote.apply_to(&mut stock);
+        stock.add_historical_data(chart);
+        return Ok(stock);
+    }
+}
+
+fn as_object(json: &JSONObject) -> Result<&JSON, IexError> {
+    return match json.as_object() {
+        Some(o) => Ok(o),
+        None => Err(IexError::InvalidField { field: "response", reason: "expected an object".to_string() }),
+    };
+}
+
+/// Returns the field, treating `null` like a missing field.
+fn field<'a>(o: &'a JSON, field: &'static str) -> Option<&'a JSONObject> {
+    return o.get(field).filter(|v| !v.is_null());
+}
+
+fn optional_str(o: &JSON, name: &'static str) -> Option<String> {
+    return field(o, name).and_then(|v| v.as_str()).map(|s| s.to_string());
+}
+
+fn optional_f64(o: &JSON, name: &'static str) -> Option<f64> {
+    return field(o, name).and_then(|v| v.as_f64());
+}
+
+fn optional_u64(o: &JSON, name: &'static str) -> Option<u64> {
+    return field(o, name).and_then(|v| v.as_u64());
+}
+
+fn required<'a, T>(o: &'a JSON, name: &'static str, convert: fn(&'a JSONObject) -> Option<T>, expected: &str) -> Result<T, IexError> {
+    let value = match field(o, name) {
+        Some(value) => value,
+        None => return Err(IexError::MissingField { field: name }),
+    };
+    return match convert(value) {
+        Some(value) => Ok(value),
+        None => Err(IexError::InvalidField { field: name, reason: format!("expected {}, got {}", expected, value.stringify()) }),
+    };
+}
+
+fn required_str(o: &JSON, name: &'static str) -> Result<String, IexError> {
+    return required(o, name, |v| v.as_str().map(|s| s.to_string()), "a string");
+}
+
+fn required_f64(o: &JSON, name: &'static str) -> Result<f64, IexError> {
+    return required(o, name, JSONObject::as_f64, "a number");
+}
+
+fn required_i64(o: &JSON, name: &'static str) -> Result<i64, IexError> {
+    return required(o, name, JSONObject::as_i64, "an integer");
+}
+
+fn required_u64(o: &JSON, name: &'static str) -> Result<u64, IexError> {
+    return required(o, name, JSONObject::as_u64, "a non-negative integer");
+}
+
+/// Parses a `YYYY-MM-DD` chart date into the 16:00:00 close of that day.
+fn parse_date(date: &str) -> Result<DateTime, IexError> {
+    let invalid = || IexError::InvalidField { field: "date", reason: format!("expected YYYY-MM-DD, got \"{}\"", date) };
+    let parts: Vec<&str> = date.split('-').collect();
+    if parts.len() != 3 { return Err(invalid()); }
+    let year = parts[0].parse::<u16>().map_err(|_| invalid())?;
+    let month = parts[1].parse::<u8>().map_err(|_| invalid())?;
+    let day = parts[2].parse::<u8>().map_err(|_| invalid())?;
+    return DateTime::parse(&format!("{}/{}/{} 16:00:00", month, day, year)).map_err(|_| invalid());
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::mock::MockServer;
+
+    async fn aapl_server() -> MockServer {
+        return MockServer::new()
+            .fixture("/stock/aapl/quote", "test/data/iex/aapl_quote.json")
+            .fixture("/stock/aapl/chart/5d", "test/data/iex/aapl_chart_5d.json")
+            .fixture("/stock/aapl/company", "test/data/iex/aapl_company.json")
+            .fixture("/stock/aapl/stats", "test/data/iex/aapl_stats.json")
+            .start().await;
+    }
+
+    #[tokio::test]
+    async fn test_quote() {
+        let server = aapl_server().await;
+        let client = Client::with_base_url(&format!("{}/", server.url()), "Tsk_test");
+        let quote = client.quote("aapl").await.unwrap();
+        assert_eq!(quote.get_symbol(), "AAPL");
+        assert_eq!(quote.get_company_name(), "Apple Inc");
+        assert_eq!(quote.get_latest_price(), 147.96);
+        assert_eq!(quote.get_previous_close(), Some(148.71));
+        assert_eq!(quote.get_volume(), Some(53950201));
+        assert_eq!(quote.get_market_cap(), Some(2394788219520));
+        assert_eq!(quote.get_latest_update().to_string(), "6/8/2022 20:00:00");
+        assert_eq!(server.requests(), vec!["/stock/aapl/quote?token=Tsk_test"]);
+    }
+
+    #[tokio::test]
+    async fn test_chart() {
+        let server = aapl_server().await;
+        let client = Client::with_base_url(&server.url(), "Tsk_test");
+        let bars = client.chart("aapl", ChartRange::FiveDays).await.unwrap();
+        assert_eq!(bars.len(), 5);
+        assert_eq!(bars[0].to_string(), HistoricalData::new("6/2/2022 16:00:00", 147.83, 151.27, 146.86, 151.21, 72348055).to_string());
+        assert_eq!(bars[3].get_high(), 149.0);
+        assert_eq!(bars[4].get_date().to_string(), "6/8/2022 16:00:00");
+    }
+
+    #[tokio::test]
+    async fn test_company_and_stats() {
+        let server = aapl_server().await;
+        let client = Client::with_base_url(&server.url(), "Tsk_test");
+        let company = client.company("aapl").await.unwrap();
+        assert_eq!(company.get_industry(), "Electronic Computer Manufacturing");
+        assert_eq!(company.get_security_type(), "EQUITY");
+        let stats = client.stats("aapl").await.unwrap();
+        assert_eq!(stats.get_ttm_eps(), Some(6.07));
+        assert_eq!(stats.get_week52_low(), Some(125.94));
+    }
+
+    #[tokio::test]
+    async fn test_load_stock() {
+        let server = aapl_server().await;
+        let client = Client::with_base_url(&server.url(), "Tsk_test");
+        let stock = client.load_stock("aapl", ChartRange::FiveDays).await.unwrap();
+        assert_eq!(stock.get_ticker(), "aapl");
+        assert_eq!(stock.get_name(), "Apple Inc");
+        assert_eq!(stock.get_security_type(), "EQUITY");
+        assert_eq!(stock.get_market_cap(), 2394788219520);
+        assert_eq!(stock.get_pe_ratio(), 24.37);
+        assert_eq!(stock.get_eps(), 6.07);
+        assert_eq!(stock.get_high52(), 182.94);
+        assert_eq!(stock.get_summary().starts_with("Apple Inc. designs"), true);
+        assert_eq!(stock.get_price_series().get_closes(), &[151.21, 145.38, 146.14, 148.71, 147.96]);
+        assert_eq!(server.requests().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_fundamentals() {
+        let server = aapl_server().await;
+        let client = Client::with_base_url(&server.url(), "Tsk_test");
+        let fundamentals = client.fundamentals("aapl").await.unwrap();
+        assert_eq!(fundamentals.get_name(), Some("Apple Inc".to_string()));
+        assert_eq!(fundamentals.get_security_type(), Some("EQUITY".to_string()));
+        assert_eq!(fundamentals.get_market_cap(), Some(2394788219520));
+        assert_eq!(fundamentals.get_eps(), Some(6.07));
+        assert_eq!(fundamentals.get_low52(), Some(125.94));
+    }
+
+    #[test]
+    fn test_chart_range_covering() {
+        let today = DateTime::new("6/8/2022 16:00:00");
+        assert_eq!(ChartRange::covering(&DateTime::new("6/6/2022"), &today), ChartRange::FiveDays);
+        assert_eq!(ChartRange::covering(&DateTime::new("6/1/2022"), &today), ChartRange::OneMonth);
+        assert_eq!(ChartRange::covering(&DateTime::new("1/3/2022"), &today), ChartRange::SixMonths);
+        assert_eq!(ChartRange::covering(&DateTime::new("6/8/2021"), &today), ChartRange::TwoYears);
+        assert_eq!(ChartRange::covering(&DateTime::new("1/2/1990"), &today), ChartRange::Max);
+    }
+
+    #[tokio::test]
+    async fn test_errors() {
+        let server = MockServer::new()
+            .route("/stock/aapl/quote", 200, "{\"symbol\": \"AAPL\", \"latestPrice\": null}")
+            .route("/stock/aapl/stats", 200, "{\"marketcap\": 1,")
+            .route("/stock/aapl/chart/1m", 200, "[{\"date\": \"06/08/2022\"}]")
+            .route("/stock/aapl/company", 402, "You have exceeded your allotted message quota.")
+            .start().await;
+        let client = Client::with_base_url(&server.url(), "Tsk_test");
+        assert_eq!(client.quote("aapl").await, Err(IexError::MissingField { field: "latestPrice" }));
+        assert_eq!(client.stats("aapl").await, Err(IexError::Json(JsonError::UnexpectedEnd)));
+        assert_eq!(
+            client.chart("aapl", ChartRange::OneMonth).await.unwrap_err(),
+            IexError::InvalidField { field: "date", reason: "expected YYYY-MM-DD, got \"06/08/2022\"".to_string() }
+        );
+        assert_eq!(
+            client.company("aapl").await,
+            Err(IexError::Status { status: 402, body: "You have exceeded your allotted message quota.".to_string() })
+        );
+        assert_eq!(client.company("zzzz").await.unwrap_err().to_string(), "IEX returned status 404: Unknown symbol");
+    }
+}
@@ -0,0 +1,148 @@
+use std::{fs::read_to_string, path::Path, sync::{Arc, Mutex}};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::stock::iex::ChartRange;
+
+/// A recorded response, replayed for requests whose path matches.
+#[derive(Debug)]
+#[derive(Clone)]
+struct Route {
+    path: String,
+    status: u16,
+    body: String,
+}
+
+/// Minimal HTTP/1.1 server on `127.0.0.1` that replays recorded JSON
+/// fixtures, so the IEX client can be tested offline. Requests are matched
+/// on their path, ignoring the query string. Unknown paths get a `404`.
+/// Every request target, query included, is recorded for assertions.
+///
+/// ### Example
+/// ```
+/// let server = MockServer::new()
+///     .fixture("/stock/aapl/quote", "test/data/iex/aapl_quote.json")
+///     .start().await;
+/// let client = Client::with_base_url(&server.url(), "token");
+/// ```
+#[derive(Debug)]
+#[derive(Default)]
+pub struct MockServer {
+    routes: Vec<Route>,
+    url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+#[allow(dead_code)]
+impl MockServer {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /// Replays `body` with `status` for requests to `path`.
+    pub fn route(mut self, path: &str, status: u16, body: &str) -> Self {
+        self.routes.push(Route { path: path.to_string(), status: status, body: body.to_string() });
+        return self;
+    }
+
+    /// Replays the content of the fixture file at `location` with a `200`
+    /// for requests to `path`.
+    pub fn fixture(self, path: &str, location: &str) -> Self {
+        let body = match read_to_string(Path::new(location)) {
+            Ok(body) => body,
+            Err(error) => panic!("Could not read fixture {}: {}", location, error),
+        };
+        return self.route(path, 200, &body);
+    }
+
+    /// Replays the fixture file at `location` for the chart endpoint of
+    /// `symbol` with any range, so that tests do not depend on the range
+    /// chosen for today's date.
+    pub fn chart_fixture(mut self, symbol: &str, location: &str) -> Self {
+        for range in ChartRange::ALL {
+            self = self.fixture(&format!("/stock/{}/chart/{}", symbol, range.as_str()), location);
+        }
+        return self;
+    }
+
+    /// Binds to a free port and serves requests in a background task until
+    /// the runtime shuts down.
+    pub async fn start(mut self) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        self.url = format!("http://{}", listener.local_addr().unwrap());
+
+        let routes = self.routes.clone();
+        let requests = Arc::clone(&self.requests);
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = match listener.accept().await {
+                    Ok(connection) => connection,
+                    Err(_) => return,
+                };
+                let routes = routes.clone();
+                let requests = Arc::clone(&requests);
+                tokio::spawn(async move {
+                    let target = match read_request_target(&mut socket).await {
+                        Some(target) => target,
+                        None => return,
+                    };
+                    requests.lock().unwrap().push(target.clone());
+
+                    let path = target.split('?').next().unwrap_or("");
+                    let (status, body) = match routes.iter().find(|r| r.path == path) {
+                        Some(route) => (route.status, route.body.clone()),
+                        None => (404, "Unknown symbol".to_string()),
+                    };
+                    let response = format!(
+                        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status, reason_phrase(status), body.len(), body
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                    let _ = socket.shutdown().await;
+                });
+            }
+        });
+        return self;
+    }
+
+    /// Returns the base URL of the running server, e.g.
+    /// `http://127.0.0.1:41234`.
+    pub fn url(&self) -> String {
+        return self.url.to_string();
+    }
+
+    /// Returns the targets of the requests received so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        return self.requests.lock().unwrap().clone();
+    }
+}
+
+/// Reads the request head and returns the request target from its first
+/// line. The client only sends `GET` requests, so there is no body to read.
+async fn read_request_target(socket: &mut tokio::net::TcpStream) -> Option<String> {
+    let mut head: Vec<u8> = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = socket.read(&mut buffer).await.ok()?;
+        if read == 0 { return None; }
+        head.extend_from_slice(&buffer[..read]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let request_line = head.lines().next()?;
+    return request_line.split_whitespace().nth(1).map(|t| t.to_string());
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    return match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        402 => "Payment Required",
+        403 => "Forbidden",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        _ => "Unknown",
+    };
+}
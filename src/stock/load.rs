@@ -0,0 +1,663 @@
+use std::{fmt, fs::read_to_string, path::{Path, PathBuf}};
+
+use crate::stock::csv::{self, CsvError, Record};
+use crate::stock::datetime::DateTime;
+use crate::stock::historical::{HistoricalData, MergeError, MergePolicy, MergeReport};
+
+/// Error returned when loading a data file fails.
+///
+/// - `NotFound`: the file does not exist
+/// - `Io`: the file exists but could not be read
+/// - `Csv`: the file is not well formed CSV
+/// - `Empty`: the file has no header, or no data row where one is required
+/// - `MissingColumn`: a required column is not in the header
+/// - `InvalidRow`: a row could not be loaded and `BadRows::Error` is set
+/// - `InvalidCache`: a cache file is truncated or was not written by
+///   `cache::write`
+/// - `Merge`: a loaded bar restates an existing bar and
+///   `MergePolicy::Error` is set
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum LoadError {
+    NotFound {
+        path: String,
+    },
+    Io {
+        path: String,
+        reason: String,
+    },
+    Csv(CsvError),
+    Empty,
+    MissingColumn {
+        column: &'static str,
+    },
+    InvalidRow {
+        line: usize,
+        reason: String,
+    },
+    InvalidCache {
+        path: String,
+        reason: String,
+    },
+    Merge(MergeError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            LoadError::NotFound { path } => write!(f, "Could not find file {}.", path),
+            LoadError::Io { path, reason } => write!(f, "Could not read file {}: {}", path, reason),
+            LoadError::Csv(error) => write!(f, "{}", error),
+            LoadError::Empty => write!(f, "The file contains no data."),
+            LoadError::MissingColumn { column } => write!(f, "Missing required column `{}`.", column),
+            LoadError::InvalidRow { line, reason } => write!(f, "Invalid row on line {}: {}.", line, reason),
+            LoadError::InvalidCache { path, reason } => write!(f, "Invalid cache file {}: {}.", path, reason),
+            LoadError::Merge(error) => write!(f, "{}", error),
+        };
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<MergeError> for LoadError {
+    fn from(error: MergeError) -> Self {
+        return LoadError::Merge(error);
+    }
+}
+
+impl From<CsvError> for LoadError {
+    fn from(error: CsvError) -> Self {
+        return LoadError::Csv(error);
+    }
+}
+
+/// What to do with rows that cannot be loaded.
+///
+/// - `Skip`: leave the row out and record it in `LoadReport::get_skipped`
+/// - `Error`: stop loading and return `LoadError::InvalidRow`
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum BadRows {
+    Skip,
+    Error,
+}
+
+/// How to treat bars whose prices are inconsistent, i.e. the high is below
+/// the low, or the open or close is outside of `[low, high]`.
+///
+/// - `Ignore`: load the bar without checking
+/// - `Flag`: load the bar and record it in `LoadReport::get_flagged`
+/// - `Reject`: handle the bar as a bad row
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum OhlcCheck {
+    Ignore,
+    Flag,
+    Reject,
+}
+
+/// Options for loading historical data. Defaults to skipping bad rows,
+/// flagging inconsistent bars, not caching and replacing restated bars.
+///
+/// When a cache directory is set, each CSV file is parsed once and stored
+/// as `<cache directory>/<file name>.bin`. Later loads read the binary file
+/// instead, until the CSV is modified again. See `cache`.
+///
+/// The merge policy decides what happens to a loaded bar that restates a
+/// bar the stock already has. See `historical::merge`.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct LoadOptions {
+    bad_rows: BadRows,
+    ohlc_check: OhlcCheck,
+    cache_directory: Option<PathBuf>,
+    merge_policy: MergePolicy,
+}
+
+#[allow(dead_code)]
+impl LoadOptions {
+    pub fn new(bad_rows: BadRows, ohlc_check: OhlcCheck) -> Self {
+        return Self { bad_rows: bad_rows, ohlc_check: ohlc_check, cache_directory: None, merge_policy: MergePolicy::TakeNew };
+    }
+
+    pub fn get_bad_rows(&self) -> BadRows { return self.bad_rows; }
+    pub fn set_bad_rows(&mut self, bad_rows: BadRows) { self.bad_rows = bad_rows; }
+
+    pub fn get_ohlc_check(&self) -> OhlcCheck { return self.ohlc_check; }
+    pub fn set_ohlc_check(&mut self, ohlc_check: OhlcCheck) { self.ohlc_check = ohlc_check; }
+
+    pub fn get_cache_directory(&self) -> Option<&Path> { return self.cache_directory.as_deref(); }
+    pub fn set_cache_directory(&mut self, cache_directory: Option<PathBuf>) { self.cache_directory = cache_directory; }
+
+    pub fn get_merge_policy(&self) -> MergePolicy { return self.merge_policy; }
+    pub fn set_merge_policy(&mut self, merge_policy: MergePolicy) { self.merge_policy = merge_policy; }
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        return Self::new(BadRows::Skip, OhlcCheck::Flag);
+    }
+}
+
+/// A row that was skipped or flagged while loading.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct RowIssue {
+    line: usize,
+    reason: String,
+}
+
+#[allow(dead_code)]
+impl RowIssue {
+    pub fn new(line: usize, reason: &str) -> Self {
+        return Self { line: line, reason: reason.to_string() };
+    }
+
+    pub fn get_line(&self) -> usize { return self.line; }
+    pub fn get_reason(&self) -> String { return self.reason.to_string(); }
+}
+
+/// Summary of a load: how many rows were loaded, which rows were skipped
+/// or flagged, and, once merged into a `Stock`, how the loaded bars
+/// overlapped its existing data.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct LoadReport {
+    loaded: usize,
+    skipped: Vec<RowIssue>,
+    flagged: Vec<RowIssue>,
+    merge: MergeReport,
+}
+
+#[allow(dead_code)]
+impl LoadReport {
+    pub fn new(loaded: usize, skipped: Vec<RowIssue>, flagged: Vec<RowIssue>) -> Self {
+        return Self { loaded: loaded, skipped: skipped, flagged: flagged, merge: MergeReport::new() };
+    }
+
+    pub fn get_loaded(&self) -> usize { return self.loaded; }
+    pub fn get_skipped(&self) -> &Vec<RowIssue> { return &self.skipped; }
+    pub fn get_flagged(&self) -> &Vec<RowIssue> { return &self.flagged; }
+
+    pub fn get_merge(&self) -> &MergeReport { return &self.merge; }
+    pub fn set_merge(&mut self, merge: MergeReport) { self.merge = merge; }
+}
+
+/// Reads a file into a string, mapping failures to `LoadError`.
+pub fn read_file(location: &Path) -> Result<String, LoadError> {
+    let path = location.to_string_lossy().to_string();
+    if !location.exists() {
+        return Err(LoadError::NotFound { path: path });
+    }
+    return match read_to_string(location) {
+        Ok(content) => Ok(content),
+        Err(error) => Err(LoadError::Io { path: path, reason: error.to_string() }),
+    };
+}
+
+/// Fundamentals read from a contemporary data file. Fields are `None` when
+/// the file has no such column. Empty values read as `0` or `""`.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Default)]
+pub struct Fundamentals {
+    name: Option<String>,
+    security_type: Option<String>,
+    market_cap: Option<u64>,
+    high52: Option<f64>,
+    low52: Option<f64>,
+    pe_ratio: Option<f64>,
+    eps: Option<f64>,
+    summary: Option<String>,
+}
+
+#[allow(dead_code)]
+impl Fundamentals {
+    /// Creates fundamentals with every field missing.
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn get_name(&self) -> Option<String> { return self.name.clone(); }
+    pub fn set_name(&mut self, name: Option<String>) { self.name = name; }
+
+    pub fn get_security_type(&self) -> Option<String> { return self.security_type.clone(); }
+    pub fn set_security_type(&mut self, security_type: Option<String>) { self.security_type = security_type; }
+
+    pub fn get_market_cap(&self) -> Option<u64> { return self.market_cap; }
+    pub fn set_market_cap(&mut self, market_cap: Option<u64>) { self.market_cap = market_cap; }
+
+    pub fn get_high52(&self) -> Option<f64> { return self.high52; }
+    pub fn set_high52(&mut self, high52: Option<f64>) { self.high52 = high52; }
+
+    pub fn get_low52(&self) -> Option<f64> { return self.low52; }
+    pub fn set_low52(&mut self, low52: Option<f64>) { self.low52 = low52; }
+
+    pub fn get_pe_ratio(&self) -> Option<f64> { return self.pe_ratio; }
+    pub fn set_pe_ratio(&mut self, pe_ratio: Option<f64>) { self.pe_ratio = pe_ratio; }
+
+    pub fn get_eps(&self) -> Option<f64> { return self.eps; }
+    pub fn set_eps(&mut self, eps: Option<f64>) { self.eps = eps; }
+
+    pub fn get_summary(&self) -> Option<String> { return self.summary.clone(); }
+    pub fn set_summary(&mut self, summary: Option<String>) { self.summary = summary; }
+}
+
+/// Returns the index of the first header column matching any of `names`.
+fn find_any(header: &Record, names: &[&str]) -> Option<usize> {
+    return names.iter().find_map(|name| header.find(name));
+}
+
+/// Returns the index of a required header column.
+fn require(header: &Record, column: &'static str) -> Result<usize, LoadError> {
+    return match header.find(column) {
+        Some(index) => Ok(index),
+        None => Err(LoadError::MissingColumn { column: column }),
+    };
+}
+
+fn parse_price(record: &Record, index: usize, column: &str) -> Result<f64, String> {
+    let value = record.get(index).unwrap_or("").trim();
+    if value.is_empty() {
+        return Err(format!("missing {}", column));
+    }
+    return match value.parse::<f64>() {
+        Ok(price) if price.is_finite() => Ok(price),
+        _ => Err(format!("invalid {} \"{}\"", column, value)),
+    };
+}
+
+/// Parses a volume, accepting whole numbers written as floats (ex.
+/// `806400.0`).
+fn parse_volume(record: &Record, index: usize) -> Result<u64, String> {
+    let value = record.get(index).unwrap_or("").trim();
+    if value.is_empty() {
+        return Err("missing volume".to_string());
+    }
+    return match parse_whole(value) {
+        Some(volume) => Ok(volume),
+        _ => Err(format!("invalid volume \"{}\"", value)),
+    };
+}
+
+/// Parses a whole number, accepting values written as floats (ex.
+/// `806400.0`).
+fn parse_whole(value: &str) -> Option<u64> {
+    if let Ok(number) = value.parse::<u64>() {
+        return Some(number);
+    }
+    return match value.parse::<f64>() {
+        Ok(number) if number.fract() == 0.0 && number >= 0.0 && number <= u64::MAX as f64 => Some(number as u64),
+        _ => None,
+    };
+}
+
+/// Returns a description of the first inconsistency in the bar's prices.
+fn check_ohlc(bar: &HistoricalData) -> Option<String> {
+    if bar.get_high() < bar.get_low() {
+        return Some(format!("high {} is below low {}", bar.get_high(), bar.get_low()));
+    }
+    if bar.get_close() < bar.get_low() || bar.get_close() > bar.get_high() {
+        return Some(format!("close {} is outside of [{}, {}]", bar.get_close(), bar.get_low(), bar.get_high()));
+    }
+    if bar.get_open() < bar.get_low() || bar.get_open() > bar.get_high() {
+        return Some(format!("open {} is outside of [{}, {}]", bar.get_open(), bar.get_low(), bar.get_high()));
+    }
+    return None;
+}
+
+/// Parses historical OHLCV data from CSV content. Columns are looked up by
+/// name (`Date`, `Open`, `High`, `Low`, `Close` and `Volume`, ignoring
+/// case), so their order does not matter and extra columns are ignored.
+///
+/// Rows that cannot be parsed, such as rows missing one of those columns,
+/// rows with empty prices or rows with malformed quoting, are skipped or
+/// reported depending on `options`. Line numbers in the report refer to
+/// the line the row starts on, or for malformed quoting the line the error
+/// is on.
+///
+/// # Arguments
+///
+/// * `content` - CSV content, header included
+/// * `options` - How to handle bad rows and inconsistent bars
+///
+/// ### Examples
+/// ```
+/// let (data, report) = load::parse_historical("Date,Open,High,Low,Close,Volume\n1/2/2014 16:00:00,183.98,184.07,182.48,182.92,119636836\n", &LoadOptions::default()).unwrap();
+/// assert_eq!(data.len(), 1);
+/// assert_eq!(report.get_loaded(), 1);
+/// ```
+pub fn parse_historical(content: &str, options: &LoadOptions) -> Result<(Vec<HistoricalData>, LoadReport), LoadError> {
+    let records = csv::parse_records(content);
+    let header = match records.first() {
+        Some(Ok(header)) => header,
+        Some(Err(error)) => return Err(LoadError::Csv(error.clone())),
+        None => return Err(LoadError::Empty),
+    };
+    let date = require(header, "date")?;
+    let open = require(header, "open")?;
+    let high = require(header, "high")?;
+    let low = require(header, "low")?;
+    let close = require(header, "close")?;
+    let volume = require(header, "volume")?;
+    // Only the columns that are read have to be present
+    let required = [date, open, high, low, close, volume].iter().max().unwrap() + 1;
+
+    let mut data = Vec::new();
+    let mut report = LoadReport::new(0, Vec::new(), Vec::new());
+    for record in records.iter().skip(1) {
+        // Malformed quoting only affects the row it is on
+        let record = match record {
+            Ok(record) => record,
+            Err(error) => {
+                let reason = error.to_string().trim_end_matches('.').to_string();
+                match options.bad_rows {
+                    BadRows::Skip => report.skipped.push(RowIssue { line: error.get_line(), reason: reason }),
+                    BadRows::Error => return Err(LoadError::InvalidRow { line: error.get_line(), reason: reason }),
+                };
+                continue;
+            },
+        };
+        let bar = (|| -> Result<HistoricalData, String> {
+            if record.len() < required {
+                return Err(format!("expected {} fields, found {}", required, record.len()));
+            }
+            let d = match DateTime::parse(record.get(date).unwrap_or("")) {
+                Ok(d) => d,
+                Err(error) => return Err(error.to_string().trim_end_matches('.').to_string()),
+            };
+            return Ok(HistoricalData::from_date(
+                d,
+                parse_price(record, open, "open")?,
+                parse_price(record, high, "high")?,
+                parse_price(record, low, "low")?,
+                parse_price(record, close, "close")?,
+                parse_volume(record, volume)?,
+            ));
+        })();
+
+        let bar = match bar {
+            Ok(bar) => match (options.ohlc_check, check_ohlc(&bar)) {
+                (OhlcCheck::Flag, Some(reason)) => {
+                    report.flagged.push(RowIssue { line: record.get_line(), reason: reason });
+                    Ok(bar)
+                },
+                (OhlcCheck::Reject, Some(reason)) => Err(reason),
+                _ => Ok(bar),
+            },
+            Err(reason) => Err(reason),
+        };
+
+        match bar {
+            Ok(bar) => data.push(bar),
+            Err(reason) => match options.bad_rows {
+                BadRows::Skip => report.skipped.push(RowIssue { line: record.get_line(), reason: reason }),
+                BadRows::Error => return Err(LoadError::InvalidRow { line: record.get_line(), reason: reason }),
+            },
+        };
+    }
+    report.loaded = data.len();
+    return Ok((data, report));
+}
+
+
+/// Parses fundamentals from CSV content with a header and one data row.
+/// Columns are looked up by name, ignoring case, so both the bundled
+/// layout (`name,type,marketcap,high52,low52,pe,eps,summary`) and shorter
+/// layouts such as `type,marketcap,high52,low52,pe,eps` load correctly.
+/// Quoted values, such as a summary containing commas, are supported.
+///
+/// # Arguments
+///
+/// * `content` - CSV content, header included
+///
+/// ### Examples
+/// ```
+/// let f = load::parse_fundamentals("name,type,pe\n\"Apple, Inc.\",EQUITY,23.9\n").unwrap();
+/// assert_eq!(f.get_name(), Some("Apple, Inc.".to_string()));
+/// assert_eq!(f.get_eps(), None);
+/// ```
+pub fn parse_fundamentals(content: &str) -> Result<Fundamentals, LoadError> {
+    let records = csv::parse(content)?;
+    if records.len() < 2 {
+        return Err(LoadError::Empty);
+    }
+    let header = &records[0];
+    let row = &records[1];
+
+    let text = |names: &[&str]| -> Option<String> {
+        let index = find_any(header, names)?;
+        return Some(row.get(index).unwrap_or("").trim().to_string());
+    };
+    let number = |names: &[&str], column: &str| -> Result<Option<f64>, LoadError> {
+        let value = match text(names) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        if value.is_empty() {
+            return Ok(Some(0.0));
+        }
+        return match value.parse::<f64>() {
+            Ok(number) => Ok(Some(number)),
+            Err(_) => Err(LoadError::InvalidRow { line: row.get_line(), reason: format!("invalid {} \"{}\"", column, value) }),
+        };
+    };
+
+    let market_cap = match text(&["marketcap", "market_cap"]) {
+        Some(value) if value.is_empty() => Some(0),
+        Some(value) => match parse_whole(&value) {
+            Some(market_cap) => Some(market_cap),
+            None => return Err(LoadError::InvalidRow { line: row.get_line(), reason: format!("invalid marketcap \"{}\"", value) }),
+        },
+        None => None,
+    };
+
+    return Ok(Fundamentals {
+        name: text(&["name"]),
+        security_type: text(&["type", "security_type"]),
+        market_cap: market_cap,
+        high52: number(&["high52"], "high52")?,
+        low52: number(&["low52"], "low52")?,
+        pe_ratio: number(&["pe", "pe_ratio"], "pe")?,
+        eps: number(&["eps"], "eps")?,
+        summary: text(&["summary"]),
+    });
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "Date,Open,High,Low,Close,Volume\n";
+
+    #[test]
+    fn test_parse_historical() {
+        let content = format!("{}1/2/2014 16:00:00,183.98,184.07,182.48,182.92,119636836\n1/3/2014 16:00:00,183.21,183.6,182.63,182.89,81390502\n\n", HEADER);
+        let (data, report) = parse_historical(&content, &LoadOptions::default()).unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[1].get_date().to_string(), "1/3/2014 16:00:00");
+        assert_eq!(data[1].get_open(), 183.21);
+        assert_eq!(data[1].get_volume(), 81390502);
+        assert_eq!(report.get_loaded(), 2);
+        assert_eq!(report.get_skipped().len(), 0);
+        assert_eq!(report.get_flagged().len(), 0);
+    }
+
+    #[test]
+    fn test_parse_historical_column_order() {
+        let content = "volume,\"Adj Close\",close,low,high,open,date\n806400.0,0.1,0.26,0.25,0.27,0.26,2014-01-02\n";
+        let (data, _) = parse_historical(content, &LoadOptions::default()).unwrap();
+        assert_eq!(data[0].get_date().to_string(), "1/2/2014 00:00:00");
+        assert_eq!(data[0].get_open(), 0.26);
+        assert_eq!(data[0].get_high(), 0.27);
+        assert_eq!(data[0].get_low(), 0.25);
+        assert_eq!(data[0].get_close(), 0.26);
+        assert_eq!(data[0].get_volume(), 806400);
+    }
+
+    #[test]
+    fn test_parse_historical_large_values() {
+        // Aggregated volume beyond `u32::MAX` and a price that needs more
+        // precision than `f32`
+        let content = format!("{}1/2/2014 16:00:00,123456.78,123456.79,123456.77,123456.78,5123456789\n", HEADER);
+        let (data, _) = parse_historical(&content, &LoadOptions::default()).unwrap();
+        assert_eq!(data[0].get_volume(), 5123456789);
+        assert_eq!(data[0].get_close(), 123456.78);
+        assert_eq!(data[0].get_high() - data[0].get_close() > 0.0, true);
+    }
+
+    #[test]
+    fn test_parse_historical_skips_bad_rows() {
+        let content = format!("{}{}{}{}{}",
+            HEADER,
+            "09/10/2001 16:00:00,24.87,25.09,24.75,24.97,13732600\n",
+            "09/12/2001 16:00:00,,,,,\n",
+            "09/17/2001 16:00:00,24.75,25.1\n",
+            "13/18/2001 16:00:00,25.14,25.15,24.47,24.73,abc\n",
+        );
+        let (data, report) = parse_historical(&content, &LoadOptions::default()).unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(report.get_loaded(), 1);
+        let skipped: Vec<(usize, String)> = report.get_skipped().iter().map(|i| (i.get_line(), i.get_reason())).collect();
+        assert_eq!(skipped, vec![
+            (3, "missing open".to_string()),
+            (4, "expected 6 fields, found 3".to_string()),
+            (5, "Invalid month 13 in datetime string \"13/18/2001 16:00:00\"".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_historical_short_unused_column() {
+        let content = format!("{}{}{}",
+            "Date,Open,High,Low,Close,Volume,Adj Close\n",
+            "09/10/2001 16:00:00,24.87,25.09,24.75,24.97,13732600,24.97\n",
+            "09/11/2001 16:00:00,24.87,25.09,24.75,24.97,13732600\n",
+        );
+        let (data, report) = parse_historical(&content, &LoadOptions::default()).unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(report.get_skipped().len(), 0);
+    }
+
+    #[test]
+    fn test_parse_historical_error_on_bad_rows() {
+        let content = format!("{}1/2/2014 16:00:00,183.98,184.07,182.48,182.92,1.5\n", HEADER);
+        let options = LoadOptions::new(BadRows::Error, OhlcCheck::Flag);
+        let error = parse_historical(&content, &options).unwrap_err();
+        assert_eq!(error, LoadError::InvalidRow { line: 2, reason: "invalid volume \"1.5\"".to_string() });
+        assert_eq!(error.to_string(), "Invalid row on line 2: invalid volume \"1.5\".");
+    }
+
+    #[test]
+    fn test_parse_historical_ohlc_check() {
+        let content = format!("{}{}{}{}",
+            HEADER,
+            "1/2/2014 16:00:00,183.98,184.07,182.48,182.92,100\n",
+            "1/3/2014 16:00:00,183.21,182.6,183.63,182.89,100\n",
+            "1/6/2014 16:00:00,0.0,183.56,182.08,182.36,100\n",
+        );
+        let (data, report) = parse_historical(&content, &LoadOptions::default()).unwrap();
+        assert_eq!(data.len(), 3);
+        let flagged: Vec<usize> = report.get_flagged().iter().map(|i| i.get_line()).collect();
+        assert_eq!(flagged, vec![3, 4]);
+        assert_eq!(report.get_flagged()[0].get_reason(), "high 182.6 is below low 183.63");
+        assert_eq!(report.get_flagged()[1].get_reason(), "open 0 is outside of [182.08, 183.56]");
+
+        let (data, report) = parse_historical(&content, &LoadOptions::new(BadRows::Skip, OhlcCheck::Reject)).unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(report.get_skipped().len(), 2);
+        assert_eq!(report.get_flagged().len(), 0);
+
+        let (data, report) = parse_historical(&content, &LoadOptions::new(BadRows::Skip, OhlcCheck::Ignore)).unwrap();
+        assert_eq!(data.len(), 3);
+        assert_eq!(report.get_flagged().len(), 0);
+
+        let error = parse_historical(&content, &LoadOptions::new(BadRows::Error, OhlcCheck::Reject)).unwrap_err();
+        assert_eq!(error, LoadError::InvalidRow { line: 3, reason: "high 182.6 is below low 183.63".to_string() });
+    }
+
+    #[test]
+    fn test_parse_historical_header_errors() {
+        assert_eq!(parse_historical("", &LoadOptions::default()).unwrap_err(), LoadError::Empty);
+        assert_eq!(parse_historical("\n\n", &LoadOptions::default()).unwrap_err(), LoadError::Empty);
+        assert_eq!(
+            parse_historical("Date,Open,High,Low,Close\n", &LoadOptions::default()).unwrap_err(),
+            LoadError::MissingColumn { column: "volume" },
+        );
+        assert_eq!(
+            parse_historical("Date,\"Open\"x,High,Low,Close,Volume\n1/2/2014,1,1,1,1,1\n", &LoadOptions::default()).unwrap_err(),
+            LoadError::Csv(CsvError::UnexpectedCharacter { line: 1, character: 'x' }),
+        );
+    }
+
+    #[test]
+    fn test_parse_historical_malformed_quoting() {
+        let content = format!("{}{}{}{}{}",
+            HEADER,
+            "1/2/2014 16:00:00,183.98,184.07,182.48,182.92,119636836\n",
+            "1/3/2014 16:00:00,\"183.21\"0,183.6,182.63,182.89,81390502\n",
+            "1/6/2014 16:00:00,\"183.47,183.56,182.08,182.36,108028139\n",
+            "1/7/2014 16:00:00,183.09,183.79,182.95,183.48,86144169\n",
+        );
+        let (data, report) = parse_historical(&content, &LoadOptions::default()).unwrap();
+        assert_eq!(data.iter().map(|bar| bar.get_date().to_string()).collect::<Vec<String>>(), vec!["1/2/2014 16:00:00", "1/7/2014 16:00:00"]);
+        assert_eq!(report.get_skipped(), &vec![
+            RowIssue::new(3, "Unexpected character '0' after a closing quote on line 3"),
+            RowIssue::new(4, "Unterminated quoted field starting on line 4"),
+        ]);
+
+        let error = parse_historical(&content, &LoadOptions::new(BadRows::Error, OhlcCheck::Flag)).unwrap_err();
+        assert_eq!(error, LoadError::InvalidRow { line: 3, reason: "Unexpected character '0' after a closing quote on line 3".to_string() });
+    }
+
+    #[test]
+    fn test_read_file_not_found() {
+        let error = read_file(Path::new("./test/data/missing.csv")).unwrap_err();
+        assert_eq!(error, LoadError::NotFound { path: "./test/data/missing.csv".to_string() });
+        assert_eq!(error.to_string(), "Could not find file ./test/data/missing.csv.");
+    }
+
+    #[test]
+    fn test_parse_fundamentals_asset_layout() {
+        let content = "name,type,marketcap,high52,low52,pe,eps,summary\nApple Inc.,EQUITY,2427490926592,182.94,125.94,23.94175,6.56,\"Apple Inc. designs, manufactures, and markets smartphones.\"\n";
+        let f = parse_fundamentals(content).unwrap();
+        assert_eq!(f.get_name(), Some("Apple Inc.".to_string()));
+        assert_eq!(f.get_security_type(), Some("EQUITY".to_string()));
+        assert_eq!(f.get_market_cap(), Some(2427490926592));
+        assert_eq!(f.get_high52(), Some(182.94));
+        assert_eq!(f.get_low52(), Some(125.94));
+        assert_eq!(f.get_pe_ratio(), Some(23.94175));
+        assert_eq!(f.get_eps(), Some(6.56));
+        assert_eq!(f.get_summary(), Some("Apple Inc. designs, manufactures, and markets smartphones.".to_string()));
+    }
+
+    #[test]
+    fn test_parse_fundamentals_test_layout() {
+        let f = parse_fundamentals("type,marketcap,high52,low52,pe,eps\netf,369696299325,479.98,380.54,,").unwrap();
+        assert_eq!(f.get_name(), None);
+        assert_eq!(f.get_summary(), None);
+        assert_eq!(f.get_security_type(), Some("etf".to_string()));
+        assert_eq!(f.get_market_cap(), Some(369696299325));
+        assert_eq!(f.get_pe_ratio(), Some(0.0));
+        assert_eq!(f.get_eps(), Some(0.0));
+    }
+
+    #[test]
+    fn test_parse_fundamentals_errors() {
+        assert_eq!(parse_fundamentals("name,type\n").unwrap_err(), LoadError::Empty);
+        assert_eq!(
+            parse_fundamentals("name,pe\nApple,abc\n").unwrap_err(),
+            LoadError::InvalidRow { line: 2, reason: "invalid pe \"abc\"".to_string() },
+        );
+        assert_eq!(
+            parse_fundamentals("name,marketcap\nApple,1.5\n").unwrap_err(),
+            LoadError::InvalidRow { line: 2, reason: "invalid marketcap \"1.5\"".to_string() },
+        );
+    }
+}
@@ -0,0 +1,532 @@
+use std::{collections::HashMap, fmt, future::Future, path::{Path, PathBuf}};
+
+use crate::stock::cache;
+use crate::stock::datetime::DateTime;
+use crate::stock::historical::{self, HistoricalData};
+use crate::stock::iex::{self, ChartRange, IexError};
+use crate::stock::load::{self, Fundamentals, LoadError, LoadOptions};
+
+/// Bar size of fetched historical data. Sources store daily bars, which
+/// are aggregated for the longer intervals. See `resample`.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum Interval {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// Dates from `start` to `end`, both inclusive.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct DateRange {
+    start: DateTime,
+    end: DateTime,
+}
+
+#[allow(dead_code)]
+impl DateRange {
+    pub fn new(start: DateTime, end: DateTime) -> Self {
+        return Self { start: start, end: end };
+    }
+
+    /// Dates from `start` up to now.
+    pub fn since(start: DateTime) -> Self {
+        return Self::new(start, DateTime::now());
+    }
+
+    /// Every date from `start` on, including bars dated later today.
+    pub fn starting(start: DateTime) -> Self {
+        return Self::new(start, DateTime::from_ymd_hms(9999, 12, 31, 23, 59, 59));
+    }
+
+    /// Every date a source can have.
+    pub fn all() -> Self {
+        return Self::starting(DateTime::from_ymd_hms(1, 1, 1, 0, 0, 0));
+    }
+
+    pub fn get_start(&self) -> &DateTime { return &self.start; }
+    pub fn get_end(&self) -> &DateTime { return &self.end; }
+
+    pub fn contains(&self, date: &DateTime) -> bool {
+        return &self.start <= date && date <= &self.end;
+    }
+}
+
+/// Error returned by a `DataProvider`.
+///
+/// - `NotFound`: the source has no data for the ticker, e.g. its file is
+///   missing or the API answered `404`. Every provider reports an unknown
+///   ticker this way.
+/// - `Truncated`: the source has bars for the ticker older than it can
+///   return, so part of the requested range would be missing. `earliest`
+///   is the oldest bar it can return.
+/// - `Load`: a local file could not be loaded
+/// - `Iex`: a request to the IEX API failed
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum ProviderError {
+    NotFound {
+        ticker: String,
+    },
+    Truncated {
+        ticker: String,
+        earliest: DateTime,
+    },
+    Load(LoadError),
+    Iex(IexError),
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            ProviderError::NotFound { ticker } => write!(f, "No data for ticker {}.", ticker),
+            ProviderError::Truncated { ticker, earliest } => {
+                write!(f, "History of ticker {} is only available from {}.", ticker, earliest.to_string())
+            },
+            ProviderError::Load(error) => write!(f, "{}", error),
+            ProviderError::Iex(error) => write!(f, "{}", error),
+        };
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+impl From<LoadError> for ProviderError {
+    fn from(error: LoadError) -> Self {
+        return ProviderError::Load(error);
+    }
+}
+
+impl From<IexError> for ProviderError {
+    fn from(error: IexError) -> Self {
+        return ProviderError::Iex(error);
+    }
+}
+
+impl ProviderError {
+    /// Turns a missing file or an IEX `404` into `NotFound` for `ticker`.
+    fn for_ticker(self, ticker: &str) -> Self {
+        return match self {
+            ProviderError::Load(LoadError::NotFound { .. }) | ProviderError::Iex(IexError::Status { status: 404, .. }) => {
+                ProviderError::NotFound { ticker: ticker.to_string() }
+            },
+            error => error,
+        };
+    }
+}
+
+/// A source of market data, e.g. local CSV files, the binary cache or an
+/// HTTP API. `Stock::load_from` and `backtest::run_from` accept any
+/// provider, and tests can use a `MemoryProvider`.
+///
+/// Implementations may use `async fn` for both methods.
+///
+/// ### Example
+/// ```
+/// let provider = CsvProvider::new(Path::new("./src/assets"));
+/// let range = DateRange::new(DateTime::new("1/1/2021"), DateTime::new("12/31/2021"));
+/// let bars = provider.fetch_history("aapl", &range, Interval::Weekly).await?;
+/// let fundamentals = provider.fetch_fundamentals("aapl").await?;
+/// ```
+pub trait DataProvider {
+    /// Fetches the bars of `ticker` dated within `range`, oldest first,
+    /// with one bar per `interval`.
+    fn fetch_history(&self, ticker: &str, range: &DateRange, interval: Interval) -> impl Future<Output = Result<Vec<HistoricalData>, ProviderError>> + Send;
+
+    /// Fetches the fundamentals of `ticker`. Fields the source does not
+    /// have are `None`.
+    fn fetch_fundamentals(&self, ticker: &str) -> impl Future<Output = Result<Fundamentals, ProviderError>> + Send;
+}
+
+/// Aggregates bars sorted by date into one bar per `interval`. A weekly bar
+/// covers an ISO week and a monthly bar a calendar month. Each aggregated
+/// bar takes the open of its first bar, the close and date of its last
+/// bar, the highest high, the lowest low and the total volume.
+///
+/// ### Example
+/// ```
+/// let weekly = provider::resample(s.get_historical_data(), Interval::Weekly);
+/// ```
+pub fn resample(data: &[HistoricalData], interval: Interval) -> Vec<HistoricalData> {
+    if interval == Interval::Daily {
+        return data.to_vec();
+    }
+    let period = |date: &DateTime| -> (u16, u8) {
+        return match interval {
+            Interval::Monthly => (date.get_year(), date.get_month()),
+            _ => date.iso_week(),
+        };
+    };
+
+    let mut bars: Vec<HistoricalData> = Vec::new();
+    let mut start = 0;
+    while start < data.len() {
+        let key = period(data[start].get_date());
+        let mut end = start + 1;
+        while end < data.len() && period(data[end].get_date()) == key {
+            end += 1;
+        }
+        let group = &data[start..end];
+        let last = &group[group.len() - 1];
+        bars.push(HistoricalData::from_date(
+            last.get_date().clone(),
+            group[0].get_open(),
+            group.iter().map(|bar| bar.get_high()).fold(f64::MIN, f64::max),
+            group.iter().map(|bar| bar.get_low()).fold(f64::MAX, f64::min),
+            last.get_close(),
+            group.iter().map(|bar| bar.get_volume()).sum(),
+        ));
+        start = end;
+    }
+    return bars;
+}
+
+/// Runs blocking file reads and parsing on tokio's blocking thread pool, so
+/// that they do not stall the async executor.
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    return match tokio::task::spawn_blocking(f).await {
+        Ok(value) => value,
+        Err(error) => std::panic::resume_unwind(error.into_panic()),
+    };
+}
+
+/// Sorts and dedups loaded bars, then keeps those within `range` and
+/// resamples them to `interval`.
+fn select(mut data: Vec<HistoricalData>, range: &DateRange, interval: Interval) -> Vec<HistoricalData> {
+    historical::sort_by_date(&mut data);
+    historical::dedup_by_date(&mut data);
+    return resample(historical::query(&data, range.get_start(), range.get_end()), interval);
+}
+
+/// Reads `<historical>/<ticker>.csv` and `<contemporary>/<ticker>.csv`, the
+/// layout of the bundled `src/assets` directory. Tickers are lowercased to
+/// find their files. Files are read and parsed on tokio's blocking thread
+/// pool, so a tokio runtime is required.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct CsvProvider {
+    historical: PathBuf,
+    contemporary: PathBuf,
+    options: LoadOptions,
+}
+
+#[allow(dead_code)]
+impl CsvProvider {
+    /// Reads `<directory>/historical` and `<directory>/contemporary` with
+    /// the default `LoadOptions`.
+    pub fn new(directory: &Path) -> Self {
+        return Self::with_directories(&directory.join("historical"), &directory.join("contemporary"), &LoadOptions::default());
+    }
+
+    /// Reads the given directories. The options' cache directory is
+    /// ignored, see `CacheProvider`.
+    pub fn with_directories(historical: &Path, contemporary: &Path, options: &LoadOptions) -> Self {
+        return Self {
+            historical: historical.to_path_buf(),
+            contemporary: contemporary.to_path_buf(),
+            options: options.clone(),
+        };
+    }
+
+    pub fn get_historical(&self) -> &Path { return &self.historical; }
+    pub fn get_contemporary(&self) -> &Path { return &self.contemporary; }
+    pub fn get_options(&self) -> &LoadOptions { return &self.options; }
+
+    pub fn historical_file(&self, ticker: &str) -> PathBuf {
+        return self.historical.join(format!("{}.csv", ticker.to_lowercase()));
+    }
+
+    pub fn contemporary_file(&self, ticker: &str) -> PathBuf {
+        return self.contemporary.join(format!("{}.csv", ticker.to_lowercase()));
+    }
+}
+
+impl DataProvider for CsvProvider {
+    async fn fetch_history(&self, ticker: &str, range: &DateRange, interval: Interval) -> Result<Vec<HistoricalData>, ProviderError> {
+        let (location, options) = (self.historical_file(ticker), self.options.clone());
+        let loaded = blocking(move || load::parse_historical(&load::read_file(&location)?, &options)).await;
+        let (data, _) = loaded.map_err(|error| ProviderError::from(error).for_ticker(ticker))?;
+        return Ok(select(data, range, interval));
+    }
+
+    async fn fetch_fundamentals(&self, ticker: &str) -> Result<Fundamentals, ProviderError> {
+        let location = self.contemporary_file(ticker);
+        let loaded = blocking(move || load::parse_fundamentals(&load::read_file(&location)?)).await;
+        return loaded.map_err(|error| ProviderError::from(error).for_ticker(ticker));
+    }
+}
+
+/// Reads historical data through the binary cache in `directory`, which
+/// is rebuilt from the CSV files when they change. See
+/// `cache::load_historical`. Fundamentals are read from the CSV files.
+/// Like `CsvProvider`, files are read on tokio's blocking thread pool.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct CacheProvider {
+    csv: CsvProvider,
+    directory: PathBuf,
+}
+
+#[allow(dead_code)]
+impl CacheProvider {
+    pub fn new(csv: CsvProvider, directory: &Path) -> Self {
+        return Self { csv: csv, directory: directory.to_path_buf() };
+    }
+
+    pub fn get_csv(&self) -> &CsvProvider { return &self.csv; }
+    pub fn get_directory(&self) -> &Path { return &self.directory; }
+}
+
+impl DataProvider for CacheProvider {
+    async fn fetch_history(&self, ticker: &str, range: &DateRange, interval: Interval) -> Result<Vec<HistoricalData>, ProviderError> {
+        let (location, directory, options) = (self.csv.historical_file(ticker), self.directory.clone(), self.csv.get_options().clone());
+        let loaded = blocking(move || cache::load_historical(&location, &directory, &options)).await;
+        let (data, _) = loaded.map_err(|error| ProviderError::from(error).for_ticker(ticker))?;
+        return Ok(select(data, range, interval));
+    }
+
+    async fn fetch_fundamentals(&self, ticker: &str) -> Result<Fundamentals, ProviderError> {
+        return self.csv.fetch_fundamentals(ticker).await;
+    }
+}
+
+/// Fetches data from the IEX Cloud API, or any server with the same
+/// endpoints at the client's base URL. History is requested with the
+/// shortest `ChartRange` that covers the start of the range.
+///
+/// The chart endpoint returns at most `iex::MAX_HISTORY_YEARS` of bars. A
+/// range starting before that fails with `ProviderError::Truncated` when
+/// the ticker's bars reach back to the limit, since older bars would be
+/// missing. Tickers listed more recently load in full.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct IexProvider {
+    client: iex::Client,
+}
+
+#[allow(dead_code)]
+impl IexProvider {
+    pub fn new(client: iex::Client) -> Self {
+        return Self { client: client };
+    }
+
+    pub fn get_client(&self) -> &iex::Client { return &self.client; }
+}
+
+impl DataProvider for IexProvider {
+    async fn fetch_history(&self, ticker: &str, range: &DateRange, interval: Interval) -> Result<Vec<HistoricalData>, ProviderError> {
+        let today = DateTime::now();
+        let chart_range = ChartRange::covering(range.get_start(), &today);
+        let data = self.client.chart(ticker, chart_range).await
+            .map_err(|error| ProviderError::from(error).for_ticker(ticker))?;
+
+        // Bars starting within a week of the limit were cut off by it
+        let limit = today.start_of_day().sub_years(iex::MAX_HISTORY_YEARS);
+        if chart_range == ChartRange::Max && range.get_start() < &limit {
+            if let Some(first) = data.iter().map(|bar| bar.get_date()).min() {
+                if first.days_since(&limit) <= 7 {
+                    return Err(ProviderError::Truncated { ticker: ticker.to_string(), earliest: first.clone() });
+                }
+            }
+        }
+        return Ok(select(data, range, interval));
+    }
+
+    async fn fetch_fundamentals(&self, ticker: &str) -> Result<Fundamentals, ProviderError> {
+        return self.client.fundamentals(ticker).await
+            .map_err(|error| ProviderError::from(error).for_ticker(ticker));
+    }
+}
+
+/// Serves data held in memory, e.g. as a fake source in tests. Tickers
+/// are matched ignoring case.
+///
+/// ### Example
+/// ```
+/// let mut provider = MemoryProvider::new();
+/// provider.insert_history("aapl", vec![HistoricalData::new("1/3/2020 16:00:00", 10.0, 11.0, 9.0, 10.5, 100)]);
+/// let mut s = Stock::new("aapl");
+/// s.load_from(&provider, &DateRange::all(), Interval::Daily).await?;
+/// ```
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Default)]
+pub struct MemoryProvider {
+    history: HashMap<String, Vec<HistoricalData>>,
+    fundamentals: HashMap<String, Fundamentals>,
+}
+
+#[allow(dead_code)]
+impl MemoryProvider {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /// Adds bars for `ticker`. A bar with the same timestamp as an
+    /// existing bar replaces it.
+    pub fn insert_history(&mut self, ticker: &str, data: Vec<HistoricalData>) {
+        let history = self.history.entry(ticker.to_lowercase()).or_default();
+        history.extend(data);
+        historical::sort_by_date(history);
+        historical::dedup_by_date(history);
+    }
+
+    pub fn insert_fundamentals(&mut self, ticker: &str, fundamentals: Fundamentals) {
+        self.fundamentals.insert(ticker.to_lowercase(), fundamentals);
+    }
+}
+
+impl DataProvider for MemoryProvider {
+    async fn fetch_history(&self, ticker: &str, range: &DateRange, interval: Interval) -> Result<Vec<HistoricalData>, ProviderError> {
+        return match self.history.get(&ticker.to_lowercase()) {
+            Some(data) => Ok(resample(historical::query(data, range.get_start(), range.get_end()), interval)),
+            None => Err(ProviderError::NotFound { ticker: ticker.to_string() }),
+        };
+    }
+
+    async fn fetch_fundamentals(&self, ticker: &str) -> Result<Fundamentals, ProviderError> {
+        return match self.fundamentals.get(&ticker.to_lowercase()) {
+            Some(fundamentals) => Ok(fundamentals.clone()),
+            None => Err(ProviderError::NotFound { ticker: ticker.to_string() }),
+        };
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, remove_dir_all};
+    use crate::stock::Stock;
+    use crate::stock::iex::mock::MockServer;
+
+    fn week_of_bars() -> Vec<HistoricalData> {
+        return vec![
+            HistoricalData::new("12/30/2019 16:00:00", 9.0, 10.0, 8.0, 9.5, 50),
+            HistoricalData::new("1/2/2020 16:00:00", 10.0, 11.0, 9.0, 10.5, 100),
+            HistoricalData::new("1/3/2020 16:00:00", 10.5, 12.0, 10.0, 11.5, 200),
+            HistoricalData::new("1/6/2020 16:00:00", 11.5, 13.0, 11.0, 12.5, 300),
+            HistoricalData::new("1/7/2020 16:00:00", 12.5, 14.0, 12.0, 13.5, 400),
+        ];
+    }
+
+    #[test]
+    fn test_resample() {
+        let weekly = resample(&week_of_bars(), Interval::Weekly);
+        assert_eq!(weekly.len(), 2);
+        assert_eq!(weekly[0].to_string(), HistoricalData::new("1/3/2020 16:00:00", 9.0, 12.0, 8.0, 11.5, 350).to_string());
+        assert_eq!(weekly[1].to_string(), HistoricalData::new("1/7/2020 16:00:00", 11.5, 14.0, 11.0, 13.5, 700).to_string());
+
+        let monthly = resample(&week_of_bars(), Interval::Monthly);
+        assert_eq!(monthly.len(), 2);
+        assert_eq!(monthly[0].get_close(), 9.5);
+        assert_eq!(monthly[1].to_string(), HistoricalData::new("1/7/2020 16:00:00", 10.0, 14.0, 9.0, 13.5, 1000).to_string());
+        assert_eq!(resample(&week_of_bars(), Interval::Daily).len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_memory_provider() {
+        let mut provider = MemoryProvider::new();
+        provider.insert_history("KO", week_of_bars());
+        let mut fundamentals = Fundamentals::new();
+        fundamentals.set_name(Some("Coca-Cola".to_string()));
+        provider.insert_fundamentals("ko", fundamentals);
+
+        let range = DateRange::new(DateTime::new("1/1/2020"), DateTime::new("1/6/2020 23:59:59"));
+        let bars = provider.fetch_history("ko", &range, Interval::Daily).await.unwrap();
+        assert_eq!(bars.iter().map(|bar| bar.get_volume()).collect::<Vec<u64>>(), vec![100, 200, 300]);
+
+        let mut s = Stock::new("ko");
+        s.load_from(&provider, &DateRange::all(), Interval::Weekly).await.unwrap();
+        assert_eq!(s.get_name(), "Coca-Cola");
+        assert_eq!(s.get_historical_data().len(), 2);
+
+        assert_eq!(
+            provider.fetch_history("pep", &range, Interval::Daily).await.unwrap_err(),
+            ProviderError::NotFound { ticker: "pep".to_string() }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_csv_provider() {
+        let provider = CsvProvider::new(Path::new("./src/assets"));
+        let range = DateRange::new(DateTime::new("6/1/2022"), DateTime::new("6/8/2022 23:59:59"));
+        let bars = provider.fetch_history("AAPL", &range, Interval::Daily).await.unwrap();
+        assert_eq!(bars.len(), 6);
+        assert_eq!(bars[5].get_date().to_string(), "6/8/2022 16:00:00");
+
+        let fundamentals = provider.fetch_fundamentals("aapl").await.unwrap();
+        assert_eq!(fundamentals.get_name(), Some("Apple Inc.".to_string()));
+
+        let not_found = ProviderError::NotFound { ticker: "zzzz".to_string() };
+        assert_eq!(provider.fetch_history("zzzz", &range, Interval::Daily).await.unwrap_err(), not_found);
+        assert_eq!(provider.fetch_fundamentals("zzzz").await.unwrap_err(), not_found);
+    }
+
+    #[tokio::test]
+    async fn test_cache_provider() {
+        let root = std::env::temp_dir().join(format!("stockwatcher-provider-cache-{}", std::process::id()));
+        let _ = remove_dir_all(&root);
+        create_dir_all(&root).unwrap();
+
+        let provider = CacheProvider::new(CsvProvider::new(Path::new("./src/assets")), &root);
+        let range = DateRange::new(DateTime::new("1/1/2021"), DateTime::new("12/31/2021"));
+        let first = provider.fetch_history("ko", &range, Interval::Monthly).await.unwrap();
+        assert_eq!(root.join("ko.csv.bin").is_file(), true);
+        let second = provider.fetch_history("ko", &range, Interval::Monthly).await.unwrap();
+        assert_eq!(first.len(), 12);
+        assert_eq!(
+            first.iter().map(|bar| bar.to_string()).collect::<Vec<String>>(),
+            second.iter().map(|bar| bar.to_string()).collect::<Vec<String>>()
+        );
+        assert_eq!(
+            provider.fetch_history("zzzz", &range, Interval::Monthly).await.unwrap_err(),
+            ProviderError::NotFound { ticker: "zzzz".to_string() }
+        );
+
+        remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_iex_provider() {
+        let server = MockServer::new()
+            .chart_fixture("aapl", "test/data/iex/aapl_chart_5d.json")
+            .fixture("/stock/aapl/quote", "test/data/iex/aapl_quote.json")
+            .fixture("/stock/aapl/company", "test/data/iex/aapl_company.json")
+            .fixture("/stock/aapl/stats", "test/data/iex/aapl_stats.json")
+            .start().await;
+        let provider = IexProvider::new(iex::Client::with_base_url(&server.url(), "Tsk_test"));
+
+        let range = DateRange::new(DateTime::new("1/1/2000"), DateTime::new("6/7/2022 23:59:59"));
+        let mut s = Stock::new("aapl");
+        s.load_from(&provider, &range, Interval::Daily).await.unwrap();
+        assert_eq!(s.get_market_cap(), 2394788219520);
+        assert_eq!(s.get_price_series().get_closes(), &[151.21, 145.38, 146.14, 148.71]);
+
+        let not_found = ProviderError::NotFound { ticker: "zzzz".to_string() };
+        assert_eq!(provider.fetch_history("zzzz", &range, Interval::Daily).await.unwrap_err(), not_found);
+        assert_eq!(provider.fetch_fundamentals("zzzz").await.unwrap_err(), not_found);
+    }
+
+    #[tokio::test]
+    async fn test_iex_provider_truncated() {
+        let first = DateTime::now().start_of_day().sub_years(iex::MAX_HISTORY_YEARS).add_days(1);
+        let bar = |date: &DateTime| format!("{{\"date\": \"{}\", \"open\": 1, \"high\": 1, \"low\": 1, \"close\": 1, \"volume\": 1}}", date.format("%Y-%m-%d"));
+        let chart = format!("[{}, {}]", bar(&first), bar(&first.add_days(1)));
+        let server = MockServer::new().route("/stock/ko/chart/max", 200, &chart).start().await;
+        let provider = IexProvider::new(iex::Client::with_base_url(&server.url(), "Tsk_test"));
+
+        // Bars reaching back to the limit mean older ones are missing
+        let error = provider.fetch_history("ko", &DateRange::all(), Interval::Daily).await.unwrap_err();
+        assert_eq!(error, ProviderError::Truncated { ticker: "ko".to_string(), earliest: first.start_of_day().add_seconds(16 * 3600) });
+
+        // A range inside the limit loads as usual
+        let bars = provider.fetch_history("ko", &DateRange::starting(first.clone()), Interval::Daily).await.unwrap();
+        assert_eq!(bars.len(), 2);
+    }
+}
@@ -0,0 +1,245 @@
+use std::ops::Range;
+
+use crate::stock::datetime::DateTime;
+use crate::stock::historical::HistoricalData;
+
+/// Historical data stored as aligned columns, so that indicators can run on
+/// `&[f64]` views of a single field without copying.
+///
+/// Dates must be in ascending order, which `Stock` guarantees for its
+/// historical data.
+///
+/// ### Example
+/// ```
+/// let series = s.get_price_series();
+/// let year = series.slice(&DateTime::new("1/1/2021"), &DateTime::new("12/31/2021"));
+/// let smas = sma::run(year.get_closes(), 50);
+/// let oscs = stochastic_oscillator::run(year.get_closes(), year.get_lows(), year.get_highs());
+/// ```
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct PriceSeries {
+    dates: Vec<DateTime>,
+    opens: Vec<f64>,
+    highs: Vec<f64>,
+    lows: Vec<f64>,
+    closes: Vec<f64>,
+    volumes: Vec<u64>,
+}
+
+#[allow(dead_code)]
+impl PriceSeries {
+    pub fn new() -> Self {
+        return Self::with_capacity(0);
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        return Self {
+            dates: Vec::with_capacity(capacity),
+            opens: Vec::with_capacity(capacity),
+            highs: Vec::with_capacity(capacity),
+            lows: Vec::with_capacity(capacity),
+            closes: Vec::with_capacity(capacity),
+            volumes: Vec::with_capacity(capacity),
+        };
+    }
+
+    /// Builds a series from historical data sorted by date.
+    pub fn from_historical(data: &[HistoricalData]) -> Self {
+        let mut series = Self::with_capacity(data.len());
+        for bar in data {
+            series.push(bar);
+        }
+        return series;
+    }
+
+    /// Appends a bar. It must not be dated before the last bar.
+    pub fn push(&mut self, bar: &HistoricalData) {
+        self.dates.push(bar.get_date().clone());
+        self.opens.push(bar.get_open());
+        self.highs.push(bar.get_high());
+        self.lows.push(bar.get_low());
+        self.closes.push(bar.get_close());
+        self.volumes.push(bar.get_volume());
+    }
+
+    pub fn get_dates(&self) -> &[DateTime] { return &self.dates; }
+    pub fn get_opens(&self) -> &[f64] { return &self.opens; }
+    pub fn get_highs(&self) -> &[f64] { return &self.highs; }
+    pub fn get_lows(&self) -> &[f64] { return &self.lows; }
+    pub fn get_closes(&self) -> &[f64] { return &self.closes; }
+    pub fn get_volumes(&self) -> &[u64] { return &self.volumes; }
+
+    pub fn len(&self) -> usize {
+        return self.dates.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.dates.is_empty();
+    }
+
+    /// Returns the bar at `index`, rebuilt from the columns.
+    pub fn get(&self, index: usize) -> Option<HistoricalData> {
+        return self.as_slice().get(index);
+    }
+
+    /// Returns a view of the whole series.
+    pub fn as_slice(&self) -> SeriesSlice<'_> {
+        return SeriesSlice {
+            dates: &self.dates,
+            opens: &self.opens,
+            highs: &self.highs,
+            lows: &self.lows,
+            closes: &self.closes,
+            volumes: &self.volumes,
+        };
+    }
+
+    /// Returns a view of the bars dated from `start` to `end`, both
+    /// inclusive. The bounds are found by binary search.
+    pub fn slice(&self, start: &DateTime, end: &DateTime) -> SeriesSlice<'_> {
+        return self.as_slice().slice(start, end);
+    }
+}
+
+impl Default for PriceSeries {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+/// A borrowed view of a range of a `PriceSeries`. Every column is a
+/// subslice of the series' column, so creating and slicing views never
+/// copies data.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq)]
+pub struct SeriesSlice<'a> {
+    dates: &'a [DateTime],
+    opens: &'a [f64],
+    highs: &'a [f64],
+    lows: &'a [f64],
+    closes: &'a [f64],
+    volumes: &'a [u64],
+}
+
+#[allow(dead_code)]
+impl<'a> SeriesSlice<'a> {
+    pub fn get_dates(&self) -> &'a [DateTime] { return self.dates; }
+    pub fn get_opens(&self) -> &'a [f64] { return self.opens; }
+    pub fn get_highs(&self) -> &'a [f64] { return self.highs; }
+    pub fn get_lows(&self) -> &'a [f64] { return self.lows; }
+    pub fn get_closes(&self) -> &'a [f64] { return self.closes; }
+    pub fn get_volumes(&self) -> &'a [u64] { return self.volumes; }
+
+    pub fn len(&self) -> usize {
+        return self.dates.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.dates.is_empty();
+    }
+
+    /// Returns the bar at `index`, rebuilt from the columns.
+    pub fn get(&self, index: usize) -> Option<HistoricalData> {
+        if index >= self.len() {
+            return None;
+        }
+        return Some(HistoricalData::from_date(
+            self.dates[index].clone(),
+            self.opens[index],
+            self.highs[index],
+            self.lows[index],
+            self.closes[index],
+            self.volumes[index],
+        ));
+    }
+
+    /// Returns the indices of the bars dated from `start` to `end`, both
+    /// inclusive. Empty if `end` is before `start`.
+    pub fn range(&self, start: &DateTime, end: &DateTime) -> Range<usize> {
+        let from = self.dates.partition_point(|d| d < start);
+        let to = self.dates.partition_point(|d| d <= end);
+        return from..to.max(from);
+    }
+
+    /// Returns a view of the bars dated from `start` to `end`, both
+    /// inclusive.
+    pub fn slice(&self, start: &DateTime, end: &DateTime) -> SeriesSlice<'a> {
+        return self.subslice(self.range(start, end));
+    }
+
+    /// Returns a view of the bars at `range`.
+    ///
+    /// Panics if `range` is out of bounds.
+    pub fn subslice(&self, range: Range<usize>) -> SeriesSlice<'a> {
+        return SeriesSlice {
+            dates: &self.dates[range.clone()],
+            opens: &self.opens[range.clone()],
+            highs: &self.highs[range.clone()],
+            lows: &self.lows[range.clone()],
+            closes: &self.closes[range.clone()],
+            volumes: &self.volumes[range],
+        };
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stock::ta::sma;
+
+    fn build_series() -> PriceSeries {
+        return PriceSeries::from_historical(&[
+            HistoricalData::new("1/2/2020 16:00:00", 10.0, 11.0, 9.0, 10.5, 100),
+            HistoricalData::new("1/3/2020 16:00:00", 10.5, 12.0, 10.0, 11.5, 200),
+            HistoricalData::new("1/6/2020 16:00:00", 11.5, 13.0, 11.0, 12.5, 300),
+            HistoricalData::new("1/7/2020 16:00:00", 12.5, 14.0, 12.0, 13.5, 400),
+            HistoricalData::new("1/8/2020 16:00:00", 13.5, 15.0, 13.0, 14.5, 500),
+        ]);
+    }
+
+    #[test]
+    fn test_from_historical() {
+        let series = build_series();
+        assert_eq!(series.len(), 5);
+        assert_eq!(series.get_opens(), &[10.0, 10.5, 11.5, 12.5, 13.5]);
+        assert_eq!(series.get_highs(), &[11.0, 12.0, 13.0, 14.0, 15.0]);
+        assert_eq!(series.get_lows(), &[9.0, 10.0, 11.0, 12.0, 13.0]);
+        assert_eq!(series.get_closes(), &[10.5, 11.5, 12.5, 13.5, 14.5]);
+        assert_eq!(series.get_volumes(), &[100, 200, 300, 400, 500]);
+        assert_eq!(series.get(1).unwrap().to_string(), HistoricalData::new("1/3/2020 16:00:00", 10.5, 12.0, 10.0, 11.5, 200).to_string());
+        assert_eq!(series.get(5).is_none(), true);
+        assert_eq!(PriceSeries::new().is_empty(), true);
+    }
+
+    #[test]
+    fn test_slice() {
+        let series = build_series();
+        // Bounds are inclusive and need not fall on a bar
+        let slice = series.slice(&DateTime::new("1/3/2020 00:00:00"), &DateTime::new("1/7/2020 16:00:00"));
+        assert_eq!(slice.len(), 3);
+        assert_eq!(slice.get_closes(), &[11.5, 12.5, 13.5]);
+        assert_eq!(slice.get_dates()[0].to_string(), "1/3/2020 16:00:00");
+        // Views borrow the series' columns
+        assert_eq!(slice.get_closes().as_ptr(), series.get_closes()[1..].as_ptr());
+
+        let nested = slice.slice(&DateTime::new("1/6/2020 00:00:00"), &DateTime::new("1/31/2020 00:00:00"));
+        assert_eq!(nested.get_volumes(), &[300, 400]);
+        assert_eq!(nested.get(0).unwrap().get_open(), 11.5);
+
+        assert_eq!(series.slice(&DateTime::new("1/4/2020"), &DateTime::new("1/5/2020")).is_empty(), true);
+        assert_eq!(series.slice(&DateTime::new("1/8/2020"), &DateTime::new("1/2/2020")).is_empty(), true);
+        assert_eq!(series.as_slice().range(&DateTime::new("1/1/2019"), &DateTime::new("1/1/2021")), 0..5);
+        assert_eq!(series.as_slice().subslice(3..5).get_closes(), &[13.5, 14.5]);
+    }
+
+    #[test]
+    fn test_indicator_on_slice() {
+        let series = build_series();
+        let slice = series.slice(&DateTime::new("1/3/2020"), &DateTime::new("1/9/2020"));
+        assert_eq!(sma::run(slice.get_closes(), 2), vec![12.0, 13.0, 14.0]);
+    }
+}
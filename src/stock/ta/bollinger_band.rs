@@ -1,6 +1,8 @@
-use crate::stock::ta::sma;
+use std::collections::VecDeque;
 
-/// Calculate the bollinger band based on a `Vec<f32>` of price data.
+use crate::stock::ta::TaError;
+
+/// Calculate the bollinger band based on a `&[f64]` of price data.
 /// 
 /// ### Definition
 /// An indicator -- bounded between [0-100] -- comprised of the most recent
@@ -18,6 +20,9 @@ use crate::stock::ta::sma;
 /// - `20sma`: 20-day simple moving average of closing price
 /// - `std_dev(20close)`: standard deviation of the last 20 closing prices
 /// 
+/// The standard deviation is calculated over the same rolling window as the
+/// SMA. Use `run_bands` for %B and bandwidth.
+/// 
 /// ### Usage
 /// A security can typically be considered overbought when its prices moves to
 /// the upper bollinger band or oversold when its price moves to the lower
@@ -29,62 +34,266 @@ use crate::stock::ta::sma;
 /// of a major event (ex. earnings).
 /// 
 /// # Arguments
-/// * `prices` - `Vec<f32>` containing prices for a period of time
+/// * `prices` - `&[f64]` containing prices for a period of time
 /// 
 /// ### Example
 /// ```
-/// bollinger_band::run(prices);
-/// bollinger_band::run(prices);
+/// bollinger_band::run(series.get_closes());
+/// bollinger_band::run(series.get_closes());
 /// ```
 /// 
 /// #### Resources
 /// - https://www.investopedia.com/terms/b/bollingerbands.asp
-pub fn run(prices: Vec<f32>) -> Vec<(f32, f32, f32)> {
-    const PERIOD: usize = 20;
-    if prices.len() < PERIOD { panic!("Not enough entries to calculate bollinger bands. Received {}, but required {}.", prices.len(), PERIOD); }
-    let mut bbs: Vec<(f32, f32, f32)> = Vec::new();
-
-    let mut i = PERIOD;
-    while {
-        let ma = sma::run(prices[i-PERIOD..i].to_vec(), PERIOD);
-        let ma = match ma.get(0) {
-            Some(&v) => v,
-            None => panic!("Error calculating SMA.")
+#[allow(dead_code)]
+pub fn run(prices: &[f64]) -> Vec<(f64, f64, f64)> {
+    return run_with(prices, &BollingerParams::default());
+}
+
+/// Standard deviation used for the width of the bands.
+/// 
+/// - `Population`: divides the squared deviations by `n` (default)
+/// - `Sample`: divides the squared deviations by `n-1`
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq)]
+#[allow(dead_code)]
+pub enum Deviation {
+    Population,
+    Sample,
+}
+
+/// Parameters used to calculate bollinger bands.
+/// 
+/// - `period`: number of periods used for the moving average (default 20)
+/// - `multiplier`: number of standard deviations between the middle and
+///   upper/lower bands (default 2.0)
+/// - `deviation`: population or sample standard deviation (default population)
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq)]
+pub struct BollingerParams {
+    period: usize,
+    multiplier: f64,
+    deviation: Deviation,
+}
+
+#[allow(dead_code)]
+impl BollingerParams {
+    pub fn new(period: usize, multiplier: f64) -> Self {
+        return Self { period: period, multiplier: multiplier, deviation: Deviation::Population };
+    }
+
+    pub fn get_period(&self) -> usize { return self.period; }
+    pub fn set_period(&mut self, period: usize) { self.period = period; }
+
+    pub fn get_multiplier(&self) -> f64 { return self.multiplier; }
+    pub fn set_multiplier(&mut self, multiplier: f64) { self.multiplier = multiplier; }
+
+    pub fn get_deviation(&self) -> Deviation { return self.deviation; }
+    pub fn set_deviation(&mut self, deviation: Deviation) { self.deviation = deviation; }
+}
+
+impl Default for BollingerParams {
+    fn default() -> Self {
+        return Self::new(20, 2.0);
+    }
+}
+
+/// A single bollinger band reading.
+/// 
+/// - `lower`, `middle`, `upper`: `bold`, `bolm` and `bolu`
+/// - `percent_b`: position of the price within the bands,
+///   `(price - bold) / (bolu - bold)`. `0.0` at the lower band and `1.0` at
+///   the upper band. `0.5` when the bands collapse onto the middle band.
+/// - `bandwidth`: width of the bands relative to the middle band,
+///   `(bolu - bold) / bolm`. Low values indicate a squeeze.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq)]
+pub struct BollingerBand {
+    lower: f64,
+    middle: f64,
+    upper: f64,
+    percent_b: f64,
+    bandwidth: f64,
+}
+
+#[allow(dead_code)]
+impl BollingerBand {
+    fn new(price: f64, middle: f64, std: f64, multiplier: f64) -> Self {
+        let lower = middle - (multiplier * std);
+        let upper = middle + (multiplier * std);
+        let width = upper - lower;
+        return Self {
+            lower: lower,
+            middle: middle,
+            upper: upper,
+            percent_b: if width > 0.0 { (price - lower) / width } else { 0.5 },
+            bandwidth: if middle != 0.0 { width / middle } else { 0.0 },
         };
-        let std = std_dev(&prices);
-        let bolu = ma + (2.0 * std);
-        let bold = ma - (2.0 * std);
-        bbs.push((bold, ma, bolu));
-        i += 1;
-        i <= prices.len()
-    } {}
-    // for i in PERIOD..prices.len() {
-    //     let ma = sma::run(prices[i-PERIOD..i].to_vec(), PERIOD);
-    //     let ma = match ma.get(0) {
-    //         Some(&v) => v,
-    //         None => panic!("Error calculating SMA.")
-    //     };
-    //     let std = std_dev(&prices);
-    //     let bolu = ma + (2.0 * std);
-    //     let bold = ma - (2.0 * std);
-    //     bbs.push((bold, ma, bolu));
-    // }
-    return bbs;
+    }
+
+    pub fn get_lower(&self) -> f64 { return self.lower; }
+    pub fn get_middle(&self) -> f64 { return self.middle; }
+    pub fn get_upper(&self) -> f64 { return self.upper; }
+    pub fn get_percent_b(&self) -> f64 { return self.percent_b; }
+    pub fn get_bandwidth(&self) -> f64 { return self.bandwidth; }
+
+    /// Returns the band as `(bold, bolm, bolu)`.
+    pub fn to_tuple(self) -> (f64, f64, f64) {
+        return (self.lower, self.middle, self.upper);
+    }
 }
 
+/// Calculate the bollinger band based on a `&[f64]` of price data using
+/// custom `BollingerParams`. See `run` for details.
+/// 
+/// ### Example
+/// ```
+/// bollinger_band::run_with(series.get_closes(), &BollingerParams::new(10, 1.5));
+/// ```
+#[allow(dead_code)]
+pub fn run_with(prices: &[f64], params: &BollingerParams) -> Vec<(f64, f64, f64)> {
+    return match try_run_with(prices, params) {
+        Ok(bbs) => bbs,
+        Err(error) => panic!("{}", error),
+    };
+}
 
-fn mean(data: &Vec<f32>) -> f32 {
-    let sum: f32 = data.iter().sum();
-    return sum / data.len() as f32;
+/// Calculate the bollinger band based on a `&[f64]` of price data. Returns
+/// a `TaError` instead of panicking. See `run` for details.
+#[allow(dead_code)]
+pub fn try_run(prices: &[f64]) -> Result<Vec<(f64, f64, f64)>, TaError> {
+    return try_run_with(prices, &BollingerParams::default());
+}
+
+/// Calculate the bollinger band based on a `&[f64]` of price data using
+/// custom `BollingerParams`. Returns a `TaError` instead of panicking.
+/// 
+/// ### Example
+/// ```
+/// match bollinger_band::try_run_with(series.get_closes(), &BollingerParams::new(10, 1.5)) {
+///     Ok(bbs) => println!("{:?}", bbs),
+///     Err(error) => println!("{}", error),
+/// };
+/// ```
+#[allow(dead_code)]
+pub fn try_run_with(prices: &[f64], params: &BollingerParams) -> Result<Vec<(f64, f64, f64)>, TaError> {
+    let bands = try_run_bands(prices, params)?;
+    return Ok(bands.iter().map(|b| b.to_tuple()).collect());
 }
 
-fn std_dev(data: &Vec<f32>) -> f32 {
-    let m = mean(data);
-    let variance = data.iter().map(|d| {
-        let diff = m - *d;
-        diff * diff
-    }).sum::<f32>() / data.len() as f32;
-    return variance.sqrt();
+/// Calculate the bollinger bands, %B and bandwidth based on a `&[f64]` of
+/// price data. See `run` for details.
+/// 
+/// ### Example
+/// ```
+/// let bands = bollinger_band::run_bands(series.get_closes(), &BollingerParams::default());
+/// let squeeze = bands.last().unwrap().get_bandwidth() < 0.05;
+/// ```
+#[allow(dead_code)]
+pub fn run_bands(prices: &[f64], params: &BollingerParams) -> Vec<BollingerBand> {
+    return match try_run_bands(prices, params) {
+        Ok(bands) => bands,
+        Err(error) => panic!("{}", error),
+    };
+}
+
+/// Calculate the bollinger bands, %B and bandwidth based on a `&[f64]` of
+/// price data. Returns a `TaError` instead of panicking.
+#[allow(dead_code)]
+pub fn try_run_bands(prices: &[f64], params: &BollingerParams) -> Result<Vec<BollingerBand>, TaError> {
+    let period = params.get_period();
+    let multiplier = params.get_multiplier();
+    if period == 0 { return Err(TaError::InvalidParameter { name: "period", reason: "must be greater than 0".to_string() }); }
+    if period < 2 && params.get_deviation() == Deviation::Sample {
+        return Err(TaError::InvalidParameter { name: "period", reason: "must be at least 2 for the sample deviation".to_string() });
+    }
+    if !multiplier.is_finite() || multiplier < 0.0 {
+        return Err(TaError::InvalidParameter { name: "multiplier", reason: format!("must be a non-negative number, received {}", multiplier) });
+    }
+    if prices.len() < period {
+        return Err(TaError::InsufficientData { indicator: "bollinger bands", received: prices.len(), required: period, note: None });
+    }
+
+    let mut state = BollingerState::new(params);
+    return Ok(prices.iter().filter_map(|&price| state.update_band(price)).collect());
+}
+
+
+/// Incremental bollinger bands. Each `update` runs in O(1) by keeping a
+/// rolling mean and sum of squared deviations (Welford's method) of the last
+/// `period` prices.
+/// 
+/// ### Example
+/// ```
+/// let mut state = BollingerState::new(&BollingerParams::default());
+/// for price in prices {
+///     if let Some((bold, bolm, bolu)) = state.update(price) { println!("{} {} {}", bold, bolm, bolu); }
+/// }
+/// ```
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct BollingerState {
+    period: usize,
+    multiplier: f64,
+    deviation: Deviation,
+    window: VecDeque<f64>,
+    mean: f64,
+    m2: f64,
+}
+
+#[allow(dead_code)]
+impl BollingerState {
+    pub fn new(params: &BollingerParams) -> Self {
+        return Self {
+            period: params.get_period(),
+            multiplier: params.get_multiplier(),
+            deviation: params.get_deviation(),
+            window: VecDeque::with_capacity(params.get_period()+1),
+            mean: 0.0,
+            m2: 0.0,
+        };
+    }
+
+    /// Adds the next price and returns `(bold, bolm, bolu)` for the last
+    /// `period` prices, or `None` until `period` prices have been received.
+    pub fn update(&mut self, price: f64) -> Option<(f64, f64, f64)> {
+        return self.update_band(price).map(|b| b.to_tuple());
+    }
+
+    /// Adds the next price and returns the `BollingerBand` for the last
+    /// `period` prices, or `None` until `period` prices have been received.
+    pub fn update_band(&mut self, price: f64) -> Option<BollingerBand> {
+        if self.period == 0 { return None; }
+
+        // Add the new price
+        self.window.push_back(price);
+        let n = self.window.len() as f64;
+        let delta = price - self.mean;
+        self.mean += delta / n;
+        self.m2 += delta * (price - self.mean);
+
+        // Remove the price that fell out of the window
+        if self.window.len() > self.period {
+            if let Some(old) = self.window.pop_front() {
+                let n = self.window.len() as f64;
+                let delta = old - self.mean;
+                self.mean -= delta / n;
+                self.m2 -= delta * (old - self.mean);
+            }
+        }
+        if self.window.len() < self.period { return None; }
+
+        let n = self.period as f64;
+        let divisor = match self.deviation {
+            Deviation::Population => n,
+            Deviation::Sample => n - 1.0,
+        };
+        if divisor <= 0.0 { return None; }
+        let std = (self.m2.max(0.0) / divisor).sqrt();
+        return Some(BollingerBand::new(price, self.mean, std, self.multiplier));
+    }
 }
 
 
@@ -100,7 +309,7 @@ mod tests {
             10.0, 10.0, 15.0, 20.0, 20.0,
             10.0, 10.0, 15.0, 20.0, 20.0
         ];
-        assert_eq!(run(prices), vec![(6.055728, 15.0, 23.944271)]);
+        assert_eq!(run(&prices), vec![(6.055728090000843, 15.0, 23.94427190999916)]);
     }
 
     #[test]
@@ -114,21 +323,96 @@ mod tests {
             11.0, 19.0, 3.0, 4.0, 7.0,
         ];
         assert_eq!(
-            run(prices),
+            run(&prices),
             vec![
-                (-5.719162, 15.6, 36.919163), (-5.6191626, 15.7, 37.01916),
-                (-5.719162, 15.6, 36.919163), (-5.469162, 15.85, 37.169163),
-                (-4.3691616, 16.95, 38.269165), (-3.5691624, 17.75, 39.06916),
-                (-3.6191616, 17.7, 39.019165), (-3.0691624, 18.25, 39.56916),
-                (-3.9191628, 17.4, 38.719162), (-5.8191624, 15.5, 36.81916),
-                (-7.269162, 14.05, 35.369164)
+                (-3.4094713235271215, 15.599999999999998, 34.60947132352712), (-3.211372240004163, 15.7, 34.61137224000416),
+                (-3.4515091265757754, 15.599999999999998, 34.651509126575775), (-3.294450893143946, 15.849999999999998, 34.99445089314394),
+                (-5.298370726864473, 16.95, 39.19837072686447), (-5.980781698039362, 17.75, 41.480781698039365),
+                (-6.083187338958588, 17.7, 41.48318733895859), (-5.1155729653693705, 18.25, 41.61557296536937),
+                (-6.868498099388024, 17.400000000000002, 41.66849809938803), (-6.622386851332298, 15.5, 37.622386851332294),
+                (-6.232751292662444, 14.049999999999999, 34.332751292662444)
             ]
         );
     }
 
+    #[test]
+    fn test_run_with_params() {
+        let prices = vec![10.0, 10.0, 15.0, 20.0, 20.0];
+        assert_eq!(run_with(&prices, &BollingerParams::new(5, 1.0)), vec![(10.52786404500042, 15.0, 19.47213595499958)]);
+    }
+
+    #[test]
+    fn test_run_rolling_window() {
+        // A volatile start must not widen the bands of a later, flat window
+        let prices = vec![10.0, 50.0, 10.0, 50.0, 20.0, 20.0, 20.0, 20.0];
+        let bbs = run_with(&prices, &BollingerParams::new(4, 2.0));
+        assert_eq!(bbs[0], (-10.0, 30.0, 70.0));
+        assert_eq!(bbs[4], (20.0, 20.0, 20.0));
+    }
+
+    #[test]
+    fn test_run_sample_deviation() {
+        let prices = vec![10.0, 10.0, 15.0, 20.0, 20.0];
+        let mut params = BollingerParams::new(5, 1.0);
+        params.set_deviation(Deviation::Sample);
+        assert_eq!(run_with(&prices, &params), vec![(10.0, 15.0, 20.0)]);
+    }
+
+    #[test]
+    fn test_run_bands() {
+        let prices = vec![10.0, 10.0, 15.0, 20.0, 20.0, 20.0];
+        let mut params = BollingerParams::new(5, 1.0);
+        params.set_deviation(Deviation::Sample);
+        let bands = run_bands(&prices, &params);
+        assert_eq!(bands[0].get_percent_b(), 1.0);
+        assert_eq!(bands[0].get_bandwidth(), 10.0 / 15.0);
+        assert_eq!(bands[1].get_middle(), 17.0);
+
+        // Bands collapse on a flat series
+        let bands = run_bands(&[10.0, 10.0, 10.0], &BollingerParams::new(3, 2.0));
+        assert_eq!(bands[0].get_percent_b(), 0.5);
+        assert_eq!(bands[0].get_bandwidth(), 0.0);
+    }
+
+    #[test]
+    fn test_state_matches_batch() {
+        let prices = vec![
+            10.0, 10.0, 15.0, 20.0, 20.0, 12.0, 8.0, 20.0, 42.0, 36.0,
+            11.0, 19.0, 3.0, 4.0, 7.0, 10.0, 10.0, 15.0, 20.0, 20.0
+        ];
+        let params = BollingerParams::new(5, 2.0);
+        let batch = run_with(&prices, &params);
+        let mut state = BollingerState::new(&params);
+        let streamed: Vec<(f64, f64, f64)> = prices.iter().filter_map(|&p| state.update(p)).collect();
+        assert_eq!(streamed.len(), batch.len());
+        for (i, (bold, bolm, bolu)) in streamed.iter().enumerate() {
+            // Compare against a direct calculation of the window
+            let window = &prices[i..i+5];
+            let mean = window.iter().sum::<f64>() / 5.0;
+            let std = (window.iter().map(|p| (p - mean) * (p - mean)).sum::<f64>() / 5.0).sqrt();
+            assert!((bolm - mean).abs() < 1e-4, "{} != {}", bolm, mean);
+            assert!((bolu - (mean + 2.0 * std)).abs() < 1e-4, "{} != {}", bolu, mean + 2.0 * std);
+            assert!((bold - (mean - 2.0 * std)).abs() < 1e-4, "{} != {}", bold, mean - 2.0 * std);
+            assert_eq!(batch[i], (*bold, *bolm, *bolu));
+        }
+    }
+
+    #[test]
+    fn test_try_run() {
+        assert_eq!(
+            try_run(&[10.0]),
+            Err(TaError::InsufficientData { indicator: "bollinger bands", received: 1, required: 20, note: None })
+        );
+        assert!(try_run_with(&[10.0], &BollingerParams::new(1, -1.0)).is_err());
+        assert!(try_run_with(&[10.0], &BollingerParams::new(1, f64::NAN)).is_err());
+        let mut params = BollingerParams::new(1, 2.0);
+        params.set_deviation(Deviation::Sample);
+        assert!(try_run_with(&[10.0], &params).is_err());
+    }
+
     #[test]
     #[should_panic(expected = "Not enough entries to calculate bollinger bands. Received 1, but required 20.")]
     fn test_run_not_enough_elements() {
-        run(vec![10.0]);
+        run(&[10.0]);
     }
 }
\ No newline at end of file
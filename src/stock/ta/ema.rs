@@ -1,6 +1,6 @@
-use crate::stock::ta::sma;
+use crate::stock::ta::{sma, TaError};
 
-/// Calculate the exponential moving average (EMA) of a `Vec<f32>` of price data.
+/// Calculate the exponential moving average (EMA) of a `&[f64]` of price data.
 /// 
 /// ### Definition
 /// Average price of an asset sampled over a given period of time. Unlike
@@ -31,32 +31,113 @@ use crate::stock::ta::sma;
 /// - 50-day and 200-day EMA
 /// 
 /// # Arguments
-/// * `prices` - `Vec<f32>` containing prices for a period of time
+/// * `prices` - `&[f64]` containing prices for a period of time
 /// * `periods` - Number of periods to average
 /// 
 /// ### Example
 /// ```
-/// ema::run(prices, 50);
-/// ema::run(prices, 200);
+/// ema::run(series.get_closes(), 50);
+/// ema::run(series.get_closes(), 200);
 /// ```
 /// 
 /// #### Resources
 /// - https://www.investopedia.com/terms/e/ema.asp
-pub fn run(prices: Vec<f32>, periods: usize) -> Vec<f32> {
-    if prices.len() < periods+1 { panic!("Not enough entries to calculate the EMA. Received {}, but required {} (periods+1).", prices.len(), periods+1); }
-    let smoothing: f32 = 2.0 / (periods as f32 + 1.0);
-    let mut emas: Vec<f32> = Vec::new();
-    // Use the SMA as its first `ema_prev`
-    let mut ema_prev = match sma::run(prices[0..periods].to_vec(), periods).pop() {
-        Some(v) => v,
-        None => 0.0,
+#[allow(dead_code)]
+pub fn run(prices: &[f64], periods: usize) -> Vec<f64> {
+    return match try_run(prices, periods) {
+        Ok(emas) => emas,
+        Err(error) => panic!("{}", error),
     };
-    for i in periods..prices.len() {
-        let ema = (prices[i] * smoothing) + (ema_prev * (1.0 - smoothing));
+}
+
+/// Calculate the exponential moving average (EMA) of a `&[f64]` of price data.
+/// Returns a `TaError` instead of panicking. See `run` for details.
+/// 
+/// ### Example
+/// ```
+/// match ema::try_run(series.get_closes(), 50) {
+///     Ok(emas) => println!("{:?}", emas),
+///     Err(error) => println!("{}", error),
+/// };
+/// ```
+#[allow(dead_code)]
+pub fn try_run(prices: &[f64], periods: usize) -> Result<Vec<f64>, TaError> {
+    if periods == 0 { return Err(TaError::InvalidParameter { name: "periods", reason: "must be greater than 0".to_string() }); }
+    if prices.len() < periods+1 {
+        return Err(TaError::InsufficientData {
+            indicator: "the EMA",
+            received: prices.len(),
+            required: periods+1,
+            note: Some("periods+1".to_string()),
+        });
+    }
+    let smoothing: f64 = 2.0 / (periods as f64 + 1.0);
+    let mut emas: Vec<f64> = Vec::new();
+    // Use the SMA as its first `ema_prev`
+    let mut ema_prev = sma::run(&prices[0..periods], periods).pop().unwrap_or(0.0);
+    for &price in &prices[periods..] {
+        let ema = (price * smoothing) + (ema_prev * (1.0 - smoothing));
         ema_prev = ema;
         emas.push(ema);
     }
-    return emas;
+    return Ok(emas);
+}
+
+/// Incremental exponential moving average (EMA). Each `update` runs in O(1).
+/// 
+/// Like `run`, the first `periods` prices seed the EMA with their SMA, and
+/// the first EMA is produced on the following price.
+/// 
+/// ### Example
+/// ```
+/// let mut state = EmaState::new(50);
+/// for price in prices {
+///     if let Some(ema) = state.update(price) { println!("{}", ema); }
+/// }
+/// ```
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct EmaState {
+    periods: usize,
+    smoothing: f64,
+    count: usize,
+    seed_sum: f64,
+    ema_prev: Option<f64>,
+}
+
+#[allow(dead_code)]
+impl EmaState {
+    pub fn new(periods: usize) -> Self {
+        return Self {
+            periods: periods,
+            smoothing: 2.0 / (periods as f64 + 1.0),
+            count: 0,
+            seed_sum: 0.0,
+            ema_prev: None,
+        };
+    }
+
+    pub fn get_periods(&self) -> usize { return self.periods; }
+
+    /// Adds the next price and returns the latest EMA, or `None` until
+    /// `periods+1` prices have been received.
+    pub fn update(&mut self, price: f64) -> Option<f64> {
+        if self.periods == 0 { return None; }
+        match self.ema_prev {
+            Some(ema_prev) => {
+                let ema = (price * self.smoothing) + (ema_prev * (1.0 - self.smoothing));
+                self.ema_prev = Some(ema);
+                return Some(ema);
+            },
+            None => {
+                // Use the SMA as its first `ema_prev`
+                self.seed_sum += price;
+                self.count += 1;
+                if self.count == self.periods { self.ema_prev = Some(self.seed_sum / self.periods as f64); }
+                return None;
+            },
+        };
+    }
 }
 
 #[cfg(test)]
@@ -66,24 +147,46 @@ mod tests {
     #[test]
     fn test_run_simple() {
         let prices = vec![10.0, 10.0, 15.0, 20.0, 20.0, 10.0];
-        assert_eq!(run(prices, 5), vec![13.333332]);
+        assert_eq!(run(&prices, 5), vec![13.333333333333336]);
     }
 
     #[test]
     fn test_run_complex() {
         let prices = vec![10.0, 10.0, 15.0, 20.0, 20.0, 10.0, 10.0, 10.0];
-        assert_eq!(run(prices, 5), vec![13.333332, 12.222221, 11.48148]);
+        assert_eq!(run(&prices, 5), vec![13.333333333333336, 12.222222222222225, 11.481481481481485]);
     }
 
     #[test]
     fn test_run_random() {
         let prices = vec![5.0, 10.0, 11.0, 6.0, 5.0, 42.0, 33.0, 1.0];
-        assert_eq!(run(prices, 5), vec![18.933332, 23.622221, 16.08148]);
+        assert_eq!(run(&prices, 5), vec![18.933333333333334, 23.622222222222224, 16.081481481481482]);
+    }
+
+    #[test]
+    fn test_state_matches_batch() {
+        let prices = vec![5.0, 10.0, 11.0, 6.0, 5.0, 42.0, 33.0, 1.0, 5.0, 10.0, 11.0, 6.0, 5.0, 42.0, 33.0, 1.0];
+        let batch = run(&prices, 5);
+        let mut state = EmaState::new(5);
+        let streamed: Vec<f64> = prices.iter().filter_map(|&p| state.update(p)).collect();
+        assert_eq!(streamed.len(), batch.len());
+        for (a, b) in streamed.iter().zip(batch.iter()) {
+            assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_try_run() {
+        assert_eq!(try_run(&[10.0, 10.0, 15.0, 20.0, 20.0, 10.0], 5), Ok(vec![13.333333333333336]));
+        assert_eq!(
+            try_run(&[10.0, 10.0, 15.0, 20.0, 20.0], 5),
+            Err(TaError::InsufficientData { indicator: "the EMA", received: 5, required: 6, note: Some("periods+1".to_string()) })
+        );
+        assert!(try_run(&[10.0], 0).is_err());
     }
 
     #[test]
     #[should_panic(expected = "Not enough entries to calculate the EMA. Received 5, but required 6 (periods+1).")]
     fn test_run_not_enough_elements() {
-        run(vec![10.0, 10.0, 15.0, 20.0, 20.0], 5);
+        run(&[10.0, 10.0, 15.0, 20.0, 20.0], 5);
     }
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
-use crate::stock::ta::ema;
+use crate::stock::ta::{ema, TaError};
 
-/// Calculate the moving average convergence divergence (MACD) of a `Vec<f32>` of price data.
+/// Calculate the moving average convergence divergence (MACD) of a `&[f64]` of price data.
 /// 
 /// ### Definition
 /// This indicator gives a picture of the momentum of a given security by displaying the
@@ -29,43 +29,175 @@ use crate::stock::ta::ema;
 ///
 /// 
 /// # Arguments
-/// * `prices` - `Vec<f32>` containing prices for a period of time
+/// * `prices` - `&[f64]` containing prices for a period of time
 /// 
 /// # Returns
-/// `(Vec<f32>, Vec<f32>)` containing values in the form of `(MACD, Signal)`.
+/// `(Vec<f64>, Vec<f64>)` containing values in the form of `(MACD, Signal)`.
 /// 
 /// ### Example
 /// ```
-/// macd::run(prices);
-/// macd::run(prices);
+/// macd::run(series.get_closes());
+/// macd::run(series.get_closes());
 /// ```
 /// 
 /// #### Resources
 /// - https://www.investopedia.com/terms/m/macd.asp
-pub fn run(prices: Vec<f32>) -> (Vec<f32>, Vec<f32>) {
-    const LOW_PERIOD: usize = 12;
-    const HIGH_PERIOD: usize = 26;
-    if prices.len() < HIGH_PERIOD+1 { panic!("Not enough entries to calculate the EMA. Received {}, but required {} (26+1).", prices.len(), HIGH_PERIOD+1); }
+#[allow(dead_code)]
+pub fn run(prices: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    return run_with(prices, &MacdParams::default());
+}
+
+/// Parameters used to calculate the MACD.
+/// 
+/// - `fast`: number of periods of the fast EMA (default 12)
+/// - `slow`: number of periods of the slow EMA (default 26)
+/// - `signal`: number of periods of the signal line EMA (default 9)
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq)]
+pub struct MacdParams {
+    fast: usize,
+    slow: usize,
+    signal: usize,
+}
+
+#[allow(dead_code)]
+impl MacdParams {
+    pub fn new(fast: usize, slow: usize, signal: usize) -> Self {
+        return Self { fast: fast, slow: slow, signal: signal };
+    }
+
+    pub fn get_fast(&self) -> usize { return self.fast; }
+    pub fn set_fast(&mut self, fast: usize) { self.fast = fast; }
+
+    pub fn get_slow(&self) -> usize { return self.slow; }
+    pub fn set_slow(&mut self, slow: usize) { self.slow = slow; }
+
+    pub fn get_signal(&self) -> usize { return self.signal; }
+    pub fn set_signal(&mut self, signal: usize) { self.signal = signal; }
+}
+
+impl Default for MacdParams {
+    fn default() -> Self {
+        return Self::new(12, 26, 9);
+    }
+}
+
+/// Calculate the moving average convergence divergence (MACD) of a `&[f64]`
+/// of price data using custom `MacdParams`. See `run` for details.
+/// 
+/// ### Example
+/// ```
+/// macd::run_with(series.get_closes(), &MacdParams::new(5, 35, 5));
+/// ```
+#[allow(dead_code)]
+pub fn run_with(prices: &[f64], params: &MacdParams) -> (Vec<f64>, Vec<f64>) {
+    return match try_run_with(prices, params) {
+        Ok(macd) => macd,
+        Err(error) => panic!("{}", error),
+    };
+}
+
+/// Calculate the moving average convergence divergence (MACD) of a `&[f64]`
+/// of price data. Returns a `TaError` instead of panicking. See `run` for
+/// details.
+#[allow(dead_code)]
+pub fn try_run(prices: &[f64]) -> Result<(Vec<f64>, Vec<f64>), TaError> {
+    return try_run_with(prices, &MacdParams::default());
+}
+
+/// Calculate the moving average convergence divergence (MACD) of a `&[f64]`
+/// of price data using custom `MacdParams`. Returns a `TaError` instead of
+/// panicking.
+/// 
+/// ### Example
+/// ```
+/// match macd::try_run_with(series.get_closes(), &MacdParams::new(5, 35, 5)) {
+///     Ok((macd, signal)) => println!("{:?} {:?}", macd, signal),
+///     Err(error) => println!("{}", error),
+/// };
+/// ```
+#[allow(dead_code)]
+pub fn try_run_with(prices: &[f64], params: &MacdParams) -> Result<(Vec<f64>, Vec<f64>), TaError> {
+    let fast = params.get_fast();
+    let slow = params.get_slow();
+    let signal_period = params.get_signal();
+    if fast == 0 { return Err(TaError::InvalidParameter { name: "fast", reason: "must be greater than 0".to_string() }); }
+    if signal_period == 0 { return Err(TaError::InvalidParameter { name: "signal", reason: "must be greater than 0".to_string() }); }
+    if slow <= fast {
+        return Err(TaError::InvalidParameter { name: "slow", reason: format!("must be greater than `fast` ({}), received {}", fast, slow) });
+    }
+    if prices.len() < slow+1 {
+        return Err(TaError::InsufficientData {
+            indicator: "the EMA",
+            received: prices.len(),
+            required: slow+1,
+            note: Some(format!("{}+1", slow)),
+        });
+    }
     
-    let mut macd: Vec<f32> = Vec::new();
-    // EMA calculation requires `period+1` entries
-    let mut i = HIGH_PERIOD+1;
-    while {
-        let emas12 = ema::run(prices[i-1-HIGH_PERIOD..i].to_vec(), LOW_PERIOD);
-        let emas26 = ema::run(prices[i-1-HIGH_PERIOD..i].to_vec(), HIGH_PERIOD);
-        macd.push(emas12[emas12.len()-1] - emas26[emas26.len()-1]);
-        i += 1;
-        i <= prices.len()
-    } {}
+    // Both EMAs run over the whole series. The fast EMA for `prices[i]` is
+    // at `i-fast` and the slow EMA at `i-slow`, so the MACD starts once the
+    // slow EMA is available (`slow+1` entries).
+    let emas_fast = ema::try_run(prices, fast)?;
+    let emas_slow = ema::try_run(prices, slow)?;
+    let mut macd: Vec<f64> = Vec::new();
+    for i in slow..prices.len() {
+        macd.push(emas_fast[i-fast] - emas_slow[i-slow]);
+    }
 
     // Calculate the MACD signal line
-    let mut signal: Vec<f32> = Vec::new();
-    if macd.len() > 9 { signal = ema::run(macd.to_vec(), 9); };
+    let mut signal: Vec<f64> = Vec::new();
+    if macd.len() > signal_period { signal = ema::try_run(&macd, signal_period)?; };
 
-    return (macd, signal);
+    return Ok((macd, signal));
 }
 
 
+/// Incremental moving average convergence divergence (MACD). Each `update`
+/// runs in O(1).
+/// 
+/// ### Example
+/// ```
+/// let mut state = MacdState::new(&MacdParams::default());
+/// for price in prices {
+///     if let Some((macd, signal)) = state.update(price) { println!("{} {:?}", macd, signal); }
+/// }
+/// ```
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct MacdState {
+    fast: ema::EmaState,
+    slow: ema::EmaState,
+    signal: ema::EmaState,
+}
+
+#[allow(dead_code)]
+impl MacdState {
+    pub fn new(params: &MacdParams) -> Self {
+        return Self {
+            fast: ema::EmaState::new(params.get_fast()),
+            slow: ema::EmaState::new(params.get_slow()),
+            signal: ema::EmaState::new(params.get_signal()),
+        };
+    }
+
+    /// Adds the next price and returns `(MACD, Signal)`. Returns `None` until
+    /// the slow EMA is available, and `Signal` is `None` until enough MACD
+    /// values have been produced to seed the signal line.
+    pub fn update(&mut self, price: f64) -> Option<(f64, Option<f64>)> {
+        let fast = self.fast.update(price);
+        let slow = self.slow.update(price);
+        return match (fast, slow) {
+            (Some(fast), Some(slow)) => {
+                let macd = fast - slow;
+                Some((macd, self.signal.update(macd)))
+            },
+            _ => None,
+        };
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -80,7 +212,7 @@ mod tests {
             10.0, 10.0, 15.0, 20.0, 20.0,
             10.0, 10.0
         ];
-        assert_eq!(run(prices), (vec![-0.17376137], vec![]));
+        assert_eq!(run(&prices), (vec![-0.1737646011001761], vec![]));
     }
 
     #[test]
@@ -100,30 +232,92 @@ mod tests {
             11.0, 19.0, 3.0, 4.0, 7.0,
         ];
         assert_eq!(
-            run(prices),
+            run(&prices),
             (
                 vec![
-                    2.4991398, 0.9562206, -0.7649956, -1.7961569, -2.1694717,
-                    -2.3433933, -2.3066406, -1.871275, -1.0476179, -0.308959,
-                    -0.28025723, -0.09412098, 2.2728977, 3.9846954, 3.26997,
-                    2.4991398, 0.9562206, -0.7649956, -1.7961569, -2.1694717,
-                    -2.3433933, -2.3066406, -1.871275, -1.0476179, -0.308959,
-                    -0.28025723, -0.09412098, 2.2728977, 3.9846954, 3.26997,
-                    2.4991398, 0.9562206, -0.7649956, -1.7961569
+                    2.4991382429371427, 0.9888691196619206, -0.12588724894016856, -0.7585210163755374, -1.0062138995072303,
+                    -1.188808450114598, -0.9194587852045348, -0.2990912838054314, 0.19036007807483912, -0.06651261649844997,
+                    -0.5860964926855168, -0.02923375758780189, 2.1623736133017246, 3.376170002308598, 2.294373213042661,
+                    2.058841560905041, 0.5744923569692411, -0.5152341402108753, -1.123820607913693, -1.3485100557388208,
+                    -1.5091811609495185, -1.2190040364171644, -0.5789051036762363, -0.0708058816863204, -0.31009217919637067,
+                    -0.8131217036934615, -0.24070185304367797, 1.9655040339673349, 3.1929815347996957, 2.1239911832034046,
+                    1.9004347404062685, 0.42727302892240004, -0.6520106257036762, -1.2508566727673713
                 ],
                 vec![
-                    -0.84794205, -0.7344051, -0.60634834, -0.0304991, 0.7725398,
-                    1.2720258, 1.5174487, 1.4052031, 0.97116345, 0.41769937,
-                    -0.09973487, -0.54846656, -0.90010136, -1.094336, -1.0849924,
-                    -0.9297857, -0.79988, -0.65872824, -0.07240304, 0.73901665,
-                    1.2452073, 1.4959939, 1.3880392, 0.9574323, 0.40671447
+                    -0.06837925603512092, -0.1719227033652001, -0.14338491420972047, 0.3177667912925686, 0.9294474334957745,
+                    1.202432589405152, 1.3737143837051298, 1.213869978357952, 0.8680491546441866, 0.4696752021326107,
+                    0.10603815055832444, -0.21700571174324418, -0.41740537667802824, -0.4497053220776699, -0.37392543399940004,
+                    -0.3611587830387942, -0.45155136716972766, -0.4093814643445178, 0.06559563531785273, 0.6910728152142214,
+                    0.977656488812058, 1.1622121391309002, 1.0152243170892001, 0.6817773285306249, 0.2952505282710256
                 ]
             ));
     }
 
+    #[test]
+    fn test_run_with_params() {
+        let prices = vec![10.0, 10.0, 15.0, 20.0, 20.0, 10.0, 10.0, 15.0, 20.0];
+        assert_eq!(run_with(&prices, &MacdParams::new(2, 4, 2)), (
+            vec![3.0092592592592595, -0.663580246913579, -1.2211934156378597, 0.3262688614540483, 1.8820896204846846],
+            vec![-0.4231824417009596, 0.076451760402379, 1.2802103337905828]
+        ));
+    }
+
+    #[test]
+    fn test_run_reference() {
+        // Textbook MACD (EMAs seeded with the SMA of their first `periods`
+        // prices) computed with exact fractions:
+        // MACD 325/108, -215/324, -1187/972, 4757/14580, 411613/218700
+        // Signal -617/1458, 836/10935, 139991/109350
+        let prices = vec![10.0, 10.0, 15.0, 20.0, 20.0, 10.0, 10.0, 15.0, 20.0];
+        let expected_macd = [325.0/108.0, -215.0/324.0, -1187.0/972.0, 4757.0/14580.0, 411613.0/218700.0];
+        let expected_signal = [-617.0/1458.0, 836.0/10935.0, 139991.0/109350.0];
+        let (macd, signal) = run_with(&prices, &MacdParams::new(2, 4, 2));
+        assert_eq!(macd.len(), expected_macd.len());
+        assert_eq!(signal.len(), expected_signal.len());
+        for (a, b) in macd.iter().zip(expected_macd.iter()) {
+            assert!((a - b).abs() < 1e-12, "{} != {}", a, b);
+        }
+        for (a, b) in signal.iter().zip(expected_signal.iter()) {
+            assert!((a - b).abs() < 1e-12, "{} != {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_state_matches_batch() {
+        let prices: Vec<f64> = (0..60).map(|i| 20.0 + ((i * 7) % 13) as f64 - (i as f64 * 0.1)).collect();
+        let (batch_macd, batch_signal) = run(&prices);
+        let mut state = MacdState::new(&MacdParams::default());
+        let mut macd: Vec<f64> = Vec::new();
+        let mut signal: Vec<f64> = Vec::new();
+        for &price in prices.iter() {
+            if let Some((m, s)) = state.update(price) {
+                macd.push(m);
+                if let Some(s) = s { signal.push(s); }
+            }
+        }
+        assert_eq!(macd.len(), batch_macd.len());
+        assert_eq!(signal.len(), batch_signal.len());
+        for (a, b) in macd.iter().zip(batch_macd.iter()) {
+            assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+        }
+        for (a, b) in signal.iter().zip(batch_signal.iter()) {
+            assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_try_run() {
+        assert_eq!(
+            try_run(&[10.0, 10.0, 15.0, 20.0, 20.0]),
+            Err(TaError::InsufficientData { indicator: "the EMA", received: 5, required: 27, note: Some("26+1".to_string()) })
+        );
+        assert!(try_run_with(&[10.0; 40], &MacdParams::new(26, 12, 9)).is_err());
+        assert!(try_run_with(&[10.0; 40], &MacdParams::new(12, 26, 0)).is_err());
+    }
+
     #[test]
     #[should_panic(expected = "Not enough entries to calculate the EMA. Received 5, but required 27 (26+1).")]
     fn test_run_not_enough_elements() {
-        run(vec![10.0, 10.0, 15.0, 20.0, 20.0]);
+        run(&[10.0, 10.0, 15.0, 20.0, 20.0]);
     }
 }
\ No newline at end of file
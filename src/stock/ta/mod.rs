@@ -1,3 +1,5 @@
+use std::fmt;
+
 pub mod bollinger_band;
 pub mod ema;
 pub mod macd;
@@ -5,3 +7,49 @@ pub mod obv;
 pub mod rsi;
 pub mod sma;
 pub mod stochastic_oscillator;
+
+/// Error returned by the non-panicking (`try_*`) indicator functions.
+/// 
+/// - `InsufficientData`: fewer entries than the indicator needs. `note`
+///   optionally explains how `required` was derived (ex. `periods+1`).
+/// - `LengthMismatch`: two inputs that must be aligned have different lengths
+/// - `InvalidParameter`: a parameter is outside of its valid range
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[allow(dead_code)]
+pub enum TaError {
+    InsufficientData {
+        indicator: &'static str,
+        received: usize,
+        required: usize,
+        note: Option<String>,
+    },
+    LengthMismatch {
+        left: &'static str,
+        left_len: usize,
+        right: &'static str,
+        right_len: usize,
+    },
+    InvalidParameter {
+        name: &'static str,
+        reason: String,
+    },
+}
+
+impl fmt::Display for TaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            TaError::InsufficientData { indicator, received, required, note } => match note {
+                Some(note) => write!(f, "Not enough entries to calculate {}. Received {}, but required {} ({}).", indicator, received, required, note),
+                None => write!(f, "Not enough entries to calculate {}. Received {}, but required {}.", indicator, received, required),
+            },
+            TaError::LengthMismatch { left, left_len, right, right_len } => {
+                write!(f, "Length mismatch. `{}` contains {} entries, but `{}` contains {}.", left, left_len, right, right_len)
+            },
+            TaError::InvalidParameter { name, reason } => write!(f, "Invalid parameter `{}`: {}.", name, reason),
+        };
+    }
+}
+
+impl std::error::Error for TaError {}
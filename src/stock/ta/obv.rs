@@ -1,5 +1,7 @@
-/// Calculate the on-balance volume (OBV) based on a `Vec<f32>` of price data
-/// and a `Vec<u32>` of volume data.
+use crate::stock::ta::TaError;
+
+/// Calculate the on-balance volume (OBV) based on a `&[f64]` of price data
+/// and a `&[u64]` of volume data.
 /// 
 /// ### Definition
 /// A momentum indicator used to predict price changes in a security using data
@@ -24,23 +26,47 @@
 /// a while.
 /// 
 /// # Arguments
-/// * `prices` - `Vec<f32>` containing prices for a period of time
-/// * `volume` - `Vec<u32>` containing volume data for a period of time
+/// * `prices` - `&[f64]` containing prices for a period of time
+/// * `volume` - `&[u64]` containing volume data for a period of time
 /// 
 /// ### Example
 /// ```
-/// obv::run(prices, volume);
-/// obv::run(prices, volume);
+/// obv::run(series.get_closes(), series.get_volumes());
+/// obv::run(series.get_closes(), series.get_volumes());
 /// ```
 /// 
 /// #### Resources
 /// - https://www.investopedia.com/terms/o/onbalancevolume.asp
-pub fn run(prices: Vec<f32>, volume: Vec<u32>) -> Vec<i32> {
-    if prices.len() != volume.len() {panic!("Length mismatch. `prices` contains {} entries, but `volume` contains {}.", prices.len(), volume.len())}
-    if prices.len() < 2 { panic!("Not enough entries to calculate OBV. Received {}, but required 2.", prices.len()); }
-    let mut obvs: Vec<i32> = Vec::new();
+#[allow(dead_code)]
+pub fn run(prices: &[f64], volume: &[u64]) -> Vec<i64> {
+    return match try_run(prices, volume) {
+        Ok(obvs) => obvs,
+        Err(error) => panic!("{}", error),
+    };
+}
+
+/// Calculate the on-balance volume (OBV) based on a `&[f64]` of price data
+/// and a `&[u64]` of volume data. Returns a `TaError` instead of panicking.
+/// See `run` for details.
+/// 
+/// ### Example
+/// ```
+/// match obv::try_run(series.get_closes(), series.get_volumes()) {
+///     Ok(obvs) => println!("{:?}", obvs),
+///     Err(error) => println!("{}", error),
+/// };
+/// ```
+#[allow(dead_code)]
+pub fn try_run(prices: &[f64], volume: &[u64]) -> Result<Vec<i64>, TaError> {
+    if prices.len() != volume.len() {
+        return Err(TaError::LengthMismatch { left: "prices", left_len: prices.len(), right: "volume", right_len: volume.len() });
+    }
+    if prices.len() < 2 {
+        return Err(TaError::InsufficientData { indicator: "OBV", received: prices.len(), required: 2, note: None });
+    }
+    let mut obvs: Vec<i64> = Vec::new();
     let mut obv_prev = 0;
-    let mut price_prev = match prices.get(0) {
+    let mut price_prev = match prices.first() {
         Some(&v) => v,
         None => 0.0
     };
@@ -53,15 +79,55 @@ pub fn run(prices: Vec<f32>, volume: Vec<u32>) -> Vec<i32> {
             Some(&v) => v,
             None => 0,
         };
-        let mut v_final: i32 = 0;
-        if price > price_prev { v_final = v as i32;}
-        if price < price_prev { v_final = -1 * v as i32;}
+        let mut v_final: i64 = 0;
+        if price > price_prev { v_final = v as i64;}
+        if price < price_prev { v_final = -(v as i64);}
         let obv = obv_prev + v_final;
         obvs.push(obv);
         obv_prev = obv;
         price_prev = price;
     }
-    return obvs;
+    return Ok(obvs);
+}
+
+/// Incremental on-balance volume (OBV). Each `update` runs in O(1).
+/// 
+/// ### Example
+/// ```
+/// let mut state = ObvState::new();
+/// for (price, volume) in prices.iter().zip(volume.iter()) {
+///     if let Some(obv) = state.update(*price, *volume) { println!("{}", obv); }
+/// }
+/// ```
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Default)]
+pub struct ObvState {
+    price_prev: Option<f64>,
+    obv: i64,
+}
+
+#[allow(dead_code)]
+impl ObvState {
+    pub fn new() -> Self {
+        return Self { price_prev: None, obv: 0 };
+    }
+
+    /// Adds the next price and volume and returns the latest OBV, or `None`
+    /// for the first entry.
+    pub fn update(&mut self, price: f64, volume: u64) -> Option<i64> {
+        let price_prev = match self.price_prev {
+            Some(v) => v,
+            None => {
+                self.price_prev = Some(price);
+                return None;
+            },
+        };
+        if price > price_prev { self.obv += volume as i64; }
+        if price < price_prev { self.obv -= volume as i64; }
+        self.price_prev = Some(price);
+        return Some(self.obv);
+    }
 }
 
 #[cfg(test)]
@@ -70,23 +136,64 @@ mod tests {
 
     #[test]
     fn test_run_simple() {
-        assert_eq!(run(vec![10.0, 15.0], vec![100, 500]), vec![500]);
+        assert_eq!(run(&[10.0, 15.0], &[100, 500]), vec![500]);
     }
 
     #[test]
     fn test_run_complex() {
-        assert_eq!(run(vec![10.0, 15.0, 20.0, 15.0, 10.0], vec![100, 500, 300, 1500, 200]), vec![500, 800, -700, -900]);
+        assert_eq!(run(&[10.0, 15.0, 20.0, 15.0, 10.0], &[100, 500, 300, 1500, 200]), vec![500, 800, -700, -900]);
+    }
+
+    #[test]
+    fn test_run_spy_volumes_beyond_i32() {
+        // SPY from 10/1/2008 to 10/13/2008. Seven straight down days push
+        // the OBV past `i32::MIN` by 10/8
+        let prices = vec![116.06, 111.85, 110.34, 104.72, 100.03, 97.51, 90.70, 88.50, 101.35];
+        let volume = vec![332783000, 365337800, 461798000, 610637500, 540012100, 725414800, 534485200, 871026300, 455584000];
+        let obvs = run(&prices, &volume);
+        assert_eq!(obvs, vec![-365337800, -827135800, -1437773300, -1977785400, -2703200200, -3237685400, -4108711700, -3653127700]);
+        let mut state = ObvState::new();
+        let streamed: Vec<i64> = prices.iter().zip(volume.iter()).filter_map(|(&p, &v)| state.update(p, v)).collect();
+        assert_eq!(streamed, obvs);
+    }
+
+    #[test]
+    fn test_run_volume_beyond_u32() {
+        assert_eq!(run(&[10.0, 15.0, 20.0], &[0, 5_000_000_000, 6_000_000_000]), vec![5_000_000_000, 11_000_000_000]);
+    }
+
+    #[test]
+    fn test_state_matches_batch() {
+        let prices = vec![10.0, 15.0, 20.0, 15.0, 10.0, 10.0, 12.0];
+        let volume = vec![100, 500, 300, 1500, 200, 700, 50];
+        let batch = run(&prices, &volume);
+        let mut state = ObvState::new();
+        let streamed: Vec<i64> = prices.iter().zip(volume.iter()).filter_map(|(&p, &v)| state.update(p, v)).collect();
+        assert_eq!(streamed, batch);
+    }
+
+    #[test]
+    fn test_try_run() {
+        assert_eq!(try_run(&[10.0, 15.0], &[100, 500]), Ok(vec![500]));
+        assert_eq!(
+            try_run(&[10.0, 10.0], &[10]),
+            Err(TaError::LengthMismatch { left: "prices", left_len: 2, right: "volume", right_len: 1 })
+        );
+        assert_eq!(
+            try_run(&[10.0], &[10]),
+            Err(TaError::InsufficientData { indicator: "OBV", received: 1, required: 2, note: None })
+        );
     }
 
     #[test]
     #[should_panic(expected = "Length mismatch. `prices` contains 2 entries, but `volume` contains 1.")]
     fn test_run_length_mismatch() {
-        run(vec![10.0, 10.0], vec![10]);
+        run(&[10.0, 10.0], &[10]);
     }
 
     #[test]
     #[should_panic(expected = "Not enough entries to calculate OBV. Received 1, but required 2.")]
     fn test_run_not_enough_elements() {
-        run(vec![10.0], vec![10]);
+        run(&[10.0], &[10]);
     }
 }
\ No newline at end of file
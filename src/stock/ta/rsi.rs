@@ -1,4 +1,6 @@
-/// Calculate the relative strength index (RSI) on a `Vec<f32>` of price data.
+use crate::stock::ta::TaError;
+
+/// Calculate the relative strength index (RSI) on a `&[f64]` of price data.
 /// 
 /// ### Definition
 /// Measures the magnitude of recent price changes to determine whether
@@ -52,28 +54,95 @@
 /// 4. RSI breaks most recent low (#2)
 /// 
 /// # Arguments
-/// * `prices` - `Vec<f32>` containing prices for a period of time
+/// * `prices` - `&[f64]` containing prices for a period of time
 /// 
 /// ### Example
 /// ```
-/// rsi::run(prices);
-/// rsi::run(prices);
+/// rsi::run(series.get_closes());
+/// rsi::run(series.get_closes());
 /// ```
 /// 
 /// #### Resources
 /// - https://www.investopedia.com/terms/r/rsi.asp
-pub fn run(prices: Vec<f32>) -> Vec<f32> {
-    const PERIOD: usize = 14;
-    if prices.len() < PERIOD+1 { panic!("Not enough entries to calculate the RSI. Received {}, but required {}.", prices.len(), PERIOD+1); }
+#[allow(dead_code)]
+pub fn run(prices: &[f64]) -> Vec<f64> {
+    return run_with(prices, &RsiParams::default());
+}
+
+/// Parameters used to calculate the RSI.
+/// 
+/// - `period`: number of periods used to average gains and losses (default 14)
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq)]
+pub struct RsiParams {
+    period: usize,
+}
+
+#[allow(dead_code)]
+impl RsiParams {
+    pub fn new(period: usize) -> Self {
+        return Self { period: period };
+    }
+
+    pub fn get_period(&self) -> usize { return self.period; }
+    pub fn set_period(&mut self, period: usize) { self.period = period; }
+}
+
+impl Default for RsiParams {
+    fn default() -> Self {
+        return Self::new(14);
+    }
+}
+
+/// Calculate the relative strength index (RSI) on a `&[f64]` of price data
+/// using custom `RsiParams`. See `run` for details.
+/// 
+/// ### Example
+/// ```
+/// rsi::run_with(series.get_closes(), &RsiParams::new(9));
+/// ```
+#[allow(dead_code)]
+pub fn run_with(prices: &[f64], params: &RsiParams) -> Vec<f64> {
+    return match try_run_with(prices, params) {
+        Ok(rsis) => rsis,
+        Err(error) => panic!("{}", error),
+    };
+}
+
+/// Calculate the relative strength index (RSI) on a `&[f64]` of price data.
+/// Returns a `TaError` instead of panicking. See `run` for details.
+#[allow(dead_code)]
+pub fn try_run(prices: &[f64]) -> Result<Vec<f64>, TaError> {
+    return try_run_with(prices, &RsiParams::default());
+}
+
+/// Calculate the relative strength index (RSI) on a `&[f64]` of price data
+/// using custom `RsiParams`. Returns a `TaError` instead of panicking.
+/// 
+/// ### Example
+/// ```
+/// match rsi::try_run_with(series.get_closes(), &RsiParams::new(9)) {
+///     Ok(rsis) => println!("{:?}", rsis),
+///     Err(error) => println!("{}", error),
+/// };
+/// ```
+#[allow(dead_code)]
+pub fn try_run_with(prices: &[f64], params: &RsiParams) -> Result<Vec<f64>, TaError> {
+    let period = params.get_period();
+    if period == 0 { return Err(TaError::InvalidParameter { name: "period", reason: "must be greater than 0".to_string() }); }
+    if prices.len() < period+1 {
+        return Err(TaError::InsufficientData { indicator: "the RSI", received: prices.len(), required: period+1, note: None });
+    }
 
     // AVG Gain/Loss
-    let mut ag: f32 = 0.0;
-    let mut al: f32 = 0.0;
-    let mut last_price: f32 = 0.0;
-    let mut rsis: Vec<f32> = Vec::new();
-    for i in 0..PERIOD+1 {
+    let mut ag: f64 = 0.0;
+    let mut al: f64 = 0.0;
+    let mut last_price: f64 = 0.0;
+    let mut rsis: Vec<f64> = Vec::new();
+    for i in 0..period+1 {
         if i == 0 {
-            last_price = match prices.get(0) {
+            last_price = match prices.first() {
                 Some(&v) => v,
                 None => 0.0,
             };
@@ -92,31 +161,103 @@ pub fn run(prices: Vec<f32>) -> Vec<f32> {
         }
         last_price = current_price;
     }
-    ag = ag / PERIOD as f32;
-    al = al / PERIOD as f32;
+    ag /= period as f64;
+    al /= period as f64;
     let rs = ag / al;
     let rsi_1 = 100.0 - (100.0 / (1.0 + rs));
     rsis.push(rsi_1);
 
     // Find remaining RSIs
-    for i in PERIOD+1..prices.len() {
+    for i in period+1..prices.len() {
         let current_price = match prices.get(i) {
             Some(&v) => v,
             None => 0.0,
         };
-        if current_price > last_price {
-            ag = ((ag * (PERIOD as f32-1.0)) + (current_price - last_price)) / PERIOD as f32;
-            al = ((al * (PERIOD as f32-1.0))) / PERIOD as f32;
-        } else if current_price < last_price {
-            ag = ((ag * (PERIOD as f32-1.0))) / PERIOD as f32;
-            al = ((al * (PERIOD as f32-1.0)) + (last_price - current_price)) / PERIOD as f32;
-        }
+        // Wilder's smoothing decays both averages on every bar, including
+        // unchanged ones
+        let cg = (current_price - last_price).max(0.0);
+        let cl = (last_price - current_price).max(0.0);
+        ag = ((ag * (period as f64-1.0)) + cg) / period as f64;
+        al = ((al * (period as f64-1.0)) + cl) / period as f64;
         let rs = ag / al;
         let rsi = 100.0 - (100.0 / (1.0 + rs));
         rsis.push(rsi);
         last_price = current_price;
     }
-    return rsis;
+    return Ok(rsis);
+}
+
+/// Incremental relative strength index (RSI) using Wilder's smoothing. Each
+/// `update` runs in O(1).
+/// 
+/// Like `run`, the first RSI is produced once `period+1` prices have been
+/// received and an unchanged price decays both the average gain and the
+/// average loss.
+/// 
+/// ### Example
+/// ```
+/// let mut state = RsiState::new(&RsiParams::default());
+/// for price in prices {
+///     if let Some(rsi) = state.update(price) { println!("{}", rsi); }
+/// }
+/// ```
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct RsiState {
+    period: usize,
+    count: usize,
+    last_price: Option<f64>,
+    ag: f64,
+    al: f64,
+}
+
+#[allow(dead_code)]
+impl RsiState {
+    pub fn new(params: &RsiParams) -> Self {
+        return Self {
+            period: params.get_period(),
+            count: 0,
+            last_price: None,
+            ag: 0.0,
+            al: 0.0,
+        };
+    }
+
+    /// Adds the next price and returns the latest RSI, or `None` until
+    /// `period+1` prices have been received.
+    pub fn update(&mut self, current_price: f64) -> Option<f64> {
+        if self.period == 0 { return None; }
+        let last_price = match self.last_price {
+            Some(v) => v,
+            None => {
+                self.last_price = Some(current_price);
+                return None;
+            },
+        };
+        self.last_price = Some(current_price);
+        self.count += 1;
+        let period = self.period as f64;
+
+        if self.count <= self.period {
+            // AVG Gain/Loss
+            if current_price > last_price {
+                self.ag += current_price - last_price;
+            } else if current_price < last_price {
+                self.al += last_price - current_price;
+            }
+            if self.count < self.period { return None; }
+            self.ag /= period;
+            self.al /= period;
+        } else {
+            let cg = (current_price - last_price).max(0.0);
+            let cl = (last_price - current_price).max(0.0);
+            self.ag = ((self.ag * (period-1.0)) + cg) / period;
+            self.al = ((self.al * (period-1.0)) + cl) / period;
+        }
+
+        let rs = self.ag / self.al;
+        return Some(100.0 - (100.0 / (1.0 + rs)));
+    }
 }
 
 #[cfg(test)]
@@ -126,24 +267,66 @@ mod tests {
     #[test]
     fn test_run_simple() {
         let prices = vec![10.0, 12.0, 15.0, 13.0, 18.0, 10.0, 12.0, 15.0, 13.0, 18.0, 10.0, 12.0, 15.0, 13.0, 18.0];
-        assert_eq!(run(prices), vec![57.69231]);
+        assert_eq!(run(&prices), vec![57.692307692307686]);
     }
 
     #[test]
     fn test_run_complex() {
         let prices = vec![10.0, 12.0, 15.0, 13.0, 18.0, 10.0, 12.0, 15.0, 13.0, 18.0, 10.0, 12.0, 15.0, 13.0, 18.0, 10.0];
-        assert_eq!(run(prices), vec![57.69231, 49.492382]);
+        assert_eq!(run(&prices), vec![57.692307692307686, 49.49238578680204]);
     }
 
     #[test]
     fn test_run_random() {
         let prices = vec![5.0, 10.0, 11.0, 6.0, 5.0, 42.0, 33.0, 1.0, 5.0, 10.0, 11.0, 6.0, 5.0, 42.0, 33.0, 1.0, 5.0, 10.0, 11.0, 6.0, 5.0, 42.0, 33.0, 1.0];
-        assert_eq!(run(prices), vec![59.210526, 48.267326, 49.52316, 51.120464, 51.451355, 49.641834, 49.268627, 60.9628, 57.491276, 47.199604]);
+        assert_eq!(run(&prices), vec![59.21052631578947, 48.26732673267326, 49.52316076294277, 51.120470145398194, 51.45136392471982, 49.64184075859335, 49.26863147895377, 60.96280571261349, 57.49127967821803, 47.199606033693016]);
+    }
+
+    #[test]
+    fn test_run_with_period() {
+        let prices = vec![10.0, 12.0, 15.0, 13.0, 18.0, 10.0, 12.0];
+        assert_eq!(run_with(&prices, &RsiParams::new(5)), vec![50.0, 55.55555555555556]);
+    }
+
+    #[test]
+    fn test_run_unchanged_price() {
+        // Unchanged closes decay both the average gain and the average loss
+        let prices = vec![10.0, 12.0, 11.0, 13.0, 13.0, 14.0];
+        assert_eq!(run_with(&prices, &RsiParams::new(3)), vec![80.0, 80.0, 86.20689655172414]);
+        let mut state = RsiState::new(&RsiParams::new(3));
+        let streamed: Vec<f64> = prices.iter().filter_map(|&p| state.update(p)).collect();
+        assert_eq!(streamed, vec![80.0, 80.0, 86.20689655172414]);
+    }
+
+    #[test]
+    fn test_state_matches_batch() {
+        let prices = vec![
+            5.0, 10.0, 11.0, 6.0, 5.0, 42.0, 33.0, 1.0, 5.0, 10.0, 11.0, 6.0, 5.0, 42.0, 33.0, 1.0,
+            1.0, 5.0, 10.0, 11.0, 6.0, 5.0, 42.0, 33.0, 1.0
+        ];
+        let batch = run(&prices);
+        let mut state = RsiState::new(&RsiParams::default());
+        let streamed: Vec<f64> = prices.iter().filter_map(|&p| state.update(p)).collect();
+        assert_eq!(streamed.len(), batch.len());
+        for (a, b) in streamed.iter().zip(batch.iter()) {
+            assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_try_run() {
+        let prices = vec![10.0, 12.0, 15.0, 13.0, 18.0, 10.0, 12.0, 15.0, 13.0, 18.0, 10.0, 12.0, 15.0, 13.0, 18.0];
+        assert_eq!(try_run(&prices), Ok(vec![57.692307692307686]));
+        assert_eq!(
+            try_run(&[10.0]),
+            Err(TaError::InsufficientData { indicator: "the RSI", received: 1, required: 15, note: None })
+        );
+        assert!(try_run_with(&[10.0, 11.0], &RsiParams::new(0)).is_err());
     }
 
     #[test]
     #[should_panic(expected = "Not enough entries to calculate the RSI. Received 0, but required 15.")]
     fn test_run_not_enough_elements() {
-        run(vec![]);
+        run(&[]);
     }
 }
\ No newline at end of file
@@ -1,4 +1,8 @@
-/// Calculate the simple moving average (SMA) based on a `Vec<f32>` of price data.
+use std::collections::VecDeque;
+
+use crate::stock::ta::TaError;
+
+/// Calculate the simple moving average (SMA) based on a `&[f64]` of price data.
 /// 
 /// ### Definition
 /// Average price of an asset sampled over a given period of time. Used
@@ -16,28 +20,94 @@
 /// - golden cross: 200-day SMA crosses below 50-day SMA
 /// 
 /// # Arguments
-/// * `prices` - `Vec<f32>` containing prices for a period of time
+/// * `prices` - `&[f64]` containing prices for a period of time
 /// * `periods` - Number of periods to average
 /// 
 /// ### Example
 /// ```
-/// sma::run(prices, 50);
-/// sma::run(prices, 200);
+/// sma::run(series.get_closes(), 50);
+/// sma::run(series.get_closes(), 200);
 /// ```
 /// 
 /// #### Resources
 /// - https://www.investopedia.com/terms/s/sma.asp
-pub fn run(prices: Vec<f32>, periods: usize) -> Vec<f32> {
-    if prices.len() < periods { panic!("Not enough entries to calculate the SMA. Received {}, but required {}.", prices.len(), periods); }
-    let mut smas: Vec<f32> = Vec::new();
+#[allow(dead_code)]
+pub fn run(prices: &[f64], periods: usize) -> Vec<f64> {
+    return match try_run(prices, periods) {
+        Ok(smas) => smas,
+        Err(error) => panic!("{}", error),
+    };
+}
+
+/// Calculate the simple moving average (SMA) based on a `&[f64]` of price data.
+/// Returns a `TaError` instead of panicking. See `run` for details.
+/// 
+/// ### Example
+/// ```
+/// match sma::try_run(series.get_closes(), 50) {
+///     Ok(smas) => println!("{:?}", smas),
+///     Err(error) => println!("{}", error),
+/// };
+/// ```
+#[allow(dead_code)]
+pub fn try_run(prices: &[f64], periods: usize) -> Result<Vec<f64>, TaError> {
+    if periods == 0 { return Err(TaError::InvalidParameter { name: "periods", reason: "must be greater than 0".to_string() }); }
+    if prices.len() < periods {
+        return Err(TaError::InsufficientData { indicator: "the SMA", received: prices.len(), required: periods, note: None });
+    }
+    let mut smas: Vec<f64> = Vec::new();
     let mut i = periods;
     while {
-        let sum = prices[i-periods..i].iter().sum::<f32>();
-        smas.push(sum / periods as f32);
+        let sum = prices[i-periods..i].iter().sum::<f64>();
+        smas.push(sum / periods as f64);
         i += 1;
         i <= prices.len()
     } {}
-    return smas;
+    return Ok(smas);
+}
+
+/// Incremental simple moving average (SMA). Each `update` runs in O(1) by
+/// keeping a running sum of the last `periods` prices.
+/// 
+/// ### Example
+/// ```
+/// let mut state = SmaState::new(50);
+/// for price in prices {
+///     if let Some(sma) = state.update(price) { println!("{}", sma); }
+/// }
+/// ```
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct SmaState {
+    periods: usize,
+    window: VecDeque<f64>,
+    sum: f64,
+}
+
+#[allow(dead_code)]
+impl SmaState {
+    pub fn new(periods: usize) -> Self {
+        return Self {
+            periods: periods,
+            window: VecDeque::with_capacity(periods+1),
+            sum: 0.0,
+        };
+    }
+
+    pub fn get_periods(&self) -> usize { return self.periods; }
+
+    /// Adds the next price and returns the SMA of the last `periods` prices,
+    /// or `None` until `periods` prices have been received.
+    pub fn update(&mut self, price: f64) -> Option<f64> {
+        if self.periods == 0 { return None; }
+        self.window.push_back(price);
+        self.sum += price;
+        if self.window.len() > self.periods {
+            if let Some(old) = self.window.pop_front() { self.sum -= old; }
+        }
+        if self.window.len() < self.periods { return None; }
+        return Some(self.sum / self.periods as f64);
+    }
 }
 
 #[cfg(test)]
@@ -47,18 +117,52 @@ mod tests {
     #[test]
     fn test_run_simple() {
         let prices = vec![10.0, 10.0, 15.0, 20.0, 20.0];
-        assert_eq!(run(prices, 5), vec![15.0]);
+        assert_eq!(run(&prices, 5), vec![15.0]);
     }
 
     #[test]
     fn test_run_complex() {
         let prices = vec![10.0, 10.0, 15.0, 20.0, 20.0, 10.0, 10.0, 10.0];
-        assert_eq!(run(prices, 5), vec![15.0, 15.0, 15.0, 14.0]);
+        assert_eq!(run(&prices, 5), vec![15.0, 15.0, 15.0, 14.0]);
+    }
+
+    #[test]
+    fn test_state_matches_batch() {
+        let prices = vec![5.0, 10.0, 11.0, 6.0, 5.0, 42.0, 33.0, 1.0, 5.0, 10.0, 11.0, 6.0, 5.0, 42.0, 33.0, 1.0];
+        let batch = run(&prices, 5);
+        let mut state = SmaState::new(5);
+        let streamed: Vec<f64> = prices.iter().filter_map(|&p| state.update(p)).collect();
+        assert_eq!(streamed.len(), batch.len());
+        for (a, b) in streamed.iter().zip(batch.iter()) {
+            assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_state_not_ready() {
+        let mut state = SmaState::new(3);
+        assert_eq!(state.update(10.0), None);
+        assert_eq!(state.update(20.0), None);
+        assert_eq!(state.update(30.0), Some(20.0));
+        assert_eq!(state.update(40.0), Some(30.0));
+    }
+
+    #[test]
+    fn test_try_run() {
+        assert_eq!(try_run(&[10.0, 10.0, 15.0, 20.0, 20.0], 5), Ok(vec![15.0]));
+        assert_eq!(
+            try_run(&[10.0], 5),
+            Err(TaError::InsufficientData { indicator: "the SMA", received: 1, required: 5, note: None })
+        );
+        assert_eq!(
+            try_run(&[10.0], 0),
+            Err(TaError::InvalidParameter { name: "periods", reason: "must be greater than 0".to_string() })
+        );
     }
 
     #[test]
     #[should_panic(expected = "Not enough entries to calculate the SMA. Received 1, but required 5.")]
     fn test_run_not_enough_elements() {
-        run(vec![10.0], 5);
+        run(&[10.0], 5);
     }
 }
\ No newline at end of file
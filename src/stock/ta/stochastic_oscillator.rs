@@ -1,5 +1,9 @@
-/// Calculate the stochasitc oscillator based on a `Vec<(f32, f32, f32)>` of
-/// price data.
+use std::collections::VecDeque;
+
+use crate::stock::ta::TaError;
+
+/// Calculate the stochasitc oscillator based on aligned close, low and high
+/// prices.
 /// 
 /// ### Definition
 /// An indicator -- bounded between [0-100] -- comprised of the most recent
@@ -27,49 +31,186 @@
 /// into a bullish reversal.
 /// 
 /// # Arguments
-/// * `prices` - `Vec<(f32, f32, f32)>` containing prices for a period of time
-///              in the format of `Vec<(close, low, high)>`
+/// * `closes` - `&[f64]` containing close prices for a period of time
+/// * `lows` - `&[f64]` containing low prices for the same period
+/// * `highs` - `&[f64]` containing high prices for the same period
 /// 
 /// ### Example
 /// ```
-/// stochastic_oscillator::run(prices);
-/// stochastic_oscillator::run(prices);
+/// stochastic_oscillator::run(series.get_closes(), series.get_lows(), series.get_highs());
 /// ```
 /// 
 /// #### Resources
 /// - https://www.investopedia.com/terms/s/stochasticoscillator.asp
-pub fn run(prices: Vec<(f32, f32, f32)>) -> Vec<f32> {
-    const PERIOD: usize = 14;
-    if prices.len() < PERIOD { panic!("Not enough entries to calculate stochastic oscillator. Received {}, but required {}.", prices.len(), PERIOD); }
-    let mut oscs: Vec<f32> = Vec::new();
-
-    for i in PERIOD-1..prices.len() {
-        let cur = match prices.get(i) {
-            Some(&v) => v,
-            None => panic!("Could not get entry in `prices`."),
-        };
-        let p = cur.0;
-        let mut low14 = cur.1;
-        let mut high14 = cur.2;
-        for j in i+1-PERIOD..i {
-            let prev = match prices.get(j) {
-                Some(&v) => v,
-                None => panic!("Could not get entry in `prices`."),
-            };
-            if low14 > prev.1 { low14 = prev.1; }
-            if high14 < prev.2 { high14 = prev.2; }
+#[allow(dead_code)]
+pub fn run(closes: &[f64], lows: &[f64], highs: &[f64]) -> Vec<f64> {
+    return run_with(closes, lows, highs, &StochasticParams::default());
+}
+
+/// Parameters used to calculate the stochastic oscillator.
+/// 
+/// - `period`: number of trading sessions used for the high/low range (default 14)
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq)]
+pub struct StochasticParams {
+    period: usize,
+}
+
+#[allow(dead_code)]
+impl StochasticParams {
+    pub fn new(period: usize) -> Self {
+        return Self { period: period };
+    }
+
+    pub fn get_period(&self) -> usize { return self.period; }
+    pub fn set_period(&mut self, period: usize) { self.period = period; }
+}
+
+impl Default for StochasticParams {
+    fn default() -> Self {
+        return Self::new(14);
+    }
+}
+
+/// Calculate the stochastic oscillator based on aligned close, low and high
+/// prices using custom `StochasticParams`. See `run` for details.
+/// 
+/// ### Example
+/// ```
+/// stochastic_oscillator::run_with(series.get_closes(), series.get_lows(), series.get_highs(), &StochasticParams::new(5));
+/// ```
+#[allow(dead_code)]
+pub fn run_with(closes: &[f64], lows: &[f64], highs: &[f64], params: &StochasticParams) -> Vec<f64> {
+    return match try_run_with(closes, lows, highs, params) {
+        Ok(oscs) => oscs,
+        Err(error) => panic!("{}", error),
+    };
+}
+
+/// Calculate the stochastic oscillator based on aligned close, low and high
+/// prices. Returns a `TaError` instead of panicking. See `run` for details.
+#[allow(dead_code)]
+pub fn try_run(closes: &[f64], lows: &[f64], highs: &[f64]) -> Result<Vec<f64>, TaError> {
+    return try_run_with(closes, lows, highs, &StochasticParams::default());
+}
+
+/// Calculate the stochastic oscillator based on aligned close, low and high
+/// prices using custom `StochasticParams`. Returns a `TaError` instead of
+/// panicking.
+/// 
+/// ### Example
+/// ```
+/// match stochastic_oscillator::try_run_with(series.get_closes(), series.get_lows(), series.get_highs(), &StochasticParams::new(5)) {
+///     Ok(oscs) => println!("{:?}", oscs),
+///     Err(error) => println!("{}", error),
+/// };
+/// ```
+#[allow(dead_code)]
+pub fn try_run_with(closes: &[f64], lows: &[f64], highs: &[f64], params: &StochasticParams) -> Result<Vec<f64>, TaError> {
+    let period = params.get_period();
+    if period == 0 { return Err(TaError::InvalidParameter { name: "period", reason: "must be greater than 0".to_string() }); }
+    if closes.len() != lows.len() {
+        return Err(TaError::LengthMismatch { left: "closes", left_len: closes.len(), right: "lows", right_len: lows.len() });
+    }
+    if closes.len() != highs.len() {
+        return Err(TaError::LengthMismatch { left: "closes", left_len: closes.len(), right: "highs", right_len: highs.len() });
+    }
+    if closes.len() < period {
+        return Err(TaError::InsufficientData { indicator: "stochastic oscillator", received: closes.len(), required: period, note: None });
+    }
+    let mut oscs: Vec<f64> = Vec::new();
+
+    for i in period-1..closes.len() {
+        let p = closes[i];
+        let mut low14 = lows[i];
+        let mut high14 = highs[i];
+        for j in i+1-period..i {
+            if low14 > lows[j] { low14 = lows[j]; }
+            if high14 < highs[j] { high14 = highs[j]; }
         }
         let osc = ((p - low14) / (high14 - low14)) * 100.0;
         oscs.push(osc);
     }
-    return oscs;
+    return Ok(oscs);
 }
 
 
+/// Incremental stochastic oscillator. Each `update` runs in amortized O(1)
+/// by tracking the period's low/high with monotonic queues.
+/// 
+/// ### Example
+/// ```
+/// let mut state = StochasticState::new(&StochasticParams::default());
+/// for (close, low, high) in prices {
+///     if let Some(osc) = state.update(close, low, high) { println!("{}", osc); }
+/// }
+/// ```
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct StochasticState {
+    period: usize,
+    count: usize,
+    lows: VecDeque<(usize, f64)>,
+    highs: VecDeque<(usize, f64)>,
+}
+
+#[allow(dead_code)]
+impl StochasticState {
+    pub fn new(params: &StochasticParams) -> Self {
+        return Self {
+            period: params.get_period(),
+            count: 0,
+            lows: VecDeque::new(),
+            highs: VecDeque::new(),
+        };
+    }
+
+    /// Adds the next session and returns the latest %K, or `None` until
+    /// `period` sessions have been received.
+    pub fn update(&mut self, close: f64, low: f64, high: f64) -> Option<f64> {
+        if self.period == 0 { return None; }
+        let i = self.count;
+        self.count += 1;
+
+        // Keep the queues ordered so the front holds the period's low/high
+        while let Some(&(_, v)) = self.lows.back() {
+            if v >= low { self.lows.pop_back(); } else { break; }
+        }
+        self.lows.push_back((i, low));
+        while let Some(&(_, v)) = self.highs.back() {
+            if v <= high { self.highs.pop_back(); } else { break; }
+        }
+        self.highs.push_back((i, high));
+
+        // Drop entries that fell out of the period
+        while let Some(&(j, _)) = self.lows.front() {
+            if j + self.period <= i { self.lows.pop_front(); } else { break; }
+        }
+        while let Some(&(j, _)) = self.highs.front() {
+            if j + self.period <= i { self.highs.pop_front(); } else { break; }
+        }
+
+        if self.count < self.period { return None; }
+        let low_n = match self.lows.front() { Some(&(_, v)) => v, None => low };
+        let high_n = match self.highs.front() { Some(&(_, v)) => v, None => high };
+        return Some(((close - low_n) / (high_n - low_n)) * 100.0);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Splits `(close, low, high)` tuples into columns.
+    fn columns(prices: &[(f64, f64, f64)]) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        return (
+            prices.iter().map(|p| p.0).collect(),
+            prices.iter().map(|p| p.1).collect(),
+            prices.iter().map(|p| p.2).collect(),
+        );
+    }
+
     #[test]
     fn test_run_simple() {
         let prices = vec![
@@ -81,7 +222,8 @@ mod tests {
             (15.0, 10.0, 20.0), (18.0, 13.0, 22.0),
             (15.0, 10.0, 20.0), (18.0, 13.0, 22.0),
         ];
-        assert_eq!(run(prices), vec![66.66667]);
+        let (closes, lows, highs) = columns(&prices);
+        assert_eq!(run(&closes, &lows, &highs), vec![66.66666666666666]);
     }
 
     #[test]
@@ -97,12 +239,48 @@ mod tests {
             (18.0, 10.0, 19.0), (21.0, 13.0, 22.0),
             (12.0, 10.0, 32.0), (14.0, 13.0, 27.0),
         ];
-        assert_eq!(run(prices), vec![36.363636, 36.363636, 50.0, 9.090909, 18.181818]);
+        let (closes, lows, highs) = columns(&prices);
+        assert_eq!(run(&closes, &lows, &highs), vec![36.36363636363637, 36.36363636363637, 50.0, 9.090909090909092, 18.181818181818183]);
+    }
+
+    #[test]
+    fn test_run_with_period() {
+        let prices = vec![(15.0, 10.0, 20.0), (18.0, 13.0, 22.0), (12.0, 11.0, 16.0), (21.0, 15.0, 23.0)];
+        let (closes, lows, highs) = columns(&prices);
+        assert_eq!(run_with(&closes, &lows, &highs, &StochasticParams::new(2)), vec![66.66666666666666, 9.090909090909092, 83.33333333333334]);
+    }
+
+    #[test]
+    fn test_state_matches_batch() {
+        let prices = vec![
+            (15.0, 10.0, 20.0), (18.0, 13.0, 22.0), (18.0, 10.0, 19.0), (21.0, 13.0, 22.0),
+            (12.0, 10.0, 32.0), (14.0, 13.0, 27.0), (15.0, 9.0, 20.0), (18.0, 13.0, 22.0),
+            (18.0, 10.0, 19.0), (21.0, 13.0, 22.0), (12.0, 11.0, 32.0), (14.0, 13.0, 27.0),
+        ];
+        let params = StochasticParams::new(4);
+        let (closes, lows, highs) = columns(&prices);
+        let batch = run_with(&closes, &lows, &highs, &params);
+        let mut state = StochasticState::new(&params);
+        let streamed: Vec<f64> = prices.iter().filter_map(|&(c, l, h)| state.update(c, l, h)).collect();
+        assert_eq!(streamed, batch);
+    }
+
+    #[test]
+    fn test_try_run() {
+        assert_eq!(
+            try_run(&[10.0], &[10.0], &[10.0]),
+            Err(TaError::InsufficientData { indicator: "stochastic oscillator", received: 1, required: 14, note: None })
+        );
+        assert!(try_run_with(&[10.0], &[10.0], &[10.0], &StochasticParams::new(0)).is_err());
+        assert_eq!(
+            try_run(&[10.0, 10.0], &[10.0, 10.0], &[10.0]),
+            Err(TaError::LengthMismatch { left: "closes", left_len: 2, right: "highs", right_len: 1 })
+        );
     }
 
     #[test]
     #[should_panic(expected = "Not enough entries to calculate stochastic oscillator. Received 1, but required 14.")]
     fn test_run_not_enough_elements() {
-        run(vec![(10.0, 10.0, 10.0)]);
+        run(&[10.0], &[10.0], &[10.0]);
     }
 }
\ No newline at end of file
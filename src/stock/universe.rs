@@ -0,0 +1,288 @@
+use std::{collections::{BTreeMap, BTreeSet}, fs::read_dir, path::{Path, PathBuf}, thread};
+
+use crate::stock::Stock;
+use crate::stock::load::{LoadError, LoadOptions};
+
+/// A ticker that could not be loaded and the reason why.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct LoadFailure {
+    ticker: String,
+    error: LoadError,
+}
+
+#[allow(dead_code)]
+impl LoadFailure {
+    pub fn get_ticker(&self) -> String { return self.ticker.to_string(); }
+    pub fn get_error(&self) -> &LoadError { return &self.error; }
+}
+
+/// A set of stocks keyed by ticker, usually loaded from the bundled
+/// `src/assets` directory.
+///
+/// ### Example
+/// ```
+/// let universe = Universe::load(Path::new("./src/assets")).unwrap();
+/// for failure in universe.get_failures() {
+///     println!("{}: {}", failure.get_ticker(), failure.get_error());
+/// }
+/// let aapl = universe.get("AAPL").unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Universe {
+    stocks: BTreeMap<String, Stock>,
+    failures: Vec<LoadFailure>,
+}
+
+#[allow(dead_code)]
+impl Universe {
+    pub fn new() -> Self {
+        return Self { stocks: BTreeMap::new(), failures: Vec::new() };
+    }
+
+    /// Loads every ticker found in `<directory>/historical` and
+    /// `<directory>/contemporary` with the default `LoadOptions`.
+    pub fn load(directory: &Path) -> Result<Self, LoadError> {
+        return Self::load_with(&directory.join("historical"), &directory.join("contemporary"), &LoadOptions::default());
+    }
+
+    /// Like `load`, but keeps a binary cache of the historical files in
+    /// `cache`, so that only files changed since the last load are parsed.
+    ///
+    /// ### Example
+    /// ```
+    /// let universe = Universe::load_cached(Path::new("./src/assets"), Path::new("./target/cache")).unwrap();
+    /// ```
+    pub fn load_cached(directory: &Path, cache: &Path) -> Result<Self, LoadError> {
+        let mut options = LoadOptions::default();
+        options.set_cache_directory(Some(cache.to_path_buf()));
+        return Self::load_with(&directory.join("historical"), &directory.join("contemporary"), &options);
+    }
+
+    /// Loads every ticker with a `<ticker>.csv` file in either directory.
+    /// Files are paired by ticker and tickers are loaded in parallel. A
+    /// ticker whose files are missing or fail to load is reported in
+    /// `get_failures` instead of aborting the whole load.
+    ///
+    /// Returns an error only if a directory cannot be read.
+    ///
+    /// # Arguments
+    ///
+    /// * `historical` - Directory of historical OHLCV files
+    /// * `contemporary` - Directory of fundamentals files
+    /// * `options` - Options used to load the historical files
+    pub fn load_with(historical: &Path, contemporary: &Path, options: &LoadOptions) -> Result<Self, LoadError> {
+        let historical_files = scan(historical)?;
+        let contemporary_files = scan(contemporary)?;
+        let mut tickers: BTreeSet<&String> = historical_files.keys().collect();
+        tickers.extend(contemporary_files.keys());
+        let tickers: Vec<String> = tickers.into_iter().cloned().collect();
+
+        let workers = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        let chunk_size = std::cmp::max(1, tickers.len().div_ceil(workers));
+        let results: Vec<(String, Result<Stock, LoadError>)> = thread::scope(|scope| {
+            let (historical_files, contemporary_files) = (&historical_files, &contemporary_files);
+            let handles: Vec<_> = tickers.chunks(chunk_size).map(|chunk| {
+                scope.spawn(move || {
+                    return chunk.iter()
+                        .map(|ticker| {
+                            let historical_file = file_for(historical_files, historical, ticker);
+                            let contemporary_file = file_for(contemporary_files, contemporary, ticker);
+                            (ticker.to_string(), load_stock(ticker, &historical_file, &contemporary_file, options))
+                        })
+                        .collect::<Vec<_>>();
+                })
+            }).collect();
+            return handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect();
+        });
+
+        let mut universe = Self::new();
+        for (ticker, result) in results {
+            match result {
+                Ok(stock) => universe.insert(stock),
+                Err(error) => universe.failures.push(LoadFailure { ticker: ticker, error: error }),
+            };
+        }
+        return Ok(universe);
+    }
+
+    /// Adds a stock, replacing any stock with the same ticker.
+    pub fn insert(&mut self, stock: Stock) {
+        self.stocks.insert(stock.get_ticker().to_lowercase(), stock);
+    }
+
+    /// Looks up a stock by ticker, ignoring case.
+    pub fn get(&self, ticker: &str) -> Option<&Stock> {
+        return self.stocks.get(&ticker.to_lowercase());
+    }
+
+    pub fn get_mut(&mut self, ticker: &str) -> Option<&mut Stock> {
+        return self.stocks.get_mut(&ticker.to_lowercase());
+    }
+
+    pub fn contains(&self, ticker: &str) -> bool {
+        return self.stocks.contains_key(&ticker.to_lowercase());
+    }
+
+    /// Returns the loaded tickers in alphabetical order.
+    pub fn tickers(&self) -> Vec<String> {
+        return self.stocks.keys().cloned().collect();
+    }
+
+    /// Iterates over the loaded stocks in ticker order.
+    pub fn iter(&self) -> impl Iterator<Item = &Stock> {
+        return self.stocks.values();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.stocks.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.stocks.is_empty();
+    }
+
+    pub fn get_failures(&self) -> &Vec<LoadFailure> {
+        return &self.failures;
+    }
+}
+
+impl Default for Universe {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+/// Returns the `.csv` files in `directory`, keyed by lowercase ticker.
+fn scan(directory: &Path) -> Result<BTreeMap<String, PathBuf>, LoadError> {
+    let path = directory.to_string_lossy().to_string();
+    if !directory.is_dir() {
+        return Err(LoadError::NotFound { path: path });
+    }
+    let entries = match read_dir(directory) {
+        Ok(entries) => entries,
+        Err(error) => return Err(LoadError::Io { path: path, reason: error.to_string() }),
+    };
+    let mut tickers = BTreeMap::new();
+    for entry in entries {
+        let file: PathBuf = match entry {
+            Ok(entry) => entry.path(),
+            Err(error) => return Err(LoadError::Io { path: path, reason: error.to_string() }),
+        };
+        let is_csv = file.extension().map(|ext| ext.eq_ignore_ascii_case("csv")).unwrap_or(false);
+        if let (true, Some(stem)) = (is_csv && file.is_file(), file.file_stem()) {
+            tickers.insert(stem.to_string_lossy().to_lowercase(), file.clone());
+        }
+    }
+    return Ok(tickers);
+}
+
+/// Returns the scanned file for `ticker`, or the path it was expected at.
+fn file_for(files: &BTreeMap<String, PathBuf>, directory: &Path, ticker: &str) -> PathBuf {
+    return match files.get(ticker) {
+        Some(file) => file.clone(),
+        None => directory.join(format!("{}.csv", ticker)),
+    };
+}
+
+fn load_stock(ticker: &str, historical: &Path, contemporary: &Path, options: &LoadOptions) -> Result<Stock, LoadError> {
+    let mut stock = Stock::new(ticker);
+    stock.load_data(contemporary)?;
+    stock.load_historical_data_with(historical, options)?;
+    return Ok(stock);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, remove_dir_all, write};
+
+    /// Creates a fresh `historical`/`contemporary` layout in the temp
+    /// directory.
+    fn fixture(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("stockwatcher-universe-{}-{}", name, std::process::id()));
+        let _ = remove_dir_all(&root);
+        create_dir_all(root.join("historical")).unwrap();
+        create_dir_all(root.join("contemporary")).unwrap();
+        return root;
+    }
+
+    const HISTORICAL: &str = "Date,Open,High,Low,Close,Volume\n1/2/2014 16:00:00,1.0,2.0,0.5,1.5,100\n";
+    const CONTEMPORARY: &str = "name,type,marketcap,high52,low52,pe,eps,summary\nTest Inc.,EQUITY,100,2.0,0.5,10.0,0.15,\"Makes things, sells them\"\n";
+
+    #[test]
+    fn test_load_fixture() {
+        let root = fixture("load");
+        write(root.join("historical/aaa.csv"), HISTORICAL).unwrap();
+        write(root.join("contemporary/aaa.csv"), CONTEMPORARY).unwrap();
+        write(root.join("historical/BBB.csv"), HISTORICAL).unwrap();
+        write(root.join("contemporary/bbb.csv"), CONTEMPORARY).unwrap();
+        // Missing its fundamentals file
+        write(root.join("historical/ccc.csv"), HISTORICAL).unwrap();
+        // Broken historical file
+        write(root.join("historical/ddd.csv"), "Date,Open\n").unwrap();
+        write(root.join("contemporary/ddd.csv"), CONTEMPORARY).unwrap();
+        write(root.join("contemporary/notes.txt"), "ignored").unwrap();
+
+        let universe = Universe::load(&root).unwrap();
+        assert_eq!(universe.tickers(), vec!["aaa", "bbb"]);
+        assert_eq!(universe.len(), 2);
+        assert_eq!(universe.get("AAA").unwrap().get_name(), "Test Inc.");
+        assert_eq!(universe.get("bbb").unwrap().get_historical_data().len(), 1);
+        assert_eq!(universe.contains("ccc"), false);
+
+        let failures: Vec<String> = universe.get_failures().iter().map(|f| f.get_ticker()).collect();
+        assert_eq!(failures, vec!["ccc", "ddd"]);
+        assert_eq!(universe.get_failures()[0].get_error(), &LoadError::NotFound { path: root.join("contemporary/ccc.csv").to_string_lossy().to_string() });
+        assert_eq!(universe.get_failures()[1].get_error(), &LoadError::MissingColumn { column: "high" });
+
+        let names: Vec<String> = universe.iter().map(|s| s.get_ticker()).collect();
+        assert_eq!(names, vec!["aaa", "bbb"]);
+        remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_load_missing_directory() {
+        let root = std::env::temp_dir().join("stockwatcher-universe-missing");
+        let error = Universe::load(&root).unwrap_err();
+        assert_eq!(error, LoadError::NotFound { path: root.join("historical").to_string_lossy().to_string() });
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut universe = Universe::new();
+        assert_eq!(universe.is_empty(), true);
+        universe.insert(Stock::new("KO"));
+        assert_eq!(universe.get("ko").unwrap().get_ticker(), "KO");
+        universe.get_mut("Ko").unwrap().set_name("The Coca-Cola Company");
+        assert_eq!(universe.get("KO").unwrap().get_name(), "The Coca-Cola Company");
+    }
+
+    #[test]
+    fn test_load_bundled_assets() {
+        let universe = Universe::load(Path::new("./src/assets")).unwrap();
+        assert_eq!(universe.get_failures().len(), 0);
+        assert_eq!(universe.tickers(), vec![
+            "aapl", "abnb", "amzn", "cost", "goog", "ko", "msft", "o", "pypl", "sbux", "spy", "sq", "v", "wba", "wmt",
+        ]);
+        assert_eq!(universe.get("aapl").unwrap().get_name(), "Apple Inc.");
+        assert_eq!(universe.get("ko").unwrap().get_historical_data().len(), 15214);
+    }
+
+    #[test]
+    fn test_load_cached() {
+        let root = fixture("cached");
+        write(root.join("historical/aaa.csv"), HISTORICAL).unwrap();
+        write(root.join("contemporary/aaa.csv"), CONTEMPORARY).unwrap();
+        let cache = root.join("cache");
+
+        let universe = Universe::load_cached(&root, &cache).unwrap();
+        assert_eq!(universe.get("aaa").unwrap().get_historical_data().len(), 1);
+        assert_eq!(cache.join("aaa.csv.bin").is_file(), true);
+        let universe = Universe::load_cached(&root, &cache).unwrap();
+        assert_eq!(universe.get("aaa").unwrap().get_historical_data()[0].get_close(), 1.5);
+        remove_dir_all(&root).unwrap();
+    }
+}
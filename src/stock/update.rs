@@ -0,0 +1,382 @@
+use std::{fmt, fs::{self, OpenOptions}, io::Write, path::Path};
+
+use crate::stock::csv;
+use crate::stock::datetime::DateTime;
+use crate::stock::historical::{self, HistoricalData};
+use crate::stock::load::{self, Fundamentals, LoadError, LoadOptions};
+use crate::stock::provider::{CsvProvider, DataProvider, DateRange, Interval, ProviderError};
+
+/// Tickers bundled in `src/assets`.
+pub const TICKERS: [&str; 15] = [
+    "SPY",
+    "AAPL",
+    "MSFT",
+    "AMZN",
+    "PYPL",
+    "SQ",
+    "COST",
+    "WMT",
+    "KO",
+    "O",
+    "WBA",
+    "V",
+    "ABNB",
+    "GOOG",
+    "SBUX",
+];
+
+pub const HISTORICAL_HEADER: &str = "Date,Open,High,Low,Close,Volume";
+pub const CONTEMPORARY_HEADER: &str = "name,type,marketcap,high52,low52,pe,eps,summary";
+
+/// Error returned when updating a ticker's files fails.
+///
+/// - `Load`: the existing historical file could not be loaded
+/// - `Provider`: the provider could not fetch the new data
+/// - `Write`: a file could not be written
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum UpdateError {
+    Load(LoadError),
+    Provider(ProviderError),
+    Write {
+        path: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            UpdateError::Load(error) => write!(f, "{}", error),
+            UpdateError::Provider(error) => write!(f, "{}", error),
+            UpdateError::Write { path, reason } => write!(f, "Could not write file {}: {}", path, reason),
+        };
+    }
+}
+
+impl std::error::Error for UpdateError {}
+
+impl From<LoadError> for UpdateError {
+    fn from(error: LoadError) -> Self {
+        return UpdateError::Load(error);
+    }
+}
+
+impl From<ProviderError> for UpdateError {
+    fn from(error: ProviderError) -> Self {
+        return UpdateError::Provider(error);
+    }
+}
+
+/// Result of updating one ticker.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct TickerUpdate {
+    ticker: String,
+    appended: usize,
+    last_date: Option<DateTime>,
+}
+
+#[allow(dead_code)]
+impl TickerUpdate {
+    pub fn get_ticker(&self) -> String { return self.ticker.to_string(); }
+    /// Number of bars appended to the historical file.
+    pub fn get_appended(&self) -> usize { return self.appended; }
+    /// Date of the last bar in the historical file after the update.
+    pub fn get_last_date(&self) -> Option<&DateTime> { return self.last_date.as_ref(); }
+}
+
+/// Formats a bar as a row of the historical CSV files, e.g.
+/// `06/08/2022 16:00:00,62.9900016784668,63.209999084472656,62.560001373291016,62.88999938964844,10210600.0`.
+pub fn format_historical_row(bar: &HistoricalData) -> String {
+    return format!(
+        "{},{:?},{:?},{:?},{:?},{}.0",
+        bar.get_date().format("%m/%d/%Y %H:%M:%S"),
+        bar.get_open(),
+        bar.get_high(),
+        bar.get_low(),
+        bar.get_close(),
+        bar.get_volume(),
+    );
+}
+
+/// Formats fundamentals as a contemporary CSV file, header included.
+/// Missing fields are left empty.
+pub fn format_fundamentals(fundamentals: &Fundamentals) -> String {
+    let number = |value: Option<f64>| value.map(|v| format!("{:?}", v)).unwrap_or_default();
+    let row = [
+        fundamentals.get_name().unwrap_or_default(),
+        fundamentals.get_security_type().unwrap_or_default(),
+        fundamentals.get_market_cap().map(|v| v.to_string()).unwrap_or_default(),
+        number(fundamentals.get_high52()),
+        number(fundamentals.get_low52()),
+        number(fundamentals.get_pe_ratio()),
+        number(fundamentals.get_eps()),
+        fundamentals.get_summary().unwrap_or_default(),
+    ];
+    let fields: Vec<&str> = row.iter().map(|field| field.as_str()).collect();
+    return format!("{}\n{}\n", CONTEMPORARY_HEADER, csv::format_record(&fields));
+}
+
+/// Appends the bars dated after the last bar of `<directory>/historical/<ticker>.csv`
+/// to that file and rewrites `<directory>/contemporary/<ticker>.csv` with
+/// fresh fundamentals. A missing or empty historical file is written with
+/// the header and every bar the provider has. Both are fetched before
+/// anything is written, so a failed fetch leaves the files untouched.
+/// Rewritten files are replaced in one step, so an interrupted update never
+/// leaves them truncated.
+///
+/// A provider that cannot reach back to the first bar of a new file fails
+/// with `ProviderError::Truncated` rather than writing a short history,
+/// see `IexProvider`.
+///
+/// # Arguments
+///
+/// * `provider` - Source of the new data, e.g. an `IexProvider`
+/// * `ticker` - Ticker to update
+/// * `directory` - Directory laid out like `src/assets`
+///
+/// ### Example
+/// ```
+/// let provider = IexProvider::new(iex::Client::from_env()?);
+/// let result = update::update_ticker(&provider, "aapl", Path::new("./src/assets")).await?;
+/// println!("Appended {} bars", result.get_appended());
+/// ```
+pub async fn update_ticker<P>(provider: &P, ticker: &str, directory: &Path) -> Result<TickerUpdate, UpdateError>
+where P: DataProvider {
+    let files = CsvProvider::new(directory);
+    let historical_file = files.historical_file(ticker);
+    let contemporary_file = files.contemporary_file(ticker);
+
+    // An empty file has no header yet, so treat it like a missing one
+    let existing = if historical_file.is_file() { Some(load::read_file(&historical_file)?) } else { None };
+    let existing = existing.filter(|content| !content.trim().is_empty());
+    let last_date = match &existing {
+        Some(content) => {
+            let (data, _) = load::parse_historical(content, &LoadOptions::default())?;
+            data.iter().map(|bar| bar.get_date().clone()).max()
+        },
+        None => None,
+    };
+
+    let range = match &last_date {
+        Some(date) => DateRange::starting(date.start_of_day().add_days(1)),
+        None => DateRange::all(),
+    };
+    let mut bars = provider.fetch_history(ticker, &range, Interval::Daily).await?;
+    let fundamentals = provider.fetch_fundamentals(ticker).await?;
+
+    historical::sort_by_date(&mut bars);
+    historical::dedup_by_date(&mut bars);
+    if let Some(date) = &last_date {
+        bars.retain(|bar| bar.get_date() > date);
+    }
+
+    let mut appended = "".to_owned();
+    match &existing {
+        None => appended.push_str(&format!("{}\n", HISTORICAL_HEADER)),
+        Some(content) if !content.ends_with('\n') => appended.push('\n'),
+        Some(_) => (),
+    };
+    for bar in &bars {
+        appended.push_str(&format_historical_row(bar));
+        appended.push('\n');
+    }
+    match &existing {
+        None => write(&historical_file, &appended)?,
+        Some(_) if !bars.is_empty() => append(&historical_file, &appended)?,
+        Some(_) => (),
+    };
+    write(&contemporary_file, &format_fundamentals(&fundamentals))?;
+
+    return Ok(TickerUpdate {
+        ticker: ticker.to_string(),
+        appended: bars.len(),
+        last_date: bars.last().map(|bar| bar.get_date().clone()).or(last_date),
+    });
+}
+
+/// Updates each ticker in turn, see `update_ticker`. A ticker that fails
+/// does not stop the others.
+///
+/// ### Example
+/// ```
+/// for (ticker, result) in update::update_all(&provider, &update::TICKERS, Path::new("./src/assets")).await {
+///     match result {
+///         Ok(update) => println!("{}: {} new bars", ticker, update.get_appended()),
+///         Err(error) => println!("{}: {}", ticker, error),
+///     };
+/// }
+/// ```
+pub async fn update_all<P>(provider: &P, tickers: &[&str], directory: &Path) -> Vec<(String, Result<TickerUpdate, UpdateError>)>
+where P: DataProvider {
+    let mut results = Vec::with_capacity(tickers.len());
+    for ticker in tickers {
+        results.push((ticker.to_string(), update_ticker(provider, ticker, directory).await));
+    }
+    return results;
+}
+
+fn write_error(path: &Path, error: std::io::Error) -> UpdateError {
+    return UpdateError::Write { path: path.to_string_lossy().to_string(), reason: error.to_string() };
+}
+
+fn create_parent(path: &Path) -> Result<(), UpdateError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|error| write_error(parent, error))?;
+    }
+    return Ok(());
+}
+
+fn append(path: &Path, content: &str) -> Result<(), UpdateError> {
+    create_parent(path)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path).map_err(|error| write_error(path, error))?;
+    return file.write_all(content.as_bytes()).map_err(|error| write_error(path, error));
+}
+
+/// Writes `content` to a temporary file next to `path`, then renames it
+/// over `path`.
+fn write(path: &Path, content: &str) -> Result<(), UpdateError> {
+    create_parent(path)?;
+    let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let temporary = path.with_file_name(format!(".{}.tmp", name));
+    if let Err(error) = fs::write(&temporary, content) {
+        let _ = fs::remove_file(&temporary);
+        return Err(write_error(&temporary, error));
+    }
+    return fs::rename(&temporary, path).map_err(|error| write_error(path, error));
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, read_to_string, remove_dir_all};
+    use crate::stock::iex::{self, mock::MockServer};
+    use crate::stock::provider::IexProvider;
+
+    fn temp_assets(name: &str) -> std::path::PathBuf {
+        let root = std::env::temp_dir().join(format!("stockwatcher-update-{}-{}", name, std::process::id()));
+        let _ = remove_dir_all(&root);
+        create_dir_all(root.join("historical")).unwrap();
+        create_dir_all(root.join("contemporary")).unwrap();
+        return root;
+    }
+
+    async fn aapl_server() -> MockServer {
+        return MockServer::new()
+            .chart_fixture("aapl", "test/data/iex/aapl_chart_5d.json")
+            .fixture("/stock/aapl/quote", "test/data/iex/aapl_quote.json")
+            .fixture("/stock/aapl/company", "test/data/iex/aapl_company.json")
+            .fixture("/stock/aapl/stats", "test/data/iex/aapl_stats.json")
+            .start().await;
+    }
+
+    #[test]
+    fn test_format_matches_bundled_files() {
+        let content = read_to_string("./src/assets/historical/ko.csv").unwrap();
+        let (data, _) = load::parse_historical(&content, &LoadOptions::default()).unwrap();
+        assert_eq!(content.lines().next(), Some(HISTORICAL_HEADER));
+        assert_eq!(content.lines().last(), Some(format_historical_row(&data[data.len() - 1]).as_str()));
+        assert_eq!(format_historical_row(&HistoricalData::new("6/7/2022 16:00:00", 144.35, 149.0, 144.1, 148.71, 67808150)), "06/07/2022 16:00:00,144.35,149.0,144.1,148.71,67808150.0");
+
+        let fundamentals = load::parse_fundamentals(&read_to_string("./src/assets/contemporary/aapl.csv").unwrap()).unwrap();
+        assert_eq!(load::parse_fundamentals(&format_fundamentals(&fundamentals)), Ok(fundamentals));
+    }
+
+    #[tokio::test]
+    async fn test_update_appends_new_bars() {
+        let root = temp_assets("append");
+        let historical_file = root.join("historical/aapl.csv");
+        fs::write(&historical_file, format!("{}\n06/03/2022 16:00:00,146.89999389648438,147.97000122070312,144.4600067138672,145.3800048828125,88471400.0\n06/06/2022 16:00:00,147.02999877929688,148.57000732421875,144.89999389648438,146.13999938964844,71598400.0", HISTORICAL_HEADER)).unwrap();
+
+        let server = aapl_server().await;
+        let provider = IexProvider::new(iex::Client::with_base_url(&server.url(), "Tsk_test"));
+        let result = update_ticker(&provider, "aapl", &root).await.unwrap();
+        assert_eq!(result.get_appended(), 2);
+        assert_eq!(result.get_last_date().unwrap().to_string(), "6/8/2022 16:00:00");
+
+        let content = read_to_string(&historical_file).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[2].starts_with("06/06/2022 16:00:00,147.02999877929688"), true);
+        assert_eq!(lines[3], "06/07/2022 16:00:00,144.35,149.0,144.1,148.71,67808150.0");
+        assert_eq!(lines[4], "06/08/2022 16:00:00,148.58,149.87,147.46,147.96,53950201.0");
+
+        let fundamentals = load::parse_fundamentals(&read_to_string(root.join("contemporary/aapl.csv")).unwrap()).unwrap();
+        assert_eq!(fundamentals.get_name(), Some("Apple Inc".to_string()));
+        assert_eq!(fundamentals.get_market_cap(), Some(2394788219520));
+        assert_eq!(fundamentals.get_summary().map(|s| s.starts_with("Apple Inc. designs, manufactures")), Some(true));
+
+        // A second run has nothing new to append
+        let result = update_ticker(&provider, "aapl", &root).await.unwrap();
+        assert_eq!(result.get_appended(), 0);
+        assert_eq!(read_to_string(&historical_file).unwrap(), content);
+
+        remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_update_empty_historical_file() {
+        let root = temp_assets("empty");
+        let historical_file = root.join("historical/aapl.csv");
+        fs::write(&historical_file, "").unwrap();
+        fs::write(root.join("contemporary/aapl.csv"), "stale").unwrap();
+
+        let server = aapl_server().await;
+        let provider = IexProvider::new(iex::Client::with_base_url(&server.url(), "Tsk_test"));
+        let result = update_ticker(&provider, "aapl", &root).await.unwrap();
+        assert_eq!(result.get_appended(), 5);
+
+        let content = read_to_string(&historical_file).unwrap();
+        assert_eq!(content.lines().next(), Some(HISTORICAL_HEADER));
+        let (data, _) = load::parse_historical(&content, &LoadOptions::default()).unwrap();
+        assert_eq!(data.len(), 5);
+
+        // The contemporary file is replaced, leaving no temporary file behind
+        let fundamentals = load::parse_fundamentals(&read_to_string(root.join("contemporary/aapl.csv")).unwrap()).unwrap();
+        assert_eq!(fundamentals.get_name(), Some("Apple Inc".to_string()));
+        assert_eq!(fs::read_dir(root.join("contemporary")).unwrap().count(), 1);
+
+        remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_update_truncated_history() {
+        let root = temp_assets("truncated");
+        let first = DateTime::now().start_of_day().sub_years(iex::MAX_HISTORY_YEARS);
+        let chart = format!("[{{\"date\": \"{}\", \"open\": 1, \"high\": 1, \"low\": 1, \"close\": 1, \"volume\": 1}}]", first.format("%Y-%m-%d"));
+        let server = MockServer::new().route("/stock/ko/chart/max", 200, &chart).start().await;
+        let provider = IexProvider::new(iex::Client::with_base_url(&server.url(), "Tsk_test"));
+        match update_ticker(&provider, "ko", &root).await {
+            Err(UpdateError::Provider(ProviderError::Truncated { .. })) => (),
+            other => panic!("unexpected result {:?}", other),
+        };
+        assert_eq!(root.join("historical/ko.csv").exists(), false);
+        remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_update_all() {
+        let root = temp_assets("all");
+        let server = aapl_server().await;
+        let provider = IexProvider::new(iex::Client::with_base_url(&server.url(), "Tsk_test"));
+        let results = update_all(&provider, &["AAPL", "ZZZZ"], &root).await;
+
+        assert_eq!(results[0].0, "AAPL");
+        assert_eq!(results[0].1.as_ref().unwrap().get_appended(), 5);
+        let (data, report) = load::parse_historical(&read_to_string(root.join("historical/aapl.csv")).unwrap(), &LoadOptions::default()).unwrap();
+        assert_eq!(data.len(), 5);
+        assert_eq!(report.get_skipped().len(), 0);
+
+        match &results[1].1 {
+            Err(UpdateError::Provider(ProviderError::NotFound { .. })) => (),
+            other => panic!("unexpected result {:?}", other),
+        };
+        assert_eq!(root.join("historical/zzzz.csv").exists(), false);
+
+        remove_dir_all(&root).unwrap();
+    }
+}